@@ -0,0 +1,492 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, RenderAssetUsages};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use quick_xml::Reader as XmlReader;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+
+/// Error raised while loading a COLLADA (`.dae`) file
+#[derive(Debug)]
+pub enum ColladaError {
+    Io(std::io::Error),
+    Xml(quick_xml::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ColladaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColladaError::Io(e) => write!(f, "Failed to read COLLADA file: {}", e),
+            ColladaError::Xml(e) => write!(f, "Error parsing COLLADA XML: {}", e),
+            ColladaError::Invalid(message) => write!(f, "Invalid COLLADA file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ColladaError {}
+
+impl From<std::io::Error> for ColladaError {
+    fn from(e: std::io::Error) -> Self {
+        ColladaError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for ColladaError {
+    fn from(e: quick_xml::Error) -> Self {
+        ColladaError::Xml(e)
+    }
+}
+
+/// Loads the geometry of `.dae` files as a single [`Mesh`].
+///
+/// Vertices are expressed in meters with Z up, like STL and OBJ meshes referenced from SDF.
+/// Materials, textures and animations are ignored.
+#[derive(Default)]
+pub struct ColladaLoader;
+
+impl AssetLoader for ColladaLoader {
+    type Asset = Mesh;
+    type Settings = ();
+    type Error = ColladaError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Mesh, ColladaError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        load_collada(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dae"]
+    }
+}
+
+/// Minimal XML element tree, enough to walk a COLLADA document
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn from_start(e: &BytesStart) -> Self {
+        let attributes = e
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .map(|attr| {
+                (
+                    String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned(),
+                    String::from_utf8_lossy(&attr.value).into_owned(),
+                )
+            })
+            .collect();
+        Element {
+            name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn floats(&self) -> Vec<f32> {
+        self.text
+            .split_whitespace()
+            .filter_map(|s| s.parse::<f32>().ok())
+            .collect()
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        self.text
+            .split_whitespace()
+            .filter_map(|s| s.parse::<usize>().ok())
+            .collect()
+    }
+}
+
+fn parse_tree(bytes: &[u8]) -> Result<Element, ColladaError> {
+    let mut reader = XmlReader::from_reader(bytes);
+    reader.trim_text(true);
+
+    let mut stack = vec![Element::default()];
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) => stack.push(Element::from_start(e)),
+            Event::Empty(ref e) => {
+                let element = Element::from_start(e);
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::Text(e) => {
+                if let Some(element) = stack.last_mut() {
+                    let text = e.unescape()?;
+                    element.text.push_str(&text);
+                }
+            }
+            Event::End(_) => {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| ColladaError::Invalid("unbalanced tags".to_string()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Err(ColladaError::Invalid("unbalanced tags".to_string())),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let document = stack
+        .pop()
+        .filter(|_| stack.is_empty())
+        .ok_or_else(|| ColladaError::Invalid("unclosed tags".to_string()))?;
+    document
+        .children
+        .into_iter()
+        .find(|element| element.name == "COLLADA")
+        .ok_or_else(|| ColladaError::Invalid("missing <COLLADA> root".to_string()))
+}
+
+/// Triangle soup: three positions, and optionally normals, per triangle
+#[derive(Default)]
+struct Triangles {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+}
+
+/// Reads every `<triangles>`, `<polylist>` and `<polygons>` primitive of a `<geometry>`
+fn read_geometry(geometry: &Element) -> Triangles {
+    let mut triangles = Triangles::default();
+    let Some(mesh) = geometry.child("mesh") else {
+        return triangles;
+    };
+
+    // Sources are arrays of 3-vectors, possibly with a larger stride
+    let mut sources = HashMap::new();
+    for source in mesh.children_named("source") {
+        let (Some(id), Some(array)) = (source.attribute("id"), source.child("float_array")) else {
+            continue;
+        };
+        let stride = source
+            .child("technique_common")
+            .and_then(|technique| technique.child("accessor"))
+            .and_then(|accessor| accessor.attribute("stride"))
+            .and_then(|stride| stride.parse::<usize>().ok())
+            .unwrap_or(3);
+        if stride < 3 {
+            continue;
+        }
+        let values: Vec<Vec3> = array
+            .floats()
+            .chunks_exact(stride)
+            .map(|v| Vec3::new(v[0], v[1], v[2]))
+            .collect();
+        sources.insert(id.to_string(), values);
+    }
+
+    // <vertices> bundles the POSITION source, and sometimes the NORMAL source
+    let mut vertices = HashMap::new();
+    for element in mesh.children_named("vertices") {
+        let Some(id) = element.attribute("id") else {
+            continue;
+        };
+        let mut position = None;
+        let mut normal = None;
+        for input in element.children_named("input") {
+            let source = input.attribute("source").map(|s| s.trim_start_matches('#'));
+            match input.attribute("semantic") {
+                Some("POSITION") => position = source,
+                Some("NORMAL") => normal = source,
+                _ => {}
+            }
+        }
+        if let Some(position) = position {
+            vertices.insert(id.to_string(), (position, normal));
+        }
+    }
+
+    for primitive in &mesh.children {
+        if !matches!(
+            primitive.name.as_str(),
+            "triangles" | "polylist" | "polygons"
+        ) {
+            continue;
+        }
+
+        let mut position_source = None;
+        let mut normal_source = None;
+        let mut position_offset = 0;
+        let mut normal_offset = 0;
+        let mut stride = 1;
+        for input in primitive.children_named("input") {
+            let offset = input
+                .attribute("offset")
+                .and_then(|offset| offset.parse::<usize>().ok())
+                .unwrap_or(0);
+            stride = stride.max(offset + 1);
+            let source = input.attribute("source").map(|s| s.trim_start_matches('#'));
+            match (input.attribute("semantic"), source) {
+                (Some("VERTEX"), Some(source)) => {
+                    if let Some(&(position, normal)) = vertices.get(source) {
+                        position_source = sources.get(position);
+                        position_offset = offset;
+                        if normal_source.is_none() {
+                            normal_source = normal.and_then(|normal| sources.get(normal));
+                            normal_offset = offset;
+                        }
+                    }
+                }
+                (Some("NORMAL"), Some(source)) => {
+                    normal_source = sources.get(source);
+                    normal_offset = offset;
+                }
+                _ => {}
+            }
+        }
+        let Some(positions) = position_source else {
+            continue;
+        };
+
+        // Polygon sizes: all triangles, a <vcount> list, or one <p> per polygon
+        let polygons: Vec<Vec<usize>> = match primitive.name.as_str() {
+            "triangles" => primitive
+                .children_named("p")
+                .flat_map(|p| p.indices())
+                .collect::<Vec<_>>()
+                .chunks_exact(3 * stride)
+                .map(<[usize]>::to_vec)
+                .collect(),
+            "polylist" => {
+                let indices: Vec<usize> = primitive
+                    .children_named("p")
+                    .flat_map(|p| p.indices())
+                    .collect();
+                let counts = primitive
+                    .child("vcount")
+                    .map(Element::indices)
+                    .unwrap_or_default();
+                let mut polygons = Vec::new();
+                let mut start = 0;
+                for count in counts {
+                    let end = start + count * stride;
+                    if end > indices.len() {
+                        break;
+                    }
+                    polygons.push(indices[start..end].to_vec());
+                    start = end;
+                }
+                polygons
+            }
+            _ => primitive
+                .children_named("p")
+                .map(Element::indices)
+                .collect(),
+        };
+
+        for polygon in polygons {
+            let corners: Vec<&[usize]> = polygon.chunks_exact(stride).collect();
+            let corner = |c: &[usize]| {
+                let position = positions.get(c[position_offset]).copied();
+                let normal =
+                    normal_source.and_then(|normals| normals.get(c[normal_offset]).copied());
+                (position, normal)
+            };
+            // Triangulate as a fan
+            for i in 1..corners.len().saturating_sub(1) {
+                let triangle = [
+                    corner(corners[0]),
+                    corner(corners[i]),
+                    corner(corners[i + 1]),
+                ];
+                if triangle.iter().any(|(position, _)| position.is_none()) {
+                    continue;
+                }
+                let has_normals = triangle.iter().all(|(_, normal)| normal.is_some());
+                for (position, normal) in triangle {
+                    triangles.positions.push(position.unwrap_or_default());
+                    if has_normals {
+                        triangles.normals.push(normal.unwrap_or_default());
+                    }
+                }
+                if !has_normals {
+                    let [a, b, c] = [
+                        triangles.positions[triangles.positions.len() - 3],
+                        triangles.positions[triangles.positions.len() - 2],
+                        triangles.positions[triangles.positions.len() - 1],
+                    ];
+                    let normal = (b - a).cross(c - a).normalize_or_zero();
+                    triangles.normals.extend([normal; 3]);
+                }
+            }
+        }
+    }
+
+    triangles
+}
+
+/// Local transform of a `<node>`, from its transform elements in document order
+fn node_transform(node: &Element) -> Mat4 {
+    let mut transform = Mat4::IDENTITY;
+    for element in &node.children {
+        let v = element.floats();
+        let local = match (element.name.as_str(), v.len()) {
+            // COLLADA matrices are row-major
+            ("matrix", 16) => Mat4::from_cols_slice(&v).transpose(),
+            ("translate", 3) => Mat4::from_translation(Vec3::new(v[0], v[1], v[2])),
+            ("rotate", 4) => Mat4::from_axis_angle(
+                Vec3::new(v[0], v[1], v[2]).normalize_or_zero(),
+                v[3].to_radians(),
+            ),
+            ("scale", 3) => Mat4::from_scale(Vec3::new(v[0], v[1], v[2])),
+            _ => continue,
+        };
+        transform *= local;
+    }
+    transform
+}
+
+/// Walks a node tree and collects every instanced geometry with its transform
+fn collect_instances<'a>(
+    node: &'a Element,
+    parent: Mat4,
+    library_nodes: &HashMap<&str, &'a Element>,
+    depth: usize,
+    instances: &mut Vec<(&'a str, Mat4)>,
+) {
+    // Guards against instance_node cycles
+    if depth > 64 {
+        return;
+    }
+    let transform = parent * node_transform(node);
+    for child in &node.children {
+        match child.name.as_str() {
+            "instance_geometry" => {
+                if let Some(url) = child.attribute("url") {
+                    instances.push((url.trim_start_matches('#'), transform));
+                }
+            }
+            "instance_node" => {
+                let target = child
+                    .attribute("url")
+                    .map(|url| url.trim_start_matches('#'));
+                if let Some(target) = target.and_then(|target| library_nodes.get(target)) {
+                    collect_instances(target, transform, library_nodes, depth + 1, instances);
+                }
+            }
+            "node" => collect_instances(child, transform, library_nodes, depth + 1, instances),
+            _ => {}
+        }
+    }
+}
+
+/// Parses a COLLADA document into a single indexed triangle mesh, in meters with Z up
+pub fn load_collada(bytes: &[u8]) -> Result<Mesh, ColladaError> {
+    let root = parse_tree(bytes)?;
+
+    // Bring the document into meters and Z up
+    let asset = root.child("asset");
+    let meter = asset
+        .and_then(|asset| asset.child("unit"))
+        .and_then(|unit| unit.attribute("meter"))
+        .and_then(|meter| meter.parse::<f32>().ok())
+        .unwrap_or(1.0);
+    let up_axis = match asset
+        .and_then(|asset| asset.child("up_axis"))
+        .map(|up| up.text.trim())
+    {
+        Some("X_UP") => Mat4::from_rotation_y(-FRAC_PI_2),
+        Some("Z_UP") => Mat4::IDENTITY,
+        _ => Mat4::from_rotation_x(FRAC_PI_2), // Y_UP is the COLLADA default
+    };
+    let document_transform = up_axis * Mat4::from_scale(Vec3::splat(meter));
+
+    let geometries: HashMap<&str, &Element> = root
+        .children_named("library_geometries")
+        .flat_map(|library| library.children_named("geometry"))
+        .filter_map(|geometry| geometry.attribute("id").map(|id| (id, geometry)))
+        .collect();
+    let library_nodes: HashMap<&str, &Element> = root
+        .children_named("library_nodes")
+        .flat_map(|library| library.children_named("node"))
+        .filter_map(|node| node.attribute("id").map(|id| (id, node)))
+        .collect();
+
+    // Instance geometries through the visual scene, or take them all as-is without one
+    let scene_url = root
+        .child("scene")
+        .and_then(|scene| scene.child("instance_visual_scene"))
+        .and_then(|instance| instance.attribute("url"))
+        .map(|url| url.trim_start_matches('#'));
+    let visual_scenes: Vec<&Element> = root
+        .children_named("library_visual_scenes")
+        .flat_map(|library| library.children_named("visual_scene"))
+        .collect();
+    let visual_scene = visual_scenes
+        .iter()
+        .find(|scene| scene_url.is_some() && scene.attribute("id") == scene_url)
+        .or(visual_scenes.first());
+    let mut instances = Vec::new();
+    match visual_scene {
+        Some(scene) => {
+            for node in scene.children_named("node") {
+                collect_instances(node, document_transform, &library_nodes, 0, &mut instances)
+            }
+        }
+        None => instances.extend(geometries.keys().map(|&id| (id, document_transform))),
+    }
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    for (id, transform) in instances {
+        let Some(geometry) = geometries.get(id) else {
+            continue;
+        };
+        let triangles = read_geometry(geometry);
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        // Mirroring transforms flip the winding
+        let mirrored = transform.determinant() < 0.0;
+        for (i, chunk) in triangles.positions.chunks_exact(3).enumerate() {
+            let order = if mirrored { [0, 2, 1] } else { [0, 1, 2] };
+            for k in order {
+                positions.push(transform.transform_point3(chunk[k]).to_array());
+                let normal = (normal_matrix * triangles.normals[3 * i + k]).normalize_or_zero();
+                normals.push(normal.to_array());
+            }
+        }
+    }
+    if positions.is_empty() {
+        return Err(ColladaError::Invalid("no triangles found".to_string()));
+    }
+
+    let indices = (0..positions.len() as u32).collect();
+    Ok(Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices)))
+}
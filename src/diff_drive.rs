@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy::reflect::Reflect;
+use bevy_rapier3d::prelude::*;
+
+use crate::turtlebot4::Wheel;
+
+// iRobot Create 3 base limits, used as defaults
+const MAX_LINEAR_VELOCITY: f32 = 0.306; // m/s
+const MAX_ANGULAR_VELOCITY: f32 = 1.9; // rad/s
+const MAX_LINEAR_ACCELERATION: f32 = 0.9; // m/s^2
+const MAX_ANGULAR_ACCELERATION: f32 = 4.0; // rad/s^2
+const MAX_WHEEL_TORQUE: f32 = 1.0; // N·m per wheel
+const CMD_VEL_TIMEOUT: f32 = 0.5; // seconds without a command before the base stops
+/// Gain of the wheel velocity motors
+const WHEEL_MOTOR_FACTOR: f32 = 100.0;
+
+/// ROS `geometry_msgs/Twist`, expressed in the ROS robot frame (x forward, y left, z up)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub struct Twist {
+    /// Linear velocity (m/s)
+    pub linear: Vec3,
+    /// Angular velocity (rad/s)
+    pub angular: Vec3,
+}
+
+/// Velocity command for a mobile base, the equivalent of publishing on `cmd_vel`.
+///
+/// A differential drive only follows `linear.x` and `angular.z`; the other components are ignored.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CmdVel {
+    /// Chassis entity carrying the [`DiffDriveController`]
+    pub robot: Entity,
+    /// Commanded velocity
+    pub twist: Twist,
+}
+
+/// Differential-drive controller for a chassis carrying a left and a right [`Wheel`].
+///
+/// The wheels must be attached to the chassis with an [`ImpulseJoint`] whose angular X axis is
+/// the axle, oriented so that a positive joint velocity rolls the robot forward (+X).
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct DiffDriveController {
+    /// Commanded forward velocity (m/s), set from [`CmdVel`]
+    pub linear_velocity: f32,
+    /// Commanded yaw rate, counter-clockwise about +Y (rad/s), set from [`CmdVel`]
+    pub angular_velocity: f32,
+    /// Time without a [`CmdVel`] after which the command is zeroed (seconds)
+    pub cmd_vel_timeout: f32,
+    /// Time since the last [`CmdVel`] (seconds) - READ ONLY
+    pub cmd_vel_age: f32,
+    /// Wheel radius (meters)
+    pub wheel_radius: f32,
+    /// Distance between the two wheel contact points (meters)
+    pub track_width: f32,
+    /// Forward velocity limit (m/s)
+    pub max_linear_velocity: f32,
+    /// Yaw rate limit (rad/s)
+    pub max_angular_velocity: f32,
+    /// Forward acceleration limit (m/s^2)
+    pub max_linear_acceleration: f32,
+    /// Yaw acceleration limit (rad/s^2)
+    pub max_angular_acceleration: f32,
+    /// Torque limit of each wheel motor (N·m)
+    pub max_wheel_torque: f32,
+    /// Forward velocity sent to the wheels after limiting (m/s) - READ ONLY
+    pub current_linear_velocity: f32,
+    /// Yaw rate sent to the wheels after limiting (rad/s) - READ ONLY
+    pub current_angular_velocity: f32,
+}
+
+impl DiffDriveController {
+    pub fn new(wheel_radius: f32, track_width: f32) -> Self {
+        DiffDriveController {
+            linear_velocity: 0.0,
+            angular_velocity: 0.0,
+            cmd_vel_timeout: CMD_VEL_TIMEOUT,
+            cmd_vel_age: 0.0,
+            wheel_radius,
+            track_width,
+            max_linear_velocity: MAX_LINEAR_VELOCITY,
+            max_angular_velocity: MAX_ANGULAR_VELOCITY,
+            max_linear_acceleration: MAX_LINEAR_ACCELERATION,
+            max_angular_acceleration: MAX_ANGULAR_ACCELERATION,
+            max_wheel_torque: MAX_WHEEL_TORQUE,
+            current_linear_velocity: 0.0,
+            current_angular_velocity: 0.0,
+        }
+    }
+
+    /// Move the current velocities towards the clamped command without exceeding the
+    /// acceleration limits over `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        let linear = self
+            .linear_velocity
+            .clamp(-self.max_linear_velocity, self.max_linear_velocity);
+        let angular = self
+            .angular_velocity
+            .clamp(-self.max_angular_velocity, self.max_angular_velocity);
+
+        self.current_linear_velocity = approach(
+            self.current_linear_velocity,
+            linear,
+            self.max_linear_acceleration * dt,
+        );
+        self.current_angular_velocity = approach(
+            self.current_angular_velocity,
+            angular,
+            self.max_angular_acceleration * dt,
+        );
+    }
+
+    /// Left and right wheel angular velocities (rad/s) for the current velocities
+    pub fn wheel_velocities(&self) -> (f32, f32) {
+        let half_track = 0.5 * self.track_width;
+        let left = (self.current_linear_velocity - self.current_angular_velocity * half_track)
+            / self.wheel_radius;
+        let right = (self.current_linear_velocity + self.current_angular_velocity * half_track)
+            / self.wheel_radius;
+        (left, right)
+    }
+}
+
+fn approach(current: f32, target: f32, max_step: f32) -> f32 {
+    current + (target - current).clamp(-max_step, max_step)
+}
+
+/// Feed [`CmdVel`] events into their controllers and stop any base whose command has gone stale
+pub fn cmd_vel_system(
+    time: Res<Time>,
+    mut cmd_vel_events: EventReader<CmdVel>,
+    mut controllers: Query<&mut DiffDriveController>,
+) {
+    for mut controller in controllers.iter_mut() {
+        controller.cmd_vel_age += time.delta_secs();
+    }
+
+    for cmd_vel in cmd_vel_events.read() {
+        let Ok(mut controller) = controllers.get_mut(cmd_vel.robot) else {
+            warn_once!(
+                "CmdVel sent to {} which has no DiffDriveController",
+                cmd_vel.robot
+            );
+            continue;
+        };
+        controller.linear_velocity = cmd_vel.twist.linear.x;
+        controller.angular_velocity = cmd_vel.twist.angular.z;
+        controller.cmd_vel_age = 0.0;
+    }
+
+    // Watchdog, as on the real base
+    for mut controller in controllers.iter_mut() {
+        if controller.cmd_vel_age > controller.cmd_vel_timeout {
+            controller.linear_velocity = 0.0;
+            controller.angular_velocity = 0.0;
+        }
+    }
+}
+
+/// Apply every controller's wheel velocities to the velocity motors of its wheel joints
+pub fn diff_drive_system(
+    time: Res<Time>,
+    mut controllers: Query<&mut DiffDriveController>,
+    mut wheels: Query<(&Wheel, &mut ImpulseJoint)>,
+) {
+    let dt = time.delta_secs();
+    for mut controller in controllers.iter_mut() {
+        controller.update(dt);
+    }
+
+    for (wheel, mut joint) in wheels.iter_mut() {
+        let Ok(controller) = controllers.get(joint.parent) else {
+            continue;
+        };
+        let (left, right) = controller.wheel_velocities();
+        let target = match wheel {
+            Wheel::Left => left,
+            Wheel::Right => right,
+        };
+
+        // Only touch the joint when the motor changes so Rapier is not re-synced every frame
+        let unchanged = joint
+            .data
+            .as_ref()
+            .motor(JointAxis::AngX)
+            .is_some_and(|motor| {
+                motor.target_vel == target && motor.max_force == controller.max_wheel_torque
+            });
+        if unchanged {
+            continue;
+        }
+        joint
+            .data
+            .as_mut()
+            .set_motor_velocity(JointAxis::AngX, target, WHEEL_MOTOR_FACTOR)
+            .set_motor_max_force(JointAxis::AngX, controller.max_wheel_torque);
+    }
+}
+
+/// Plugin driving differential-drive robots through their wheel joints
+pub struct DiffDrivePlugin;
+
+impl Plugin for DiffDrivePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CmdVel>()
+            .add_systems(Update, (cmd_vel_system, diff_drive_system).chain())
+            .register_type::<DiffDriveController>();
+    }
+}
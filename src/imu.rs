@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+use bevy::reflect::Reflect;
+use bevy_rapier3d::plugin::PhysicsSet;
+use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::utils::iso_to_transform;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::time::Duration;
+
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+// Create 3 base IMU, roughly an ICM-42670 class MEMS part
+const IMU_SAMPLE_RATE: f32 = 100.0; // Hz
+const IMU_BANDWIDTH: f32 = 25.0; // Hz, low-pass filter of the 100 Hz output
+const ACCEL_NOISE_STDDEV: f32 = 0.02; // m/s^2
+const GYRO_NOISE_STDDEV: f32 = 0.002; // rad/s
+const ORIENTATION_NOISE_STDDEV: f32 = 0.001; // rad
+const ACCEL_BIAS_RANDOM_WALK: f32 = 4.0e-4; // m/s^2 per sqrt(s)
+const GYRO_BIAS_RANDOM_WALK: f32 = 4.0e-5; // rad/s per sqrt(s)
+const ACCEL_LIMIT: f32 = 16.0 * 9.81; // ±16 g
+const GYRO_LIMIT: f32 = 2000.0 * std::f32::consts::PI / 180.0; // ±2000 °/s
+const STANDARD_GRAVITY: f32 = 9.81;
+
+/// ROS `sensor_msgs/Imu` message format, in the ROS axes of the sensor (x forward, y left, z up)
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct Imu {
+    /// Orientation of the sensor relative to the world (z up)
+    pub orientation: Quat,
+    /// Row-major 3x3 covariance of the orientation about x, y and z
+    pub orientation_covariance: [f32; 9],
+    /// Angular velocity (rad/s)
+    pub angular_velocity: Vec3,
+    /// Row-major 3x3 covariance of the angular velocity
+    pub angular_velocity_covariance: [f32; 9],
+    /// Specific force including the reaction to gravity (m/s^2)
+    pub linear_acceleration: Vec3,
+    /// Row-major 3x3 covariance of the linear acceleration
+    pub linear_acceleration_covariance: [f32; 9],
+}
+
+/// Event emitted for every IMU sample
+#[allow(dead_code)]
+#[derive(Event, Debug, Clone)]
+pub struct ImuEvent {
+    /// Sensor entity that produced the sample
+    pub sensor: Entity,
+    /// Elapsed simulation time of the sample (seconds)
+    pub stamp: f32,
+    /// Frame the sample is expressed in
+    pub frame_id: String,
+    /// The sample itself
+    pub imu: Imu,
+}
+
+/// Most recent sample of an IMU, kept on the sensor entity
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct LatestImu {
+    /// Elapsed simulation time of the sample (seconds)
+    pub stamp: f32,
+    /// Frame the sample is expressed in
+    pub frame_id: String,
+    /// The sample itself
+    pub imu: Imu,
+}
+
+/// Error sources applied to every IMU sample, per sensor axis
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct ImuNoise {
+    /// White noise on the linear acceleration (m/s^2)
+    pub accel_stddev: Vec3,
+    /// White noise on the angular velocity (rad/s)
+    pub gyro_stddev: Vec3,
+    /// White noise on roll, pitch and yaw (rad)
+    pub orientation_stddev: Vec3,
+    /// Accelerometer bias random walk (m/s^2 per sqrt(s))
+    pub accel_bias_random_walk: Vec3,
+    /// Gyroscope bias random walk (rad/s per sqrt(s))
+    pub gyro_bias_random_walk: Vec3,
+}
+
+impl ImuNoise {
+    /// A perfect IMU
+    #[allow(dead_code)]
+    pub const NONE: ImuNoise = ImuNoise {
+        accel_stddev: Vec3::ZERO,
+        gyro_stddev: Vec3::ZERO,
+        orientation_stddev: Vec3::ZERO,
+        accel_bias_random_walk: Vec3::ZERO,
+        gyro_bias_random_walk: Vec3::ZERO,
+    };
+}
+
+impl Default for ImuNoise {
+    fn default() -> Self {
+        ImuNoise {
+            accel_stddev: Vec3::splat(ACCEL_NOISE_STDDEV),
+            gyro_stddev: Vec3::splat(GYRO_NOISE_STDDEV),
+            orientation_stddev: Vec3::splat(ORIENTATION_NOISE_STDDEV),
+            accel_bias_random_walk: Vec3::splat(ACCEL_BIAS_RANDOM_WALK),
+            gyro_bias_random_walk: Vec3::splat(GYRO_BIAS_RANDOM_WALK),
+        }
+    }
+}
+
+/// IMU sensor component; mount it on a child entity of a rigid body
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct ImuSensor {
+    /// Sample rate (Hz)
+    pub sample_rate: f32,
+    /// Timer for sample rate control
+    #[reflect(ignore)]
+    pub sample_timer: Timer,
+    /// Error model applied to every sample
+    pub noise: ImuNoise,
+    /// Accelerometer saturation, per axis (m/s^2)
+    pub accel_limit: f32,
+    /// Gyroscope saturation, per axis (rad/s)
+    pub gyro_limit: f32,
+    /// Current accelerometer bias (m/s^2) - READ ONLY
+    pub accel_bias: Vec3,
+    /// Current gyroscope bias (rad/s) - READ ONLY
+    pub gyro_bias: Vec3,
+    /// Frame id stamped on published samples
+    pub frame_id: String,
+    /// Cut-off of the low-pass filter applied before sampling (Hz), 0 for no filter
+    pub bandwidth: f32,
+    /// Filtered specific force in the sensor axes (m/s^2) - READ ONLY
+    pub filtered_specific_force: Vec3,
+    /// Filtered angular velocity in the sensor axes (rad/s) - READ ONLY
+    pub filtered_angular_velocity: Vec3,
+    /// World velocity of the sensor at the previous physics step
+    #[reflect(ignore)]
+    pub last_velocity: Option<Vec3>,
+}
+
+impl Default for ImuSensor {
+    fn default() -> Self {
+        ImuSensor {
+            sample_rate: IMU_SAMPLE_RATE,
+            sample_timer: Timer::from_seconds(1.0 / IMU_SAMPLE_RATE, TimerMode::Repeating),
+            noise: ImuNoise::default(),
+            accel_limit: ACCEL_LIMIT,
+            gyro_limit: GYRO_LIMIT,
+            accel_bias: Vec3::ZERO,
+            gyro_bias: Vec3::ZERO,
+            frame_id: "imu_link".to_string(),
+            bandwidth: IMU_BANDWIDTH,
+            filtered_specific_force: Vec3::ZERO,
+            filtered_angular_velocity: Vec3::ZERO,
+            last_velocity: None,
+        }
+    }
+}
+
+/// Rotation from Bevy axes (y up, -z left) to ROS axes (z up, y left)
+fn bevy_to_ros() -> Quat {
+    Quat::from_rotation_x(FRAC_PI_2)
+}
+
+/// Per-axis Gaussian sample
+fn gaussian(rng: &mut impl Rng, stddev: Vec3) -> Vec3 {
+    let mut sample = |stddev: f32| {
+        if stddev > 0.0 {
+            Normal::new(0.0, stddev).unwrap().sample(rng)
+        } else {
+            0.0
+        }
+    };
+    Vec3::new(sample(stddev.x), sample(stddev.y), sample(stddev.z))
+}
+
+fn diagonal_covariance(stddev: Vec3) -> [f32; 9] {
+    let variance = stddev * stddev;
+    [
+        variance.x, 0.0, 0.0, //
+        0.0, variance.y, 0.0, //
+        0.0, 0.0, variance.z,
+    ]
+}
+
+/// System that samples every IMU from the motion of the rigid body it is mounted on.
+///
+/// Runs after every physics step. Like a MEMS part, the sensor low-pass filters its signal at the
+/// physics rate and samples the filter output at its own rate, at most once per physics step.
+pub fn imu_sampling_system(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    rapier_context: ReadRapierContext,
+    rapier_config: Query<&RapierConfiguration>,
+    mut imu_query: Query<(Entity, &mut ImuSensor, &Transform, &ChildOf)>,
+    mut imu_events: EventWriter<ImuEvent>,
+) {
+    let Ok(rapier_context) = rapier_context.single() else {
+        return;
+    };
+    let body_set = &rapier_context.rigidbody_set;
+    let gravity = rapier_config
+        .single()
+        .map_or(Vec3::NEG_Y * STANDARD_GRAVITY, |config| config.gravity);
+    let dt = time.delta_secs();
+    let mut rng = rand::thread_rng();
+
+    for (imu_entity, mut imu, imu_transform, child_of) in imu_query.iter_mut() {
+        // Global transforms are only propagated once a frame, so the body is posed from Rapier
+        let Some(body) = body_set
+            .entity2body()
+            .get(&child_of.parent())
+            .and_then(|handle| body_set.bodies.get(*handle))
+        else {
+            warn_once!("IMU {imu_entity} is not mounted on a rigid body");
+            continue;
+        };
+        let imu_pose = iso_to_transform(body.position()) * *imu_transform;
+
+        // Velocity of the mounting point, differentiated between physics steps
+        let linvel = Vec3::from(*body.linvel());
+        let angvel = Vec3::from(*body.angvel());
+        let lever = imu_pose.translation - Vec3::from(body.center_of_mass().coords);
+        let velocity = linvel + angvel.cross(lever);
+        let acceleration = match imu.last_velocity {
+            Some(last_velocity) if dt > 0.0 => (velocity - last_velocity) / dt,
+            _ => Vec3::ZERO,
+        };
+
+        // An accelerometer measures specific force, so it reads +g when at rest
+        let to_sensor = bevy_to_ros() * imu_pose.rotation.inverse();
+        let specific_force = to_sensor * (acceleration - gravity);
+        let angular_velocity = to_sensor * angvel;
+        // First-order low-pass, starting from the first reading
+        let smoothing = if imu.bandwidth > 0.0 && imu.last_velocity.is_some() {
+            1.0 - (-TAU * imu.bandwidth * dt).exp()
+        } else {
+            1.0
+        };
+        imu.filtered_specific_force = imu.filtered_specific_force.lerp(specific_force, smoothing);
+        imu.filtered_angular_velocity = imu
+            .filtered_angular_velocity
+            .lerp(angular_velocity, smoothing);
+        imu.last_velocity = Some(velocity);
+
+        imu.sample_timer.tick(time.delta());
+        if !imu.sample_timer.just_finished() {
+            continue;
+        }
+        if imu.sample_timer.times_finished_this_tick() > 1 {
+            warn_once!(
+                "IMU {imu_entity} samples at {} Hz, faster than the physics steps",
+                imu.sample_rate
+            );
+        }
+        let stamp = time.elapsed_secs();
+        let sample_interval = imu.sample_timer.duration().as_secs_f32();
+        let specific_force = imu.filtered_specific_force;
+        let angular_velocity = imu.filtered_angular_velocity;
+        let orientation = bevy_to_ros() * imu_pose.rotation * bevy_to_ros().inverse();
+
+        // Biases wander between samples
+        let walk_scale = sample_interval.sqrt();
+        let accel_walk = gaussian(&mut rng, imu.noise.accel_bias_random_walk * walk_scale);
+        let gyro_walk = gaussian(&mut rng, imu.noise.gyro_bias_random_walk * walk_scale);
+        imu.accel_bias += accel_walk;
+        imu.gyro_bias += gyro_walk;
+
+        let accel_limit = Vec3::splat(imu.accel_limit);
+        let gyro_limit = Vec3::splat(imu.gyro_limit);
+        let linear_acceleration =
+            (specific_force + imu.accel_bias + gaussian(&mut rng, imu.noise.accel_stddev))
+                .clamp(-accel_limit, accel_limit);
+        let angular_velocity =
+            (angular_velocity + imu.gyro_bias + gaussian(&mut rng, imu.noise.gyro_stddev))
+                .clamp(-gyro_limit, gyro_limit);
+        let orientation_error = gaussian(&mut rng, imu.noise.orientation_stddev);
+        let orientation = orientation
+            * Quat::from_euler(
+                EulerRot::XYZ,
+                orientation_error.x,
+                orientation_error.y,
+                orientation_error.z,
+            );
+
+        let sample = Imu {
+            orientation,
+            orientation_covariance: diagonal_covariance(imu.noise.orientation_stddev),
+            angular_velocity,
+            angular_velocity_covariance: diagonal_covariance(imu.noise.gyro_stddev),
+            linear_acceleration,
+            linear_acceleration_covariance: diagonal_covariance(imu.noise.accel_stddev),
+        };
+        commands.entity(imu_entity).insert(LatestImu {
+            stamp,
+            frame_id: imu.frame_id.clone(),
+            imu: sample.clone(),
+        });
+        imu_events.write(ImuEvent {
+            sensor: imu_entity,
+            stamp,
+            frame_id: imu.frame_id.clone(),
+            imu: sample,
+        });
+    }
+}
+
+/// System to rebuild the sample timer when the rate changes
+pub fn imu_parameter_update_system(mut imu_query: Query<&mut ImuSensor, Changed<ImuSensor>>) {
+    for mut imu in imu_query.iter_mut() {
+        // Sampling ticks the timer every physics step, so only rebuild it when the rate changed
+        let period = Duration::from_secs_f32(1.0 / imu.sample_rate);
+        if imu.sample_timer.duration() != period {
+            imu.sample_timer = Timer::new(period, TimerMode::Repeating);
+        }
+    }
+}
+
+/// Plugin for simulated IMUs
+pub struct ImuPlugin;
+
+impl Plugin for ImuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ImuEvent>()
+            .add_systems(
+                FixedUpdate,
+                (imu_parameter_update_system, imu_sampling_system)
+                    .chain()
+                    .after(PhysicsSet::Writeback),
+            )
+            .register_type::<ImuSensor>()
+            .register_type::<LatestImu>();
+    }
+}
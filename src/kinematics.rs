@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::robotic_arm::ARM_JOINT_COUNT;
+
+/// Denavit–Hartenberg parameters of one link, in the standard convention: the link's frame
+/// follows from the previous one by turning θ about z, moving `d` along z, moving `a` along
+/// the new x and turning `alpha` about it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DhParameters {
+    /// Link length (meters)
+    pub a: f32,
+    /// Link offset (meters)
+    pub d: f32,
+    /// Link twist (radians)
+    pub alpha: f32,
+}
+
+/// The UR3e's parameters as published by Universal Robots, from the base to the tool flange
+pub const UR3E_DH: [DhParameters; ARM_JOINT_COUNT] = [
+    DhParameters {
+        a: 0.0,
+        d: 0.15185,
+        alpha: FRAC_PI_2,
+    },
+    DhParameters {
+        a: -0.24355,
+        d: 0.0,
+        alpha: 0.0,
+    },
+    DhParameters {
+        a: -0.2132,
+        d: 0.0,
+        alpha: 0.0,
+    },
+    DhParameters {
+        a: 0.0,
+        d: 0.13105,
+        alpha: FRAC_PI_2,
+    },
+    DhParameters {
+        a: 0.0,
+        d: 0.08535,
+        alpha: -FRAC_PI_2,
+    },
+    DhParameters {
+        a: 0.0,
+        d: 0.0921,
+        alpha: 0.0,
+    },
+];
+
+/// DH joint angle of each joint when the simulated arm's joint is at zero, i.e. with the arm
+/// upright as it is spawned
+const JOINT_OFFSETS: [f32; ARM_JOINT_COUNT] = [0.0, -FRAC_PI_2, 0.0, -FRAC_PI_2, 0.0, 0.0];
+
+/// Whether each simulated joint turns the same way as the DH joint (1) or the opposite way (-1)
+const JOINT_DIRECTIONS: [f32; ARM_JOINT_COUNT] = [1.0, -1.0, -1.0, -1.0, 1.0, -1.0];
+
+/// DH angles of the joints, from the simulated arm's joint positions
+pub fn dh_joint_angles(q: [f32; ARM_JOINT_COUNT]) -> [f32; ARM_JOINT_COUNT] {
+    std::array::from_fn(|joint| JOINT_DIRECTIONS[joint] * q[joint] + JOINT_OFFSETS[joint])
+}
+
+/// Orientation of the DH base frame (z up, arm reaching along -x at zero) in Bevy axes
+fn base_rotation() -> Quat {
+    Quat::from_mat3(&Mat3::from_cols(Vec3::NEG_X, Vec3::Z, Vec3::Y))
+}
+
+/// Transform from the previous link's frame to this one's
+fn link_transform(parameters: &DhParameters, theta: f32) -> Transform {
+    Transform::from_rotation(Quat::from_rotation_z(theta))
+        * Transform::from_xyz(parameters.a, 0.0, parameters.d)
+        * Transform::from_rotation(Quat::from_rotation_x(parameters.alpha))
+}
+
+/// DH frames of the base and of each link for the given joint positions, in the arm's frame.
+///
+/// `q` holds joint positions as in `JointTargets` and `JointStates`. The frames are in Bevy
+/// axes relative to the bottom of the arm's base; the z axis of frame `i` is the axis of joint
+/// `i + 1`, and the last frame is the tool flange.
+pub fn link_frames(q: [f32; ARM_JOINT_COUNT]) -> [Transform; ARM_JOINT_COUNT + 1] {
+    let theta = dh_joint_angles(q);
+    let mut frames = [Transform::from_rotation(base_rotation()); ARM_JOINT_COUNT + 1];
+    for joint in 0..ARM_JOINT_COUNT {
+        frames[joint + 1] = frames[joint] * link_transform(&UR3E_DH[joint], theta[joint]);
+    }
+    frames
+}
+
+/// Pose of the tool flange for the given joint positions, in the arm's frame.
+///
+/// The flange's z axis points out of the wrist along the last joint's axis.
+pub fn forward_kinematics(q: [f32; ARM_JOINT_COUNT]) -> Transform {
+    link_frames(q)[ARM_JOINT_COUNT]
+}
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy::reflect::Reflect;
 use bevy_rapier3d::prelude::*;
 use std::f32::consts::PI;
+use std::time::Duration;
 
 use rand_distr::{Distribution, Normal};
 
@@ -35,6 +36,31 @@ pub struct LaserScan {
     pub intensities: Vec<f32>,
 }
 
+/// Event emitted for every completed LIDAR scan
+#[derive(Event, Debug, Clone)]
+pub struct LidarScanEvent {
+    /// Sensor entity that produced the scan
+    pub sensor: Entity,
+    /// Elapsed simulation time when the scan completed (seconds)
+    pub stamp: f32,
+    /// Frame the ranges are expressed in
+    pub frame_id: String,
+    /// The scan itself
+    pub scan: LaserScan,
+}
+
+/// Most recent scan of a LIDAR sensor, kept on the sensor entity
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct LatestScan {
+    /// Elapsed simulation time when the scan completed (seconds)
+    pub stamp: f32,
+    /// Frame the ranges are expressed in
+    pub frame_id: String,
+    /// The scan itself
+    pub scan: LaserScan,
+}
+
 /// LIDAR sensor component with obstacle detection
 #[derive(Component, Reflect)]
 #[reflect(Component)]
@@ -65,10 +91,14 @@ pub struct LidarSensor {
     /// Last scan results: (angle, distance, hit_something)
     #[reflect(ignore)]
     pub scan_results: Vec<(f32, f32, bool)>,
-    /// Whether to enable logging
+    /// Whether to dump completed scans to the console
     pub enable_logging: bool,
+    /// Frame id stamped on published scans
+    pub frame_id: String,
     /// Standard deviation for noise (0 for no noise)
     pub noise_stddev: f32,
+    /// Collision groups used for ray casts; colliders must accept the LIDAR group to be seen
+    pub collision_groups: CollisionGroups,
 }
 
 impl Default for LidarSensor {
@@ -86,7 +116,12 @@ impl Default for LidarSensor {
             current_ray: 0,
             scan_results: Vec::with_capacity(LIDAR_RAYS_PER_SCAN),
             enable_logging: true,
+            frame_id: "lidar_link".to_string(),
             noise_stddev: 0.0,
+            collision_groups: CollisionGroups::new(
+                crate::LIDAR_GROUP,
+                crate::STATIC_GROUP | crate::CHASSIS_GROUP,
+            ),
         }
     }
 }
@@ -97,8 +132,12 @@ impl LidarSensor {
         // Recalculate angular resolution
         self.angular_resolution = 2.0 * PI / self.rays_per_scan as f32;
 
-        // Update timer with new scan rate
-        self.scan_timer = Timer::from_seconds(1.0 / self.scan_rate, TimerMode::Repeating);
+        // Update timer with new scan rate. Ticking the timer marks the component as changed
+        // every frame, so keep its progress unless the rate actually changed.
+        let scan_period = Duration::from_secs_f32(1.0 / self.scan_rate);
+        if self.scan_timer.duration() != scan_period {
+            self.scan_timer = Timer::new(scan_period, TimerMode::Repeating);
+        }
 
         // Resize scan results if rays_per_scan changed
         if self.scan_results.capacity() != self.rays_per_scan {
@@ -112,18 +151,22 @@ pub struct LidarPlugin;
 
 impl Plugin for LidarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                lidar_parameter_update_system,
-                lidar_scanning_system,
-                lidar_visualization_system,
-            ),
-        )
-        .register_type::<LidarSensor>()
-        .register_type::<LaserScan>()
-        .register_type::<Vec3>()
-        .register_type::<Timer>();
+        app.add_event::<LidarScanEvent>()
+            .add_systems(
+                Update,
+                (
+                    lidar_parameter_update_system,
+                    lidar_scanning_system,
+                    lidar_logging_system.after(lidar_scanning_system),
+                    // Gizmos are unavailable when running headless
+                    lidar_visualization_system.run_if(resource_exists::<GizmoConfigStore>),
+                ),
+            )
+            .register_type::<LidarSensor>()
+            .register_type::<LatestScan>()
+            .register_type::<LaserScan>()
+            .register_type::<Vec3>()
+            .register_type::<Timer>();
     }
 }
 
@@ -136,18 +179,29 @@ pub fn lidar_parameter_update_system(
     }
 }
 
-/// System that performs LIDAR scanning by detecting nearby obstacles
+/// Direction of a LIDAR ray in the sensor frame.
+///
+/// Angle 0 points along +X and angles increase counter-clockwise about +Y (ROS convention).
+pub fn ray_direction(angle: f32) -> Vec3 {
+    Vec3::new(angle.cos(), 0.0, -angle.sin())
+}
+
+/// System that performs LIDAR scanning by casting rays against Rapier collider geometry
 pub fn lidar_scanning_system(
+    mut commands: Commands,
     time: Res<Time>,
+    rapier_context: ReadRapierContext,
     mut lidar_query: Query<(&mut LidarSensor, &GlobalTransform, Entity), With<LidarSensor>>,
-    obstacle_query: Query<&GlobalTransform, (With<Collider>, Without<LidarSensor>)>,
+    mut scan_events: EventWriter<LidarScanEvent>,
 ) {
-    for (mut lidar, lidar_transform, _lidar_entity) in lidar_query.iter_mut() {
+    let Ok(rapier_context) = rapier_context.single() else {
+        return;
+    };
+
+    for (mut lidar, lidar_transform, lidar_entity) in lidar_query.iter_mut() {
         lidar.scan_timer.tick(time.delta());
 
         if lidar.scan_timer.just_finished() {
-            let scan_start_time = time.elapsed().as_secs_f32();
-
             // Initialize LaserScan message
             let mut laser_scan = LaserScan {
                 angle_min: 0.0,
@@ -169,53 +223,38 @@ pub fn lidar_scanning_system(
             // Get LIDAR world position
             let lidar_pos = lidar_transform.translation();
 
-            // Scan statistics
+            // Only colliders whose filters accept the LIDAR group are hit, which keeps
+            // the robot's own chassis and wheels out of the scan
+            let filter = QueryFilter::new()
+                .groups(lidar.collision_groups)
+                .exclude_sensors();
+
             let mut valid_ranges = 0;
-            let mut min_range_detected = f32::INFINITY;
-            let mut max_range_detected: f32 = 0.0;
 
             // Perform 360-degree scan using configurable parameters
             for i in 0..lidar.rays_per_scan {
                 let angle = i as f32 * lidar.angular_resolution;
-
-                // Calculate ray direction (starting from +X axis, rotating counter-clockwise in XZ plane)
-                let local_direction = Vec3::new(
-                    angle.cos(),
-                    0.0,
-                    angle.sin(), // Positive for counter-clockwise rotation (ROS standard)
+                let world_direction = lidar_transform.rotation() * ray_direction(angle);
+
+                // Cast the ray against the actual collider shapes
+                let hit = rapier_context.cast_ray_and_get_normal(
+                    lidar_pos,
+                    world_direction,
+                    lidar.range_max,
+                    true,
+                    filter,
                 );
-                let world_direction = lidar_transform.rotation() * local_direction;
 
-                // Find closest obstacle in this direction
+                // Returns closer than range_min are discarded, as on the real sensor
                 let mut closest_distance = lidar.range_max;
+                let mut intensity = 0.0;
                 let mut found_obstacle = false;
-
-                for obstacle_transform in obstacle_query.iter() {
-                    let obstacle_pos = obstacle_transform.translation();
-                    let to_obstacle = obstacle_pos - lidar_pos;
-
-                    // Skip if obstacle is too close or too far
-                    let distance_to_obstacle = to_obstacle.length();
-                    if distance_to_obstacle < lidar.range_min
-                        || distance_to_obstacle > lidar.range_max
-                    {
-                        continue;
-                    }
-
-                    // Check if obstacle is in the direction of our ray (within a cone)
-                    let to_obstacle_normalized = to_obstacle.normalize();
-                    let dot_product = world_direction.dot(to_obstacle_normalized);
-
-                    // Angular tolerance (roughly 5 degrees on each side)
-                    let angular_tolerance: f32 = 0.087; // ~5 degrees in radians
-                    let min_dot = angular_tolerance.cos();
-
-                    if dot_product > min_dot {
-                        // Obstacle is in this ray's direction
-                        if distance_to_obstacle < closest_distance {
-                            closest_distance = distance_to_obstacle;
-                            found_obstacle = true;
-                        }
+                if let Some((_, intersection)) = hit {
+                    if intersection.time_of_impact >= lidar.range_min {
+                        closest_distance = intersection.time_of_impact;
+                        // Approximate return strength by the angle of incidence
+                        intensity = intersection.normal.dot(-world_direction).abs();
+                        found_obstacle = true;
                     }
                 }
 
@@ -224,25 +263,15 @@ pub fn lidar_scanning_system(
                     let mut rng = rand::thread_rng();
                     let noise = Normal::new(0.0, lidar.noise_stddev).unwrap();
                     let noise_value = noise.sample(&mut rng);
-                    closest_distance += noise_value;
-                }
-
-                // Log individual object detection with distance and angle
-                if found_obstacle && lidar.enable_logging {
-                    let angle_degrees = angle * 180.0 / PI;
-                    info!(
-                        "Object detected at angle: {:.1}° ({:.3} rad), distance: {:.3}m",
-                        angle_degrees, angle, closest_distance
-                    );
+                    closest_distance =
+                        (closest_distance + noise_value).clamp(lidar.range_min, lidar.range_max);
                 }
 
                 // Store in LaserScan format
                 if found_obstacle {
                     laser_scan.ranges.push(closest_distance);
-                    laser_scan.intensities.push(1.0); // Hit intensity
+                    laser_scan.intensities.push(intensity);
                     valid_ranges += 1;
-                    min_range_detected = min_range_detected.min(closest_distance);
-                    max_range_detected = max_range_detected.max(closest_distance);
                 } else {
                     laser_scan.ranges.push(f32::INFINITY); // No hit - ROS standard
                     laser_scan.intensities.push(0.0); // No hit intensity
@@ -260,69 +289,18 @@ pub fn lidar_scanning_system(
                 lidar.current_angle = angle;
             }
 
-            let scan_end_time = time.elapsed().as_secs_f32();
-            let scan_duration = scan_end_time - scan_start_time;
-
-            // Print LaserScan message in ROS/Gazebo format
-            if lidar.enable_logging {
-                info!("---");
-                info!("LaserScan Message:");
-                info!("  header:");
-                info!("    stamp: {:.6}", scan_end_time);
-                info!("    frame_id: \"lidar_link\"");
-                info!("  angle_min: {:.6}", laser_scan.angle_min);
-                info!("  angle_max: {:.6}", laser_scan.angle_max);
-                info!("  angle_increment: {:.6}", laser_scan.angle_increment);
-                info!("  time_increment: {:.6}", laser_scan.time_increment);
-                info!("  scan_time: {:.6}", laser_scan.scan_time);
-                info!("  range_min: {:.2}", laser_scan.range_min);
-                info!("  range_max: {:.2}", laser_scan.range_max);
-                info!("  ranges: [");
-
-                // Print ranges in groups of 10 for readability
-                for (i, &range) in laser_scan.ranges.iter().enumerate() {
-                    if i % 10 == 0 {
-                        if i > 0 {
-                            info!("");
-                        }
-                        print!("    ");
-                    }
-                    if range == f32::INFINITY {
-                        print!("inf, ");
-                    } else {
-                        print!("{range:.3}, ");
-                    }
-                }
-                info!("");
-                info!("  ]");
-                info!("  intensities: [");
-
-                // Print intensities in groups of 10
-                for (i, &intensity) in laser_scan.intensities.iter().enumerate() {
-                    if i % 10 == 0 {
-                        if i > 0 {
-                            info!("");
-                        }
-                        print!("    ");
-                    }
-                    print!("{intensity:.1}, ");
-                }
-                info!("");
-                info!("  ]");
-                info!("---");
-
-                // Print scan statistics (Gazebo style)
-                info!("LIDAR Scan Statistics:");
-                info!("  Total rays: {}", lidar.rays_per_scan);
-                info!("  Valid ranges: {}", valid_ranges);
-                info!("  Invalid ranges: {}", lidar.rays_per_scan - valid_ranges);
-                if valid_ranges > 0 {
-                    info!("  Min range detected: {:.3}m", min_range_detected);
-                    info!("  Max range detected: {:.3}m", max_range_detected);
-                }
-                info!("  Scan duration: {:.3}ms", scan_duration * 1000.0);
-                info!("  Scan rate: {:.1}Hz", lidar.scan_rate);
-            }
+            let stamp = time.elapsed_secs();
+            commands.entity(lidar_entity).insert(LatestScan {
+                stamp,
+                frame_id: lidar.frame_id.clone(),
+                scan: laser_scan.clone(),
+            });
+            scan_events.write(LidarScanEvent {
+                sensor: lidar_entity,
+                stamp,
+                frame_id: lidar.frame_id.clone(),
+                scan: laser_scan,
+            });
 
             debug!(
                 "LIDAR scan completed: {} rays, {} valid ranges",
@@ -339,6 +317,102 @@ pub fn lidar_scanning_system(
     }
 }
 
+/// Formats values in rows of ten for the console dump
+fn format_rows(values: &[f32], precision: usize) -> Vec<String> {
+    values
+        .chunks(10)
+        .map(|row| {
+            let row: Vec<String> = row
+                .iter()
+                .map(|&value| {
+                    if value == f32::INFINITY {
+                        "inf".to_string()
+                    } else {
+                        format!("{value:.precision$}")
+                    }
+                })
+                .collect();
+            format!("    {},", row.join(", "))
+        })
+        .collect()
+}
+
+/// System that dumps completed scans of sensors with `enable_logging` set, in ROS/Gazebo format
+pub fn lidar_logging_system(
+    mut scan_events: EventReader<LidarScanEvent>,
+    lidar_query: Query<&LidarSensor>,
+) {
+    for event in scan_events.read() {
+        let Ok(lidar) = lidar_query.get(event.sensor) else {
+            continue;
+        };
+        if !lidar.enable_logging {
+            continue;
+        }
+        let laser_scan = &event.scan;
+
+        // Log individual object detection with distance and angle
+        for (i, &range) in laser_scan.ranges.iter().enumerate() {
+            if range.is_finite() {
+                let angle = laser_scan.angle_min + i as f32 * laser_scan.angle_increment;
+                info!(
+                    "Object detected at angle: {:.1}° ({:.3} rad), distance: {:.3}m",
+                    angle.to_degrees(),
+                    angle,
+                    range
+                );
+            }
+        }
+
+        // Print LaserScan message in ROS/Gazebo format
+        info!("---");
+        info!("LaserScan Message:");
+        info!("  header:");
+        info!("    stamp: {:.6}", event.stamp);
+        info!("    frame_id: \"{}\"", event.frame_id);
+        info!("  angle_min: {:.6}", laser_scan.angle_min);
+        info!("  angle_max: {:.6}", laser_scan.angle_max);
+        info!("  angle_increment: {:.6}", laser_scan.angle_increment);
+        info!("  time_increment: {:.6}", laser_scan.time_increment);
+        info!("  scan_time: {:.6}", laser_scan.scan_time);
+        info!("  range_min: {:.2}", laser_scan.range_min);
+        info!("  range_max: {:.2}", laser_scan.range_max);
+        info!("  ranges: [");
+        for row in format_rows(&laser_scan.ranges, 3) {
+            info!("{}", row);
+        }
+        info!("  ]");
+        info!("  intensities: [");
+        for row in format_rows(&laser_scan.intensities, 1) {
+            info!("{}", row);
+        }
+        info!("  ]");
+        info!("---");
+
+        // Print scan statistics (Gazebo style)
+        let valid: Vec<f32> = laser_scan
+            .ranges
+            .iter()
+            .copied()
+            .filter(|range| range.is_finite())
+            .collect();
+        info!("LIDAR Scan Statistics:");
+        info!("  Total rays: {}", laser_scan.ranges.len());
+        info!("  Valid ranges: {}", valid.len());
+        info!(
+            "  Invalid ranges: {}",
+            laser_scan.ranges.len() - valid.len()
+        );
+        if !valid.is_empty() {
+            let min_range = valid.iter().copied().fold(f32::INFINITY, f32::min);
+            let max_range = valid.iter().copied().fold(0.0, f32::max);
+            info!("  Min range detected: {:.3}m", min_range);
+            info!("  Max range detected: {:.3}m", max_range);
+        }
+        info!("  Scan rate: {:.1}Hz", 1.0 / laser_scan.scan_time);
+    }
+}
+
 /// System to visualize LIDAR rays using gizmos
 pub fn lidar_visualization_system(
     mut gizmos: Gizmos,
@@ -354,8 +428,7 @@ pub fn lidar_visualization_system(
         // Draw all rays from the last scan
         for &(angle, distance, hit_something) in &lidar.scan_results {
             // Calculate ray direction
-            let local_direction = Vec3::new(angle.cos(), 0.0, -angle.sin());
-            let world_direction = transform.rotation() * local_direction;
+            let world_direction = transform.rotation() * ray_direction(angle);
 
             // Calculate hit point
             let hit_point = lidar_pos + world_direction * distance;
@@ -438,9 +511,7 @@ pub fn lidar_visualization_system(
 
         // Draw current ray direction indicator with medium opacity
         if !lidar.scan_results.is_empty() {
-            let local_direction =
-                Vec3::new(lidar.current_angle.cos(), 0.0, -lidar.current_angle.sin());
-            let world_direction = transform.rotation() * local_direction;
+            let world_direction = transform.rotation() * ray_direction(lidar.current_angle);
             let direction_end = lidar_pos + world_direction * 0.4;
             let direction_color = Color::srgba(1.0, 1.0, 1.0, 0.6); // 60% opacity for current ray
             gizmos.line(lidar_pos, direction_end, direction_color);
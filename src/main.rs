@@ -1,10 +1,15 @@
+use bevy::gltf::GltfPlugin;
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy::render::camera::{CameraProjection, RenderTarget, SubCameraView};
+use bevy::render::mesh::MeshPlugin;
+use bevy::scene::ScenePlugin;
 use bevy::window::{Window, WindowPosition, WindowRef};
 use bevy_rapier3d::{
     dynamics::Velocity,
     geometry::{Collider, CollisionGroups, Group},
-    plugin::{NoUserData, RapierPhysicsPlugin},
+    plugin::{NoUserData, PhysicsSet, RapierPhysicsPlugin, TimestepMode},
     render::RapierDebugRenderPlugin,
 };
 use bevy_stl::StlPlugin;
@@ -13,14 +18,24 @@ use bevy_obj::ObjPlugin;
 use clap::Parser;
 
 mod camera;
+mod collada;
+mod diff_drive;
+mod imu;
 mod keyboard_controls;
+mod kinematics;
 mod lidar;
+mod odometry;
 mod robot_drag;
 mod robotic_arm;
+mod sdf_asset;
+mod sdf_geometry;
 mod sdf_loader;
-mod sdf_world_loader;
-mod sdf_world_simple;
+mod sdf_model_path;
+mod sdf_validation;
+mod sdf_writer;
 mod turtlebot4;
+mod urdf;
+mod xacro;
 
 #[derive(Parser)]
 #[command(name = "bevy_turtlebot4_testbed")]
@@ -30,6 +45,104 @@ struct Args {
     /// Robot to spawn: turtlebot or robotic-arm
     #[arg(short, long, default_value = "turtlebot")]
     robot: String,
+
+    /// Run without windows, cameras, gizmos or the render pipeline
+    #[arg(long)]
+    headless: bool,
+
+    /// Directory to search for included models and model:// URIs, before GZ_SIM_RESOURCE_PATH
+    #[arg(long = "model-path", value_name = "DIR")]
+    model_paths: Vec<std::path::PathBuf>,
+
+    /// Check an SDF file for errors and exit, with a non-zero status if it has any
+    #[arg(long, value_name = "FILE")]
+    validate_sdf: Option<std::path::PathBuf>,
+
+    /// File the scene is saved to as an SDF world when F5 is pressed
+    #[arg(long, value_name = "FILE", default_value = "saved_world.sdf")]
+    save_sdf: std::path::PathBuf,
+
+    /// URDF or xacro file of a robot to spawn at the origin, along with the chosen robot
+    #[arg(long, value_name = "FILE")]
+    urdf: Option<std::path::PathBuf>,
+
+    /// Stiffness of the robotic arm's joint position motors, in N·m/rad
+    #[arg(long, value_name = "STIFFNESS", default_value_t = robotic_arm::JointGains::default().stiffness)]
+    arm_stiffness: f32,
+
+    /// Damping of the robotic arm's joint position motors, in N·m·s/rad
+    #[arg(long, value_name = "DAMPING", default_value_t = robotic_arm::JointGains::default().damping)]
+    arm_damping: f32,
+
+    /// Arguments for a xacro --urdf file, as name:=value
+    #[arg(value_name = "NAME:=VALUE")]
+    xacro_args: Vec<String>,
+}
+
+/// Plugins for running the simulation without a window or renderer.
+///
+/// Provides only what SDF worlds and GLB/STL/OBJ assets need to load; physics, sensors and
+/// robot control are added on top exactly as in windowed mode.
+struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            LogPlugin::default(),
+            AssetPlugin::default(),
+            TransformPlugin,
+            ScenePlugin,
+            MeshPlugin,
+            GltfPlugin::default(),
+            InputPlugin,
+        ))
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        // Heightmap images are decoded without a GPU, so no compressed formats
+        .register_asset_loader(bevy::image::ImageLoader::new(
+            bevy::image::CompressedImageFormats::NONE,
+        ))
+        // glTF scenes carry these components; the scene spawner needs them
+        // registered even though nothing renders them.
+        .register_type::<Visibility>()
+        .register_type::<InheritedVisibility>()
+        .register_type::<ViewVisibility>()
+        .register_type::<bevy::render::view::VisibilityClass>()
+        .register_type::<Mesh3d>()
+        .register_type::<MeshMaterial3d<StandardMaterial>>()
+        .register_type::<bevy::render::primitives::Aabb>()
+        .register_type::<DirectionalLight>()
+        .register_type::<PointLight>()
+        .register_type::<SpotLight>();
+    }
+}
+
+/// Rapier physics, stepped once per run of the `FixedUpdate` schedule.
+///
+/// Sensors and controllers that have to see every physics step run in `FixedUpdate` after
+/// `PhysicsSet::Writeback`. The step length is the `Time<Fixed>` timestep, which the SDF
+/// world's `max_step_size` sets.
+struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        // Inserted before Rapier's plugin, which would otherwise default to a variable timestep
+        app.insert_resource(TimestepMode::Fixed {
+            dt: Time::<Fixed>::default().timestep().as_secs_f32(),
+            substeps: 1,
+        })
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+        .add_systems(FixedUpdate, physics_timestep_system.before(PhysicsSet::SyncBackend));
+    }
+}
+
+/// System to keep Rapier's step length equal to the fixed timestep
+fn physics_timestep_system(fixed_time: Res<Time<Fixed>>, mut timestep_mode: ResMut<TimestepMode>) {
+    timestep_mode.set_if_neq(TimestepMode::Fixed {
+        dt: fixed_time.timestep().as_secs_f32(),
+        substeps: 1,
+    });
 }
 
 #[derive(Debug, Clone)]
@@ -198,27 +311,62 @@ mod tests;
 pub const STATIC_GROUP: Group = Group::GROUP_1;
 pub const CHASSIS_INTERNAL_GROUP: Group = Group::GROUP_2;
 pub const CHASSIS_GROUP: Group = Group::GROUP_3;
+/// Membership used by LIDAR ray casts; colliders that should show up in scans list it in their filters
+pub const LIDAR_GROUP: Group = Group::GROUP_4;
+
+/// System to request the default SDF world
+fn load_sdf_world_system(mut requests: EventWriter<sdf_loader::LoadSdfWorldRequest>) {
+    requests.write(sdf_loader::LoadSdfWorldRequest::new("worlds/simple_world.sdf"));
+}
 
-/// System to load SDF world from file
-fn load_sdf_world_system(
+/// System to fall back to a simple world if the SDF world fails to load
+fn sdf_world_fallback_system(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    asset_server: Res<AssetServer>,
+    mut failed_events: EventReader<sdf_loader::SdfWorldLoadFailed>,
 ) {
-    match sdf_loader::load_sdf("assets/worlds/simple_world.sdf") {
-        Ok(sdf_world) => {
-            info!("Loading SDF world: {}", sdf_world.name);
-            sdf_loader::spawn_sdf_world(&mut commands, &mut meshes, &mut materials, &asset_server, &sdf_world);
-        },
-        Err(e) => {
-            error!("Failed to load SDF world: {}", e);
-            // Fallback to simple world if SDF loading fails
-            spawn_fallback_world(&mut commands, &mut meshes, &mut materials);
-        }
+    if failed_events.read().count() > 0 {
+        spawn_fallback_world(&mut commands, &mut meshes, &mut materials);
+    }
+}
+
+/// Loads the robot of a URDF file, or of a xacro file expanded with `xacro_args`
+fn load_robot_description(
+    file: &std::path::Path,
+    xacro_args: &[String],
+) -> Result<urdf::UrdfRobot, String> {
+    let name = file.to_string_lossy();
+    if file.extension().is_some_and(|extension| extension == "xacro") {
+        let urdf = xacro::expand_xacro_file(file, xacro_args).map_err(|e| e.to_string())?;
+        urdf::parse_urdf_file(&urdf, &name).map_err(|e| e.to_string())
+    } else {
+        urdf::load_urdf(&name).map_err(|e| e.to_string())
     }
 }
 
+/// URDF robot to spawn at startup
+#[derive(Resource)]
+struct UrdfRobotToSpawn(urdf::UrdfRobot);
+
+/// System to spawn the robot given with `--urdf`
+fn spawn_urdf_robot_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    robot: Res<UrdfRobotToSpawn>,
+) {
+    urdf::spawn_urdf_robot(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &asset_server,
+        &robot.0,
+        Transform::IDENTITY,
+    );
+}
+
 /// Fallback world if SDF loading fails
 fn spawn_fallback_world(
     commands: &mut Commands,
@@ -232,7 +380,7 @@ fn spawn_fallback_world(
         .spawn(Collider::cuboid(10.0, 0.1, 10.0))
         .insert(CollisionGroups::new(
             STATIC_GROUP,
-            CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP,
+            CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP | LIDAR_GROUP,
         ))
         .insert((
             Transform::from_xyz(0.0, -0.1, 0.0),
@@ -254,67 +402,121 @@ fn spawn_fallback_world(
 
 pub fn main() {
     let args = Args::parse();
+    let model_path = sdf_model_path::SdfModelPath::new(args.model_paths);
 
-    let mut app_binding = App::new();
-    let app_binding = app_binding
-        .add_plugins(DefaultPlugins)
-        .add_plugins(StlPlugin)
-        .add_plugins(ObjPlugin)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugins(RapierDebugRenderPlugin::default())
+    if let Some(file) = &args.validate_sdf {
+        let diagnostics = sdf_validation::validate_sdf_file(file, &model_path);
+        for diagnostic in &diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+        let failed = diagnostics.iter().any(|d| d.severity == sdf_validation::SdfSeverity::Error);
+        std::process::exit(if failed { 1 } else { 0 });
+    }
 
+    let mut app = App::new();
+    // The model:// asset source has to be registered before the AssetPlugin
+    app.add_plugins(sdf_model_path::SdfModelPathPlugin(model_path));
+    if args.headless {
+        app.add_plugins(HeadlessPlugin);
+    } else {
+        app.add_plugins(DefaultPlugins)
+            .add_plugins(RapierDebugRenderPlugin::default());
+    }
+    app.add_plugins(StlPlugin)
+        .add_plugins(ObjPlugin)
+        .add_plugins(PhysicsPlugin)
+        .add_plugins(diff_drive::DiffDrivePlugin)
+        .add_plugins(imu::ImuPlugin)
         .add_plugins(lidar::LidarPlugin)
+        .add_plugins(odometry::OdometryPlugin)
         .add_plugins(robot_drag::RobotDragPlugin)
-        .add_systems(
-            Update,
-            (
-                camera::update_camera_system,
-                camera::accumulate_mouse_events_system,
-                camera::update_camera_focus_on_robot,
-                keyboard_controls::control_robot_movement,
-                update_projection_from_robot,
-                keyboard_controls::display_robot_controls_info,
-                keyboard_controls::manual_adjust_oblique_projection,
-                keyboard_controls::toggle_lidar_visualization,
-                render_origin,
-            ),
-        )
-;
+        .add_plugins(sdf_loader::SdfPlugin)
+        .insert_resource(sdf_writer::SdfSavePath(args.save_sdf.clone()))
+        .add_systems(Update, sdf_writer::save_sdf_world_system);
+
+    if let Some(file) = &args.urdf {
+        match load_robot_description(file, &args.xacro_args) {
+            Ok(robot) => {
+                app.insert_resource(UrdfRobotToSpawn(robot))
+                    .add_systems(Startup, spawn_urdf_robot_system);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Setup robot-specific systems based on CLI args
-    let app = match args.robot.as_str() {
-        "turtlebot" => {
-            app_binding
-                .add_systems(Startup, (setup_camera_and_robot, setup_custom_projection_window))
-                .add_systems(PostStartup, setup_custom_projection_camera)
-                .add_systems(Update, robot_drag::make_robot_draggable)
-                .add_systems(Startup, load_sdf_world_system)
+    let robot = match args.robot.as_str() {
+        "turtlebot" | "robotic-arm" => args.robot.as_str(),
+        _ => {
+            eprintln!("Unknown robot type: {}. Using turtlebot as default.", args.robot);
+            "turtlebot"
         }
+    };
+
+    match robot {
         "robotic-arm" => {
-            app_binding
-                .insert_resource(robotic_arm::JointTargets::default())
+            let gains = robotic_arm::JointGains {
+                stiffness: args.arm_stiffness,
+                damping: args.arm_damping,
+            };
+            app.insert_resource(robotic_arm::JointTargets::default())
+                .insert_resource(robotic_arm::ArmJointGains::uniform(gains))
+                .init_resource::<robotic_arm::JointStates>()
+                .add_event::<robotic_arm::JointStates>()
                 .add_systems(Startup, robotic_arm::setup)
+                .add_systems(FixedUpdate, robotic_arm::joint_state_system.after(PhysicsSet::Writeback))
                 .add_systems(Update, (
                     robotic_arm::keyboard_input,
+                    robotic_arm::drive_arm_joints,
                     robotic_arm::update_gripped_objects,
                     robotic_arm::detect_drag_state,
                     robotic_arm::return_to_original_position,
                     robotic_arm::simple_gripper_control,
                     robotic_arm::animate_gripper_fingers_system,
                     robotic_arm::highlight_grippable_blocks,
-                    camera::update_camera_system,
-                    camera::accumulate_mouse_events_system,
-                    render_origin,
-                ))
+                ));
+            if !args.headless {
+                app.add_systems(Startup, robotic_arm::setup_camera_and_light)
+                    .add_systems(Update, (
+                        camera::update_camera_system,
+                        camera::accumulate_mouse_events_system,
+                        render_origin,
+                    ));
+            }
         }
         _ => {
-            eprintln!("Unknown robot type: {}. Using turtlebot as default.", args.robot);
-            app_binding
-                .add_systems(Startup, (setup_camera_and_robot, setup_custom_projection_window))
-                .add_systems(Update, robot_drag::make_robot_draggable)
+            // Step at the SDF default from the start, not only once the world has loaded
+            let max_step_size = sdf_loader::SdfPhysics::default().max_step_size;
+            app.insert_resource(Time::<Fixed>::from_seconds(max_step_size as f64))
                 .add_systems(Startup, load_sdf_world_system)
+                .add_systems(Update, (
+                    sdf_world_fallback_system,
+                    spawn_robot_system,
+                    keyboard_controls::control_robot_movement,
+                    keyboard_controls::toggle_lidar_visualization,
+                ));
+            if !args.headless {
+                app.add_systems(Startup, (setup_camera, setup_custom_projection_window))
+                    .add_systems(PostStartup, setup_custom_projection_camera)
+                    .add_systems(Update, robot_drag::make_robot_draggable)
+                    .add_systems(
+                        Update,
+                        (
+                            camera::update_camera_system,
+                            camera::accumulate_mouse_events_system,
+                            camera::update_camera_focus_on_robot,
+                            update_projection_from_robot,
+                            keyboard_controls::display_robot_controls_info,
+                            keyboard_controls::manual_adjust_oblique_projection,
+                            render_origin,
+                        ),
+                    );
+            }
         }
-    };
+    }
 
     app.run();
 }
@@ -325,10 +527,7 @@ fn render_origin(mut gizmos: Gizmos) {
     gizmos.line(Vec3::ZERO, Vec3::Z, Color::srgb(0.0, 0.0, 1.0));
 }
 
-fn setup_camera_and_robot(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-) {
+fn setup_camera(mut commands: Commands) {
     let translation = Vec3::new(1.0, 2.0, 2.0);
     let focus = Vec3::ZERO;
     let transform = Transform::from_translation(translation).looking_at(focus, Vec3::Y);
@@ -352,6 +551,20 @@ fn setup_camera_and_robot(
             radius: translation.length(),
             ..default()
         });
+}
+
+/// System to spawn the robot once the SDF world, or the fallback world, is there to stand on
+fn spawn_robot_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loaded_events: EventReader<sdf_loader::SdfWorldLoaded>,
+    mut failed_events: EventReader<sdf_loader::SdfWorldLoadFailed>,
+    robots: Query<(), With<RobotChassis>>,
+) {
+    let world_ready = loaded_events.read().count() + failed_events.read().count() > 0;
+    if !world_ready || !robots.is_empty() {
+        return;
+    }
 
     // Robot - this will be controlled by the user
     turtlebot4::spawn(
@@ -359,6 +572,4 @@ fn setup_camera_and_robot(
         &asset_server,
         &Transform::from_xyz(0.0, 0.5, 0.0),
     );
-
 }
-
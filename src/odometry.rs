@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use bevy::reflect::Reflect;
+use bevy_rapier3d::plugin::PhysicsSet;
+use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::utils::iso_to_transform;
+use std::f32::consts::TAU;
+
+use rand_distr::{Distribution, Normal};
+
+use crate::diff_drive::Twist;
+use crate::turtlebot4::Wheel;
+
+// iRobot Create 3 wheel encoders
+const ENCODER_TICKS_PER_REVOLUTION: f32 = 508.8;
+const SLIP_VARIANCE: f32 = 1.0e-4; // m² per meter travelled
+
+/// Error sources applied to simulated wheel odometry
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct OdometryNoise {
+    /// Variance of random wheel slip per meter travelled by a wheel (m²/m)
+    pub slip_variance: f32,
+    /// Encoder resolution (ticks per wheel revolution), 0 for ideal encoders
+    pub ticks_per_revolution: f32,
+    /// Systematic error of the left wheel radius assumed by the estimator (0.01 = 1% too large)
+    pub left_wheel_scale_error: f32,
+    /// Systematic error of the right wheel radius assumed by the estimator
+    pub right_wheel_scale_error: f32,
+    /// Systematic error of the track width assumed by the estimator
+    pub track_width_error: f32,
+}
+
+impl OdometryNoise {
+    /// Perfect odometry: no slip, continuous encoders and exact geometry
+    pub const NONE: OdometryNoise = OdometryNoise {
+        slip_variance: 0.0,
+        ticks_per_revolution: 0.0,
+        left_wheel_scale_error: 0.0,
+        right_wheel_scale_error: 0.0,
+        track_width_error: 0.0,
+    };
+}
+
+impl Default for OdometryNoise {
+    fn default() -> Self {
+        OdometryNoise {
+            slip_variance: SLIP_VARIANCE,
+            ticks_per_revolution: ENCODER_TICKS_PER_REVOLUTION,
+            ..OdometryNoise::NONE
+        }
+    }
+}
+
+/// Integration state that is not part of the published message
+#[derive(Debug, Clone, Default)]
+struct OdometryState {
+    /// True wheel angles relative to the chassis (radians), left then right
+    wheel_angles: [f32; 2],
+    /// Wheel angles as last read from the encoders (radians)
+    measured_angles: [f32; 2],
+    /// Heading in the odom frame (radians)
+    yaw: f32,
+    /// Covariance of (x, y, yaw)
+    covariance: Mat3,
+}
+
+/// Wheel odometry of a differential-drive chassis, laid out like ROS `nav_msgs/Odometry`.
+///
+/// The pose is integrated from the wheel joint velocities in the ROS `odom` frame (x forward at
+/// start-up, y left, z up); it drifts from the ground-truth `Transform` according to `noise`.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Odometry {
+    /// Elapsed simulation time of the last update (seconds)
+    pub stamp: f32,
+    /// Frame the pose is expressed in
+    pub frame_id: String,
+    /// Frame the twist is expressed in
+    pub child_frame_id: String,
+    /// Position in the odom frame (meters)
+    pub position: Vec3,
+    /// Orientation in the odom frame
+    pub orientation: Quat,
+    /// Row-major 6x6 covariance of (x, y, z, roll, pitch, yaw)
+    pub pose_covariance: [f32; 36],
+    /// Velocity in the child frame
+    pub twist: Twist,
+    /// Row-major 6x6 covariance of the twist
+    pub twist_covariance: [f32; 36],
+    /// Nominal wheel radius (meters)
+    pub wheel_radius: f32,
+    /// Nominal distance between the wheel contact points (meters)
+    pub track_width: f32,
+    /// Error model applied to the estimate
+    pub noise: OdometryNoise,
+    #[reflect(ignore)]
+    state: OdometryState,
+}
+
+impl Odometry {
+    pub fn new(wheel_radius: f32, track_width: f32) -> Self {
+        Odometry {
+            stamp: 0.0,
+            frame_id: "odom".to_string(),
+            child_frame_id: "base_link".to_string(),
+            position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            pose_covariance: [0.0; 36],
+            twist: Twist::default(),
+            twist_covariance: [0.0; 36],
+            wheel_radius,
+            track_width,
+            noise: OdometryNoise::default(),
+            state: OdometryState::default(),
+        }
+    }
+
+    /// Heading in the odom frame (radians)
+    #[allow(dead_code)]
+    pub fn yaw(&self) -> f32 {
+        self.state.yaw
+    }
+
+    /// Read the encoders and integrate the wheel travel since the last update
+    fn integrate(&mut self, dt: f32) {
+        let noise = &self.noise;
+        let scale_errors = [noise.left_wheel_scale_error, noise.right_wheel_scale_error];
+        let track_width = self.track_width * (1.0 + noise.track_width_error);
+
+        let mut rng = rand::thread_rng();
+        let mut distances = [0.0; 2];
+        let mut variances = [0.0; 2];
+        for side in 0..2 {
+            let measured = quantize(self.state.wheel_angles[side], noise.ticks_per_revolution);
+            let angle = measured - self.state.measured_angles[side];
+            self.state.measured_angles[side] = measured;
+
+            let distance = angle * self.wheel_radius * (1.0 + scale_errors[side]);
+            let variance = noise.slip_variance * distance.abs();
+            distances[side] = if variance > 0.0 {
+                distance + Normal::new(0.0, variance.sqrt()).unwrap().sample(&mut rng)
+            } else {
+                distance
+            };
+            variances[side] = variance;
+        }
+
+        let [left, right] = distances;
+        let distance = 0.5 * (left + right);
+        let rotation = (right - left) / track_width;
+        let heading = self.state.yaw + 0.5 * rotation;
+        let (sin, cos) = heading.sin_cos();
+
+        // Propagate the covariance through the motion model
+        let jacobian_pose = Mat3::from_cols(
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(-distance * sin, distance * cos, 1.0),
+        );
+        let lever = distance / (2.0 * track_width);
+        let d_left = Vec3::new(
+            0.5 * cos + lever * sin,
+            0.5 * sin - lever * cos,
+            -1.0 / track_width,
+        );
+        let d_right = Vec3::new(
+            0.5 * cos - lever * sin,
+            0.5 * sin + lever * cos,
+            1.0 / track_width,
+        );
+        let wheel_noise = outer(d_left) * variances[0] + outer(d_right) * variances[1];
+        self.state.covariance =
+            jacobian_pose * self.state.covariance * jacobian_pose.transpose() + wheel_noise;
+
+        self.position += Vec3::new(distance * cos, distance * sin, 0.0);
+        self.state.yaw += rotation;
+        self.orientation = Quat::from_rotation_z(self.state.yaw);
+
+        let covariance = self.state.covariance;
+        for (row, i) in [0, 1, 5].into_iter().enumerate() {
+            for (col, j) in [0, 1, 5].into_iter().enumerate() {
+                self.pose_covariance[i * 6 + j] = covariance.col(col)[row];
+            }
+        }
+
+        if dt > 0.0 {
+            self.twist = Twist {
+                linear: Vec3::new(distance / dt, 0.0, 0.0),
+                angular: Vec3::new(0.0, 0.0, rotation / dt),
+            };
+            let wheel_variance = (variances[0] + variances[1]) / (dt * dt);
+            self.twist_covariance[0] = 0.25 * wheel_variance;
+            self.twist_covariance[35] = wheel_variance / (track_width * track_width);
+        }
+    }
+}
+
+/// Angle reported by an encoder with the given resolution
+fn quantize(angle: f32, ticks_per_revolution: f32) -> f32 {
+    if ticks_per_revolution <= 0.0 {
+        return angle;
+    }
+    let tick = TAU / ticks_per_revolution;
+    (angle / tick).trunc() * tick
+}
+
+fn outer(v: Vec3) -> Mat3 {
+    Mat3::from_cols(v * v.x, v * v.y, v * v.z)
+}
+
+/// Accumulate wheel rotation from the joint velocities and update each chassis' odometry.
+///
+/// Runs after every physics step, so the wheel travel is integrated over the simulated step
+/// rather than the frame.
+pub fn odometry_system(
+    time: Res<Time<Fixed>>,
+    rapier_context: ReadRapierContext,
+    mut odometry_query: Query<&mut Odometry>,
+    wheels: Query<(Entity, &Wheel, &ImpulseJoint, &Velocity)>,
+    chassis_velocities: Query<&Velocity>,
+) {
+    let dt = time.delta_secs();
+    let Ok(rapier_context) = rapier_context.single() else {
+        return;
+    };
+    let body_set = &rapier_context.rigidbody_set;
+
+    for (entity, wheel, joint, wheel_velocity) in wheels.iter() {
+        let Ok(mut odometry) = odometry_query.get_mut(joint.parent) else {
+            continue;
+        };
+        // Global transforms are only propagated once a frame, so the wheel is posed from Rapier
+        let Some(wheel_body) = body_set
+            .entity2body()
+            .get(&entity)
+            .and_then(|handle| body_set.bodies.get(*handle))
+        else {
+            continue;
+        };
+        let chassis_angvel = chassis_velocities
+            .get(joint.parent)
+            .map_or(Vec3::ZERO, |velocity| velocity.angvel);
+
+        // The joint's wheel-side axis is oriented so that positive rotation drives forward
+        let wheel_rotation = iso_to_transform(wheel_body.position()).rotation;
+        let axle = wheel_rotation * joint.data.as_ref().local_axis2();
+        let rate = (wheel_velocity.angvel - chassis_angvel).dot(axle);
+        let side = match wheel {
+            Wheel::Left => 0,
+            Wheel::Right => 1,
+        };
+        odometry.state.wheel_angles[side] += rate * dt;
+    }
+
+    for mut odometry in odometry_query.iter_mut() {
+        odometry.integrate(dt);
+        odometry.stamp = time.elapsed_secs();
+    }
+}
+
+/// Plugin providing simulated wheel odometry
+pub struct OdometryPlugin;
+
+impl Plugin for OdometryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, odometry_system.after(PhysicsSet::Writeback))
+            .register_type::<Odometry>();
+    }
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use bevy_rapier3d::dynamics::TypedJoint;
+use bevy_rapier3d::utils::iso_to_transform;
+use crate::kinematics::forward_kinematics;
 use crate::robot_drag::{Draggable, DraggableBundle};
 use bevy::ecs::event::EventReader;
 
@@ -18,6 +20,21 @@ pub enum ArmLink {
     GripperBase,
 }
 
+impl ArmLink {
+    /// Index in [`UR3E_JOINTS`] and [`JointTargets`] of the joint that drives this link
+    pub fn joint_index(&self) -> Option<usize> {
+        match self {
+            ArmLink::Link1 => Some(0),
+            ArmLink::Link2 => Some(1),
+            ArmLink::Link3 => Some(2),
+            ArmLink::Link4 => Some(3),
+            ArmLink::Link5 => Some(4),
+            ArmLink::Link6 => Some(5),
+            ArmLink::Base | ArmLink::GripperBase => None,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct SimpleGripper {
     pub is_open: bool,
@@ -56,8 +73,83 @@ impl Default for DragState {
     }
 }
 
-const MOTOR_STIFFNESS: f32 = 10000.0;
-const MOTOR_DAMPING: f32 = 1000.0;
+/// Number of revolute joints on the UR3e, from the base (Link1) to the last wrist (Link6)
+pub const ARM_JOINT_COUNT: usize = 6;
+
+/// A revolute joint of the UR3e and its working range in radians
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArmJoint {
+    pub name: &'static str,
+    pub lower: f32,
+    pub upper: f32,
+}
+
+impl ArmJoint {
+    /// Clamps a target position to the joint's working range
+    pub fn clamp(&self, position: f32) -> f32 {
+        position.clamp(self.lower, self.upper)
+    }
+}
+
+const FULL_TURN: f32 = 2.0 * std::f32::consts::PI;
+
+/// UR3e joints with their working ranges from the datasheet: ±360° for every joint except
+/// wrist 3, which turns without limit
+pub const UR3E_JOINTS: [ArmJoint; ARM_JOINT_COUNT] = [
+    ArmJoint { name: "shoulder_pan_joint", lower: -FULL_TURN, upper: FULL_TURN },
+    ArmJoint { name: "shoulder_lift_joint", lower: -FULL_TURN, upper: FULL_TURN },
+    ArmJoint { name: "elbow_joint", lower: -FULL_TURN, upper: FULL_TURN },
+    ArmJoint { name: "wrist_1_joint", lower: -FULL_TURN, upper: FULL_TURN },
+    ArmJoint { name: "wrist_2_joint", lower: -FULL_TURN, upper: FULL_TURN },
+    ArmJoint { name: "wrist_3_joint", lower: f32::NEG_INFINITY, upper: f32::INFINITY },
+];
+
+/// Rapier turns a generic joint's frame so that its X axis lies along `local_axis1` and
+/// `local_axis2`; the free rotation of every arm joint is therefore `AngX`, whichever body
+/// axis the joint turns about.
+const JOINT_MOTOR_AXIS: JointAxis = JointAxis::AngX;
+
+/// Stiffness (N·m/rad) and damping (N·m·s/rad) of a joint's position motor.
+///
+/// The motors are force based: an acceleration-based motor scales its torque by the tiny
+/// inertia of the link it moves and cannot hold the arm up against gravity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointGains {
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl Default for JointGains {
+    fn default() -> Self {
+        Self {
+            stiffness: 500.0,
+            damping: 50.0,
+        }
+    }
+}
+
+/// Position motor gains of each arm joint, indexed like [`UR3E_JOINTS`]
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct ArmJointGains(pub [JointGains; ARM_JOINT_COUNT]);
+
+impl ArmJointGains {
+    /// The same gains for every joint
+    pub fn uniform(gains: JointGains) -> Self {
+        Self([gains; ARM_JOINT_COUNT])
+    }
+}
+
+/// Solver iterations the arm's links run on top of the world's; with Rapier's default 4 the
+/// joint chain sags under gravity
+const ARM_ADDITIONAL_SOLVER_ITERATIONS: usize = 12;
+
+/// Builds an arm joint without contacts between its two links, which touch at the joint and
+/// would otherwise rub against each other as it turns
+fn arm_joint(builder: GenericJointBuilder) -> TypedJoint {
+    let mut joint = builder.build();
+    joint.set_contacts_enabled(false);
+    TypedJoint::GenericJoint(joint)
+}
 
 #[allow(unused_variables)]
 fn spawn_ur3e_arm(
@@ -66,6 +158,7 @@ fn spawn_ur3e_arm(
     robot_transform: Transform,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
+    gains: &ArmJointGains,
 ) {
     const BASE_HEIGHT: f32 = 0.0949500;
     const BASE_MASS: f32 = 0.1;
@@ -131,17 +224,19 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Y)
         .local_anchor1(Vec3::new(0.0, 0.5 * BASE_HEIGHT, 0.0))
         .local_anchor2(Vec3::new(0.0, -0.5 * LINK1_HEIGHT, 0.0))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[0].stiffness, gains.0[0].damping);
 
     let link1_transform = robot_transform * Transform::from_xyz(0.0, 0.5 * LINK1_HEIGHT + LINK1_OFFSET, 0.0);
     let link1 = commands.spawn(ArmLink::Link1)
         .insert(link1_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK1_HEIGHT, LINK1_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK1_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(base, TypedJoint::GenericJoint(base_link1_joint.build())))
+        .insert(ImpulseJoint::new(base, arm_joint(base_link1_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link1_transform })
@@ -164,17 +259,19 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Z)
         .local_anchor1(Vec3::new(0.0, -0.0006427, -0.062950))
         .local_anchor2(Vec3::new(0.0, -0.1179571, 0.056900))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[1].stiffness, gains.0[1].damping);
 
     let link2_transform = robot_transform * Transform::from_xyz(0.0, 0.5 * LINK2_HEIGHT + LINK2_OFFSET, -LINK2_Z_OFFSET);
     let link2 = commands.spawn(ArmLink::Link2)
         .insert(link2_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK2_HEIGHT, LINK2_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK2_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(link1, TypedJoint::GenericJoint(link1_link2_joint.build())))
+        .insert(ImpulseJoint::new(link1, arm_joint(link1_link2_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link2_transform })
@@ -195,17 +292,19 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Z)
         .local_anchor1(Vec3::new(0.0, 0.1255929, 0.046300))
         .local_anchor2(Vec3::new(0.0, -0.10400308, -0.046550))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[2].stiffness, gains.0[2].damping);
 
     let link3_transform = robot_transform * Transform::from_xyz(0.0, 0.5 * LINK3_HEIGHT + LINK3_OFFSET, -LINK3_Z_OFFSET);
     let link3 = commands.spawn(ArmLink::Link3)
         .insert(link3_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK3_HEIGHT, LINK3_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK3_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(link2, TypedJoint::GenericJoint(link2_link3_joint.build())))
+        .insert(ImpulseJoint::new(link2, arm_joint(link2_link3_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link3_transform })
@@ -229,17 +328,19 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Z)
         .local_anchor1(Vec3::new(0.0, 0.10919692, -0.043100))
         .local_anchor2(Vec3::new(0.0, 0.00172307, 0.060950))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[3].stiffness, gains.0[3].damping);
 
     let link4_transform = robot_transform * Transform::from_xyz(0.0, 0.5 * LINK4_HEIGHT + LINK4_OFFSET, -LINK4_Z_OFFSET);
     let link4 = commands.spawn(ArmLink::Link4)
         .insert(link4_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK4_HEIGHT, LINK4_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK4_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(link3, TypedJoint::GenericJoint(link3_link4_joint.build())))
+        .insert(ImpulseJoint::new(link3, arm_joint(link3_link4_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link4_transform })
@@ -263,17 +364,19 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Y)
         .local_anchor1(Vec3::new(0.0, 0.04482307, 0.0))
         .local_anchor2(Vec3::new(0.0, -0.03725884, 0.0))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[4].stiffness, gains.0[4].damping);
 
     let link5_transform = robot_transform * Transform::from_xyz(0.0, 0.5 * LINK5_HEIGHT + LINK5_OFFSET, -LINK5_Z_OFFSET);
     let link5 = commands.spawn(ArmLink::Link5)
         .insert(link5_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK5_HEIGHT, LINK5_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK5_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(link4, TypedJoint::GenericJoint(link4_link5_joint.build())))
+        .insert(ImpulseJoint::new(link4, arm_joint(link4_link5_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link5_transform })
@@ -297,7 +400,8 @@ fn spawn_ur3e_arm(
         .local_axis2(Vec3::Y)
         .local_anchor1(Vec3::new(0.0, 0.00499116, -0.043100))
         .local_anchor2(Vec3::new(0.0, 0.024500, 0.0))
-        .motor_position(JointAxis::AngX, 0.0, MOTOR_STIFFNESS, MOTOR_DAMPING);
+        .motor_model(JOINT_MOTOR_AXIS, MotorModel::ForceBased)
+        .motor_position(JOINT_MOTOR_AXIS, 0.0, gains.0[5].stiffness, gains.0[5].damping);
 
     let link6_transform = robot_transform * Transform::from_xyz(0.0, LINK6_OFFSET, -LINK6_Z_OFFSET)
         .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2));
@@ -305,10 +409,11 @@ fn spawn_ur3e_arm(
         .insert(link6_transform)
         .insert(Visibility::default())
         .insert(RigidBody::Dynamic)
+        .insert(AdditionalSolverIterations(ARM_ADDITIONAL_SOLVER_ITERATIONS))
         .insert(Collider::cylinder(0.5 * LINK6_HEIGHT, LINK6_RADIUS))
         .insert(ColliderMassProperties::Mass(LINK6_MASS))
         .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
-        .insert(ImpulseJoint::new(link5, TypedJoint::GenericJoint(link5_link6_joint.build())))
+        .insert(ImpulseJoint::new(link5, arm_joint(link5_link6_joint)))
         .insert(Draggable)
         .insert(DraggableBundle::default())
         .insert(OriginalTransform { transform: link6_transform })
@@ -416,9 +521,9 @@ fn spawn_simple_gripper(
         ..default()
     });
 
-    // Calculate proper gripper position - at the end of Link6
+    // Calculate proper gripper position - on the tool flange, the end face of Link6 away from Link5
     const LINK6_HEIGHT: f32 = 0.049000; // From earlier definition
-    let gripper_z_offset = LINK6_HEIGHT * 0.5; // Position at the end of Link6
+    let flange_offset = -0.5 * LINK6_HEIGHT;
 
     // Spawn gripper as child of Link6
     commands.entity(parent_entity).with_children(|commands| {
@@ -429,7 +534,7 @@ fn spawn_simple_gripper(
                 is_open: false,
                 grip_strength: 1.0,
             },
-            Transform::from_xyz(0.0, 0.0, gripper_z_offset), // Position at end of Link6
+            Transform::from_xyz(0.0, flange_offset, 0.0), // Position on the tool flange
             Visibility::default(),
             // Add sensor collider for detection only
             Collider::cuboid(0.03, 0.02, 0.04), // Collider for gripper pickup detection
@@ -452,12 +557,8 @@ fn spawn_simple_gripper(
 }
 
 
-pub fn setup(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
+/// Camera and light for the arm scene; not needed when running headless
+pub fn setup_camera_and_light(mut commands: Commands) {
     // Camera
     let camera_translation = Vec3::new(2.0, 2.0, 2.0);
     let camera_focus = Vec3::ZERO;
@@ -476,69 +577,199 @@ pub fn setup(
         ..default()
     })
     .insert(Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -0.5, -0.5, 0.0)));
+}
 
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    gains: Res<ArmJointGains>,
+) {
     // Ground plane - make it much larger to cover the entire workspace
     commands.spawn((
         Mesh3d(meshes.add(Plane3d::default().mesh().size(100.0, 100.0))),
         MeshMaterial3d(materials.add(Color::srgb(0.3, 0.5, 0.3))),
         Transform::default(),
         RigidBody::Fixed,
-        Collider::cuboid(50.0, 0.1, 50.0),
+        // Top face level with the plane the blocks rest on and the base stands on; centred on the
+        // plane, the box reached 10 cm above it and Link1 scraped it as it turned
+        Collider::compound(vec![(Vec3::new(0.0, -0.1, 0.0), Quat::IDENTITY, Collider::cuboid(50.0, 0.1, 50.0))]),
         CollisionGroups::new(Group::GROUP_1, Group::ALL),
     ));
 
     // Spawn robotic arm
-    spawn_ur3e_arm(&mut commands, &asset_server, Transform::IDENTITY, &mut meshes, &mut materials);
+    spawn_ur3e_arm(&mut commands, &asset_server, Transform::IDENTITY, &mut meshes, &mut materials, &gains);
 
     // Add some blocks for the gripper to pick up
     spawn_pickup_blocks(&mut commands, &mut meshes, &mut materials);
 }
 
-// Track current target positions for each joint
+/// Target position of each arm joint in radians, indexed like [`UR3E_JOINTS`], and the joint
+/// the keyboard moves
 #[derive(Resource, Default)]
 pub struct JointTargets {
-    pub positions: Vec<f32>,
+    pub positions: [f32; ARM_JOINT_COUNT],
+    pub active: usize,
+}
+
+impl JointTargets {
+    /// Sets a joint's target, clamped to its working range
+    pub fn set(&mut self, joint: usize, position: f32) {
+        self.positions[joint] = UR3E_JOINTS[joint].clamp(position);
+    }
 }
 
+/// Keys 1-6 select the active joint; the up and down arrows turn it
 pub fn keyboard_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
     mut joint_targets: ResMut<JointTargets>,
-    mut joint_query: Query<(&mut ImpulseJoint, &ArmLink)>,
 ) {
-    // Initialize joint targets if empty
-    if joint_targets.positions.is_empty() {
-        joint_targets.positions = vec![0.0; 6]; // 6 joints
+    const JOINT_KEYS: [KeyCode; ARM_JOINT_COUNT] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+    ];
+    const JOG_SPEED: f32 = 0.3; // rad/s
+
+    if let Some(joint) = JOINT_KEYS.iter().position(|key| keyboard_input.just_pressed(*key)) {
+        joint_targets.active = joint;
+        info!("Controlling arm joint {} ({})", joint + 1, UR3E_JOINTS[joint].name);
     }
 
-    // Only control the first joint for now to prevent unrealistic behavior
+    let mut direction = 0.0;
     if keyboard_input.pressed(KeyCode::ArrowUp) {
-        joint_targets.positions[0] += 0.005; // Smaller increments for stability
-        joint_targets.positions[0] = joint_targets.positions[0].clamp(-1.57, 1.57); // ±90 degrees
+        direction += 1.0;
     }
     if keyboard_input.pressed(KeyCode::ArrowDown) {
-        joint_targets.positions[0] -= 0.005;
-        joint_targets.positions[0] = joint_targets.positions[0].clamp(-1.57, 1.57); // ±90 degrees
+        direction -= 1.0;
+    }
+    if direction != 0.0 {
+        let joint = joint_targets.active;
+        let position = joint_targets.positions[joint] + direction * JOG_SPEED * time.delta_secs();
+        joint_targets.set(joint, position);
+    }
+}
+
+/// Drives each arm joint's position motor towards its target whenever targets or gains change
+pub fn drive_arm_joints(
+    joint_targets: Res<JointTargets>,
+    gains: Res<ArmJointGains>,
+    mut joint_query: Query<(&mut ImpulseJoint, &ArmLink)>,
+) {
+    if !joint_targets.is_changed() && !gains.is_changed() {
+        return;
     }
 
-    // Apply motor control only to Link1 for now
     for (mut joint, arm_link) in joint_query.iter_mut() {
-        if matches!(arm_link, ArmLink::Link1) {
-            if let TypedJoint::GenericJoint(generic_joint) = &mut joint.data {
-                // Use lower stiffness and higher damping for stability
-                const MOTOR_STIFFNESS: f32 = 5000.0;
-                const MOTOR_DAMPING: f32 = 2000.0;
-                
-                generic_joint.set_motor_position(
-                    JointAxis::AngX, 
-                    joint_targets.positions[0], 
-                    MOTOR_STIFFNESS, 
-                    MOTOR_DAMPING
-                );
-            }
+        let Some(index) = arm_link.joint_index() else {
+            continue;
+        };
+        if let TypedJoint::GenericJoint(generic_joint) = &mut joint.data {
+            generic_joint.set_motor_position(
+                JOINT_MOTOR_AXIS,
+                joint_targets.positions[index],
+                gains.0[index].stiffness,
+                gains.0[index].damping,
+            );
         }
     }
 }
 
+/// Measured state of each arm joint, indexed like [`UR3E_JOINTS`], as of the last physics step.
+///
+/// Kept as a resource and also sent as an event after every step.
+#[derive(Resource, Event, Debug, Clone, Default, PartialEq)]
+pub struct JointStates {
+    /// Elapsed simulation time of the measurement (seconds)
+    pub stamp: f32,
+    /// Joint angles (rad), counted on past a full turn
+    pub positions: [f32; ARM_JOINT_COUNT],
+    /// Joint velocities (rad/s)
+    pub velocities: [f32; ARM_JOINT_COUNT],
+    /// Torque the joint motors apply to the links they drive (N·m), positive turning the joint
+    /// towards positive positions
+    pub efforts: [f32; ARM_JOINT_COUNT],
+    /// Pose of the tool flange in the arm's frame, from the joint positions
+    pub flange_pose: Transform,
+}
+
+/// Wraps an angle into [-π, π)
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + std::f32::consts::PI).rem_euclid(2.0 * std::f32::consts::PI) - std::f32::consts::PI
+}
+
+/// System that measures every arm joint from the Rapier bodies it connects.
+///
+/// The position is the twist about the joint axis between the joint frames on the two bodies,
+/// the velocity is its change over the step, and the effort is the torque of the joint motor.
+/// Run it in `FixedUpdate` after `PhysicsSet::Writeback`, so it sees every physics step.
+pub fn joint_state_system(
+    time: Res<Time<Fixed>>,
+    timestep_mode: Res<TimestepMode>,
+    rapier_context: ReadRapierContext,
+    arm_links: Query<(Entity, &ArmLink), With<ImpulseJoint>>,
+    mut joint_states: ResMut<JointStates>,
+    mut joint_state_events: EventWriter<JointStates>,
+) {
+    let Ok(rapier_context) = rapier_context.single() else {
+        return;
+    };
+    let integration_parameters = &rapier_context.simulation.integration_parameters;
+    let dt = integration_parameters.dt;
+    let substeps = match *timestep_mode {
+        TimestepMode::Fixed { substeps, .. }
+        | TimestepMode::Variable { substeps, .. }
+        | TimestepMode::Interpolated { substeps, .. } => substeps,
+    };
+    let bodies = &rapier_context.rigidbody_set.bodies;
+
+    for (entity, arm_link) in arm_links.iter() {
+        let Some(index) = arm_link.joint_index() else {
+            continue;
+        };
+        let Some(joint) = rapier_context
+            .joints
+            .entity2impulse_joint()
+            .get(&entity)
+            .and_then(|handle| rapier_context.joints.impulse_joints.get(*handle))
+        else {
+            continue;
+        };
+        let (Some(body1), Some(body2)) = (bodies.get(joint.body1), bodies.get(joint.body2)) else {
+            continue;
+        };
+        // A physics step is split into Rapier steps, each solved in one substep per solver
+        // iteration of the link's island; the motor impulse is the last substep's
+        let iterations = integration_parameters.num_solver_iterations.get() + body2.additional_solver_iterations();
+        let substep = dt / (substeps * iterations) as f32;
+
+        let frame1 = iso_to_transform(&(body1.position() * joint.data.local_frame1)).rotation;
+        let frame2 = iso_to_transform(&(body2.position() * joint.data.local_frame2)).rotation;
+        let relative = frame1.inverse() * frame2;
+        let twist = 2.0 * relative.x.atan2(relative.w);
+        let last = joint_states.positions[index];
+        let position = last + wrap_angle(twist - last);
+        joint_states.positions[index] = position;
+
+        // Rapier's body velocities keep the drift its joint correction cancels, so the velocity
+        // is differentiated from the position, like an encoder's
+        if dt > 0.0 {
+            joint_states.velocities[index] = (position - last) / dt;
+            // Rapier's motor impulse is the one applied to the parent link
+            joint_states.efforts[index] = -joint.data.motors[JOINT_MOTOR_AXIS as usize].impulse / substep;
+        }
+    }
+
+    joint_states.flange_pose = forward_kinematics(joint_states.positions);
+    joint_states.stamp = time.elapsed_secs();
+    joint_state_events.write(joint_states.clone());
+}
+
 pub fn simple_gripper_control(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut commands: Commands,
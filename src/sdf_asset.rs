@@ -0,0 +1,182 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::sdf_loader::{
+    SdfError, SdfGeometry, SdfWorld, is_gltf, parse_sdf_file_with, resolve_mesh_uri,
+};
+use crate::sdf_model_path::{MODEL_ASSET_SOURCE, SdfModelPath};
+
+/// Error raised while loading an SDF (`.sdf`) file
+#[derive(Debug)]
+pub enum SdfAssetError {
+    Io(std::io::Error),
+    Parse(SdfError),
+}
+
+impl fmt::Display for SdfAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdfAssetError::Io(e) => write!(f, "Failed to read SDF file: {}", e),
+            SdfAssetError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SdfAssetError {}
+
+impl From<std::io::Error> for SdfAssetError {
+    fn from(e: std::io::Error) -> Self {
+        SdfAssetError::Io(e)
+    }
+}
+
+/// Loads `.sdf` files as [`SdfWorld`] assets.
+///
+/// Every STL, OBJ and COLLADA mesh and heightmap image the world references is loaded along with
+/// it as a sub-asset, labeled with its asset path, so editing one reloads the world too. glTF files are
+/// scenes and are loaded when the world is spawned. Included models are found on the
+/// [`SdfModelPath`] the loader was created with and read through the `model://` asset source, so
+/// editing an included model reloads the world as well.
+pub struct SdfWorldLoader {
+    model_path: SdfModelPath,
+}
+
+impl SdfWorldLoader {
+    /// Asset path of a file on the model path, or of any other file by its absolute path
+    fn asset_path(&self, path: &Path) -> AssetPath<'static> {
+        let model_file = self
+            .model_path
+            .dirs
+            .iter()
+            .find_map(|dir| path.strip_prefix(dir).ok());
+        match model_file {
+            Some(model_file) => AssetPath::from_path(model_file)
+                .with_source(MODEL_ASSET_SOURCE)
+                .into_owned(),
+            None => {
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+                AssetPath::from_path(&path).into_owned()
+            }
+        }
+    }
+}
+
+impl FromWorld for SdfWorldLoader {
+    fn from_world(world: &mut World) -> Self {
+        SdfWorldLoader {
+            model_path: world
+                .get_resource::<SdfModelPath>()
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl AssetLoader for SdfWorldLoader {
+    type Asset = SdfWorld;
+    type Settings = ();
+    type Error = SdfAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<SdfWorld, SdfAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let content = String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let file = load_context.path().to_string_lossy().into_owned();
+
+        // Parsing stops at the first included file that has not been read yet; it is read as a
+        // dependency of the world and parsing starts over
+        let mut files: HashMap<PathBuf, Result<String, String>> = HashMap::new();
+        let mut world = loop {
+            let mut unread = None;
+            let mut read_file = |path: &Path| match files.get(path) {
+                Some(Ok(content)) => Ok(content.clone()),
+                Some(Err(message)) => Err(std::io::Error::other(message.clone())),
+                None => {
+                    unread.get_or_insert_with(|| path.to_path_buf());
+                    Err(std::io::Error::other("not read yet"))
+                }
+            };
+            let result = parse_sdf_file_with(&content, &file, &self.model_path, &mut read_file);
+            let Some(path) = unread else {
+                break result.map_err(SdfAssetError::Parse)?;
+            };
+            let content = match load_context.read_asset_bytes(self.asset_path(&path)).await {
+                Ok(bytes) => String::from_utf8(bytes).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            files.insert(path, content);
+        };
+
+        let geometries: Vec<SdfGeometry> = world
+            .models
+            .iter()
+            .flat_map(|model| &model.links)
+            .flat_map(|link| {
+                let visuals = link.visuals.iter().map(|visual| &visual.geometry);
+                let collisions = link.collisions.iter().map(|collision| &collision.geometry);
+                visuals.chain(collisions)
+            })
+            .filter(|geometry| {
+                matches!(
+                    geometry,
+                    SdfGeometry::Mesh { .. } | SdfGeometry::Heightmap { .. }
+                )
+            })
+            .cloned()
+            .collect();
+        for geometry in geometries {
+            match geometry {
+                SdfGeometry::Mesh { uri, .. } => {
+                    if world.meshes.contains_key(&uri) {
+                        continue;
+                    }
+                    let Some(path) = resolve_mesh_uri(&uri).filter(|path| !is_gltf(path)) else {
+                        continue;
+                    };
+                    match load_context.loader().immediate().load::<Mesh>(&path).await {
+                        Ok(mesh) => {
+                            // "://" would read as an asset source in the label
+                            let label = path.replace("://", "/");
+                            let handle = load_context.add_loaded_labeled_asset(label, mesh);
+                            world.meshes.insert(uri, handle);
+                        }
+                        Err(e) => warn!("Failed to load mesh {}: {}", uri, e),
+                    }
+                }
+                SdfGeometry::Heightmap { uri, .. } => {
+                    if world.images.contains_key(&uri) {
+                        continue;
+                    }
+                    let Some(path) = resolve_mesh_uri(&uri) else {
+                        continue;
+                    };
+                    match load_context.loader().immediate().load::<Image>(&path).await {
+                        Ok(image) => {
+                            let label = path.replace("://", "/");
+                            let handle = load_context.add_loaded_labeled_asset(label, image);
+                            world.images.insert(uri, handle);
+                        }
+                        Err(e) => warn!("Failed to load heightmap {}: {}", uri, e),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(world)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sdf"]
+    }
+}
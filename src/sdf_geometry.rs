@@ -0,0 +1,244 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy_rapier3d::geometry::Collider;
+
+use crate::sdf_loader::SdfPolyline;
+
+// Shapes are built in the Y-up frame of their entity: SDF x, y, z become x, -z, y.
+
+/// Ellipsoid mesh with the given SDF radii
+pub fn ellipsoid_mesh(radii: Vec3) -> Mesh {
+    Sphere::new(1.0)
+        .mesh()
+        .build()
+        .scaled_by(Vec3::new(radii.x, radii.z, radii.y))
+}
+
+/// Ellipsoid collider: Rapier has no ellipsoid, so this is the convex hull of a coarse mesh
+pub fn ellipsoid_collider(radii: Vec3) -> Option<Collider> {
+    let mesh = Sphere::new(1.0)
+        .mesh()
+        .uv(16, 8)
+        .scaled_by(Vec3::new(radii.x, radii.z, radii.y));
+    let points: Vec<Vec3> = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(|positions| positions.as_float3())?
+        .iter()
+        .map(|&position| Vec3::from(position))
+        .collect();
+    Collider::convex_hull(&points)
+}
+
+/// Points of an outline with the repeated closing point removed
+fn outline(polyline: &SdfPolyline) -> &[Vec2] {
+    match polyline.points.as_slice() {
+        [first, points @ .., last] if first == last => &polyline.points[..points.len() + 1],
+        points => points,
+    }
+}
+
+/// Mesh of outlines in the SDF XY plane extruded upwards by their height
+pub fn polyline_mesh(polylines: &[SdfPolyline]) -> Mesh {
+    let mut positions: Vec<Vec3> = Vec::new();
+    for polyline in polylines {
+        let points = outline(polyline);
+        if points.len() < 3 {
+            continue;
+        }
+        let bottom = |p: Vec2| Vec3::new(p.x, 0.0, -p.y);
+        let top = |p: Vec2| Vec3::new(p.x, polyline.height, -p.y);
+        let triangles = triangulate_polygon(points);
+        // Caps, facing up and down
+        for &[a, b, c] in &triangles {
+            positions.extend([top(points[a]), top(points[b]), top(points[c])]);
+            positions.extend([bottom(points[a]), bottom(points[c]), bottom(points[b])]);
+        }
+        // Sides, wound to face away from the outline's interior
+        let counter_clockwise = signed_area(points) > 0.0;
+        for i in 0..points.len() {
+            let (mut p, mut q) = (points[i], points[(i + 1) % points.len()]);
+            if !counter_clockwise {
+                std::mem::swap(&mut p, &mut q);
+            }
+            positions.extend([bottom(p), bottom(q), top(q)]);
+            positions.extend([bottom(p), top(q), top(p)]);
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.compute_flat_normals();
+    mesh
+}
+
+/// Collider of extruded outlines: the convex hull of each outline, so concave outlines are filled
+pub fn polyline_collider(polylines: &[SdfPolyline]) -> Option<Collider> {
+    let mut hulls: Vec<Collider> = polylines
+        .iter()
+        .filter_map(|polyline| {
+            let points: Vec<Vec3> = outline(polyline)
+                .iter()
+                .flat_map(|p| {
+                    [
+                        Vec3::new(p.x, 0.0, -p.y),
+                        Vec3::new(p.x, polyline.height, -p.y),
+                    ]
+                })
+                .collect();
+            Collider::convex_hull(&points)
+        })
+        .collect();
+    if hulls.len() > 1 {
+        Some(Collider::compound(
+            hulls
+                .into_iter()
+                .map(|hull| (Vec3::ZERO, Quat::IDENTITY, hull))
+                .collect(),
+        ))
+    } else {
+        hulls.pop()
+    }
+}
+
+/// Twice the signed area of a polygon, positive when its points run counter-clockwise
+fn signed_area(points: &[Vec2]) -> f32 {
+    (0..points.len())
+        .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+        .sum()
+}
+
+/// Triangulates a simple polygon by ear clipping.
+///
+/// Triangles index into `points` and run counter-clockwise. A self-intersecting outline is
+/// triangulated only as far as ears can be found.
+fn triangulate_polygon(points: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if signed_area(points) < 0.0 {
+        remaining.reverse();
+    }
+    let mut triangles = Vec::new();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            [
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ]
+        };
+        let is_ear = |[a, b, c]: [usize; 3]| {
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            let convex = (pb - pa).perp_dot(pc - pb) > 0.0;
+            convex
+                && remaining.iter().all(|&j| {
+                    let p = points[j];
+                    [a, b, c].contains(&j)
+                        || (pb - pa).perp_dot(p - pa) < 0.0
+                        || (pc - pb).perp_dot(p - pb) < 0.0
+                        || (pa - pc).perp_dot(p - pc) < 0.0
+                })
+        };
+        let Some(ear) = (0..n).find(|&i| is_ear(corner(i))) else {
+            break;
+        };
+        triangles.push(corner(ear));
+        remaining.remove(ear);
+    }
+    if let [a, b, c] = remaining[..] {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+/// Heights sampled from a heightmap image, from 0 (black) to 1 (white)
+pub struct HeightGrid {
+    pub rows: usize,
+    pub cols: usize,
+    /// Row-major, from the top row of the image
+    pub heights: Vec<f32>,
+}
+
+impl HeightGrid {
+    /// Reads the heights of a grayscale image; `None` when it is smaller than 2x2 pixels
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let (cols, rows) = (image.width() as usize, image.height() as usize);
+        if rows < 2 || cols < 2 {
+            return None;
+        }
+        let heights = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col as u32, row as u32)))
+            .map(|(x, y)| match image.get_color_at(x, y) {
+                // The raw pixel value, whether or not the image is sRGB encoded
+                Ok(Color::Srgba(color)) => color.red,
+                Ok(color) => color.to_linear().red,
+                Err(_) => 0.0,
+            })
+            .collect();
+        Some(HeightGrid {
+            rows,
+            cols,
+            heights,
+        })
+    }
+
+    /// Position of a sample in a heightmap of the given SDF size, centered on the origin
+    fn position(&self, row: usize, col: usize, size: Vec3) -> Vec3 {
+        // The top row of the image is the +Y edge of the heightmap, which is -Z in Bevy
+        Vec3::new(
+            size.x * (col as f32 / (self.cols - 1) as f32 - 0.5),
+            size.z * self.heights[row * self.cols + col],
+            size.y * (row as f32 / (self.rows - 1) as f32 - 0.5),
+        )
+    }
+}
+
+/// Terrain mesh of a heightmap of the given SDF size
+pub fn heightmap_mesh(grid: &HeightGrid, size: Vec3) -> Mesh {
+    let mut positions = Vec::with_capacity(grid.rows * grid.cols);
+    let mut uvs = Vec::with_capacity(grid.rows * grid.cols);
+    for row in 0..grid.rows {
+        for col in 0..grid.cols {
+            positions.push(grid.position(row, col, size));
+            uvs.push(Vec2::new(
+                col as f32 / (grid.cols - 1) as f32,
+                row as f32 / (grid.rows - 1) as f32,
+            ));
+        }
+    }
+    let mut indices = Vec::with_capacity((grid.rows - 1) * (grid.cols - 1) * 6);
+    for row in 0..grid.rows - 1 {
+        for col in 0..grid.cols - 1 {
+            let i = (row * grid.cols + col) as u32;
+            let below = i + grid.cols as u32;
+            indices.extend([i, below, i + 1, below, below + 1, i + 1]);
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices));
+    mesh.compute_smooth_normals();
+    mesh
+}
+
+/// Heightfield collider of a heightmap of the given SDF size
+pub fn heightmap_collider(grid: &HeightGrid, size: Vec3) -> Collider {
+    // Rapier heightfields are column-major, with rows along Z and columns along X
+    let heights = (0..grid.cols)
+        .flat_map(|col| (0..grid.rows).map(move |row| grid.heights[row * grid.cols + col]))
+        .collect();
+    Collider::heightfield(
+        heights,
+        grid.rows,
+        grid.cols,
+        Vec3::new(size.x, size.z, size.y),
+    )
+}
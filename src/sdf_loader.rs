@@ -1,22 +1,50 @@
+use bevy::asset::LoadState;
 use bevy::prelude::*;
-use bevy_rapier3d::geometry::{Collider, CollisionGroups, Group};
-use bevy_rapier3d::dynamics::{RigidBody, AdditionalMassProperties, Damping};
+use bevy_rapier3d::geometry::{
+    Collider, ColliderMassProperties, CollisionGroups, ComputedColliderShape, Friction, Group,
+    Restitution, TriMeshFlags, VHACDParameters,
+};
+use bevy_rapier3d::plugin::{PhysicsSet, RapierConfiguration, ReadRapierContext};
+use bevy_rapier3d::utils::iso_to_transform;
+use bevy_rapier3d::dynamics::{
+    RigidBody, AdditionalMassProperties, CoefficientCombineRule, Damping, GenericJoint,
+    GenericJointBuilder, ImpulseJoint, JointAxesMask, JointAxis, MassProperties, MotorModel,
+    TypedJoint,
+};
+use bevy_rapier3d::rapier::dynamics::{
+    MassProperties as RapierMassProperties, RigidBody as RapierRigidBody,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::collada::ColladaLoader;
+use crate::sdf_asset::SdfWorldLoader;
+use crate::sdf_geometry::{
+    HeightGrid, ellipsoid_collider, ellipsoid_mesh, heightmap_collider, heightmap_mesh,
+    polyline_collider, polyline_mesh,
+};
+use crate::sdf_model_path::{MODEL_ASSET_SOURCE, SdfModelPath, model_sdf_file};
 use quick_xml::Reader;
 use quick_xml::events::{Event, BytesStart};
 
 /// SDF World structure representing a complete simulation world
-#[derive(Debug, Clone)]
+#[derive(Asset, TypePath, Debug, Clone, PartialEq)]
 pub struct SdfWorld {
     pub name: String,
     pub models: Vec<SdfModel>,
     pub lights: Vec<SdfLight>,
     pub physics: Option<SdfPhysics>,
     pub scene: Option<SdfScene>,
+    /// Meshes loaded along with the world asset, by URI
+    pub meshes: HashMap<String, Handle<Mesh>>,
+    /// Heightmap images loaded along with the world asset, by URI
+    pub images: HashMap<String, Handle<Image>>,
 }
 
 /// SDF Model structure representing a model in the world
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfModel {
     pub name: String,
     pub static_: bool,
@@ -26,19 +54,19 @@ pub struct SdfModel {
 }
 
 /// SDF Link structure representing a link in a model
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfLink {
     pub name: String,
     pub pose: SdfPose,
-    pub visual: Option<SdfVisual>,
-    pub collision: Option<SdfCollision>,
+    pub visuals: Vec<SdfVisual>,
+    pub collisions: Vec<SdfCollision>,
     pub inertial: Option<SdfInertial>,
     pub linear_damping: Option<f32>,
     pub angular_damping: Option<f32>,
 }
 
 /// SDF Visual structure for visual representation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfVisual {
     pub name: String,
     pub pose: SdfPose,
@@ -47,25 +75,74 @@ pub struct SdfVisual {
 }
 
 /// SDF Collision structure for collision detection
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfCollision {
     pub name: String,
     pub pose: SdfPose,
     pub geometry: SdfGeometry,
+    pub surface: Option<SdfSurface>,
+}
+
+/// SDF Surface structure with the contact properties of a collision
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfSurface {
+    /// Friction coefficient along the first friction direction
+    pub mu: f32,
+    /// Friction coefficient along the second friction direction
+    pub mu2: f32,
+    pub restitution_coefficient: f32,
+    /// Contact stiffness (N/m) and damping (N·s/m); Rapier has no per-collider equivalent
+    pub kp: Option<f32>,
+    pub kd: Option<f32>,
+}
+
+impl Default for SdfSurface {
+    fn default() -> Self {
+        Self {
+            mu: 1.0,
+            mu2: 1.0,
+            restitution_coefficient: 0.0,
+            kp: None,
+            kd: None,
+        }
+    }
 }
 
 /// SDF Geometry types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SdfGeometry {
     Box { size: Vec3 },
     Sphere { radius: f32 },
     Cylinder { radius: f32, length: f32 },
+    /// Cylinder with hemispherical ends; `length` excludes the ends
+    Capsule { radius: f32, length: f32 },
+    Ellipsoid { radii: Vec3 },
     Plane { normal: Vec3, size: Vec2 },
     Mesh { uri: String, scale: Option<Vec3> },
+    /// Outlines extruded along Z
+    Polyline { polylines: Vec<SdfPolyline> },
+    /// Terrain from a grayscale image, `size` across and `size.z` high, centered on `pos`
+    Heightmap { uri: String, size: Vec3, pos: Vec3 },
+}
+
+/// Outline in the XY plane, extruded from Z = 0 up to `height`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfPolyline {
+    pub points: Vec<Vec2>,
+    pub height: f32,
+}
+
+impl Default for SdfPolyline {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            height: 1.0,
+        }
+    }
 }
 
 /// SDF Material structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfMaterial {
     pub ambient: Option<Color>,
     pub diffuse: Option<Color>,
@@ -74,34 +151,129 @@ pub struct SdfMaterial {
 }
 
 /// SDF Pose structure
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct SdfPose {
     pub xyz: Vec3,
     pub rpy: Vec3, // roll, pitch, yaw in radians
 }
 
+/// How the rotation of a `<pose>` is written, as its `rotation_format` and `degrees` attributes say
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoseRotation {
+    /// Roll, pitch and yaw in radians
+    #[default]
+    Radians,
+    /// Roll, pitch and yaw in degrees
+    Degrees,
+    /// Quaternion, as x y z w
+    Quaternion,
+}
+
+impl PoseRotation {
+    /// Rotation format of a `<pose>` start tag; `degrees` only applies to Euler angles
+    pub fn from_attributes(e: &BytesStart) -> Result<Self, String> {
+        let degrees = get_attribute(e, "degrees").map(|degrees| parse_bool(&degrees)).transpose()?;
+        match get_attribute(e, "rotation_format").as_deref() {
+            None | Some("euler_rpy") if degrees == Some(true) => Ok(PoseRotation::Degrees),
+            None | Some("euler_rpy") => Ok(PoseRotation::Radians),
+            Some("quat_xyzw") => Ok(PoseRotation::Quaternion),
+            Some(other) => Err(format!("unknown rotation format {:?}", other)),
+        }
+    }
+}
+
 /// SDF Joint structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfJoint {
     pub name: String,
-    pub joint_type: String,
-    pub parent: String,
+    pub joint_type: String, // revolute, continuous, prismatic, fixed or ball
+    pub parent: String,     // link name, or "world"
     pub child: String,
-    pub pose: SdfPose,
+    pub pose: SdfPose,      // joint frame relative to the child link
+    pub axis: Option<SdfAxis>,
+}
+
+/// SDF Joint axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfAxis {
+    pub xyz: Vec3, // in the joint frame
+    pub limit: Option<SdfJointLimit>,
+    pub dynamics: Option<SdfJointDynamics>,
+}
+
+/// SDF Joint limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfJointLimit {
+    pub lower: f32, // radians or meters
+    pub upper: f32,
+    pub effort: f32,   // N·m or N, negative for unlimited
+    pub velocity: f32, // rad/s or m/s, negative for unlimited
+}
+
+/// SDF Joint dynamics
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfJointDynamics {
+    pub damping: f32,  // viscous, N·m·s/rad or N·s/m
+    pub friction: f32, // Coulomb, N·m or N
 }
 
 /// SDF Light structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfLight {
     pub name: String,
     pub light_type: String,
     pub pose: SdfPose,
     pub diffuse: Color,
     pub specular: Color,
+    pub cast_shadows: bool,
+    pub intensity: f32,
+    pub direction: Vec3, // in the light frame, for spot and directional lights
+    pub attenuation: SdfAttenuation,
+    pub spot: Option<SdfSpot>,
 }
 
-/// SDF Physics structure
-#[derive(Debug, Clone)]
+impl SdfLight {
+    /// A light with the SDF specification defaults
+    fn new(name: String, light_type: String) -> Self {
+        Self {
+            name,
+            light_type,
+            pose: SdfPose::default(),
+            diffuse: Color::WHITE,
+            specular: Color::srgb(0.1, 0.1, 0.1),
+            cast_shadows: false,
+            intensity: 1.0,
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            attenuation: SdfAttenuation {
+                range: 10.0,
+                constant: 1.0,
+                linear: 1.0,
+                quadratic: 0.0,
+            },
+            spot: None,
+        }
+    }
+}
+
+/// SDF Light attenuation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfAttenuation {
+    pub range: f32,
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+/// SDF Spot light cone
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfSpot {
+    pub inner_angle: f32, // full cone angles in radians
+    pub outer_angle: f32,
+    pub falloff: f32,
+}
+
+/// SDF Physics structure; the physics of the spawned world is kept as a resource
+#[derive(Resource, Debug, Clone, PartialEq)]
 pub struct SdfPhysics {
     pub name: String,
     pub max_step_size: f32,
@@ -110,15 +282,28 @@ pub struct SdfPhysics {
     pub gravity: Vec3,
 }
 
+impl Default for SdfPhysics {
+    /// SDF defaults, used for worlds without physics settings
+    fn default() -> Self {
+        Self {
+            name: "default_physics".to_string(),
+            max_step_size: 0.001,
+            real_time_factor: 1.0,
+            real_time_update_rate: 1000.0,
+            gravity: Vec3::new(0.0, 0.0, -9.8),
+        }
+    }
+}
+
 /// SDF Scene structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfScene {
     pub ambient: Color,
     pub background: Color,
 }
 
 /// SDF Inertial structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SdfInertial {
     pub mass: f32,
     pub ixx: f32,
@@ -130,10 +315,43 @@ pub struct SdfInertial {
     pub pose: SdfPose,
 }
 
+/// An `<include>` of a model, with the overrides it applies
+#[derive(Debug, Default)]
+struct SdfInclude {
+    uri: String,
+    name: Option<String>,
+    pose: Option<SdfPose>,
+    static_: Option<bool>,
+}
+
+impl SdfInertial {
+    /// Whether an inertia tensor was given
+    fn has_inertia(&self) -> bool {
+        [self.ixx, self.iyy, self.izz, self.ixy, self.ixz, self.iyz].iter().any(|&v| v != 0.0)
+    }
+}
+
+/// Elements the parser reads; any other element is skipped together with its contents
+pub const SDF_ELEMENTS: &[&str] = &[
+    "sdf", "world", "model", "link", "visual", "collision", "geometry",
+    "box", "sphere", "cylinder", "capsule", "ellipsoid", "plane", "mesh", "polyline", "heightmap",
+    "uri", "scale", "size", "radius", "length", "radii", "normal", "point", "height", "pos",
+    "material", "ambient", "diffuse", "specular", "emissive",
+    "pose", "static", "inertial", "mass", "inertia", "ixx", "iyy", "izz", "ixy", "ixz", "iyz",
+    "velocity_decay", "linear", "angular",
+    "light", "attenuation", "range", "constant", "quadratic", "spot", "inner_angle", "outer_angle",
+    "falloff", "cast_shadows", "intensity", "direction",
+    "joint", "parent", "child", "axis", "xyz", "limit", "lower", "upper", "effort", "velocity",
+    "dynamics", "damping", "friction",
+    "include", "name",
+    "physics", "gravity", "max_step_size", "real_time_factor", "real_time_update_rate",
+    "scene", "background",
+    "surface", "ode", "bounce", "restitution_coefficient", "contact", "mu", "mu2", "kp", "kd",
+];
+
 /// XML parsing context
 #[derive(Debug)]
 struct XmlContext {
-    current_element: String,
     current_model: Option<SdfModel>,
     current_link: Option<SdfLink>,
     current_visual: Option<SdfVisual>,
@@ -141,15 +359,24 @@ struct XmlContext {
     current_inertial: Option<SdfInertial>,
     current_geometry: Option<SdfGeometry>,
     current_material: Option<SdfMaterial>,
-    current_pose: Option<SdfPose>,
+    /// Rotation format of the open `<pose>`, or why its attributes are invalid
+    pose_rotation: Result<PoseRotation, String>,
+    current_light: Option<SdfLight>,
+    current_joint: Option<SdfJoint>,
+    current_include: Option<SdfInclude>,
+    current_surface: Option<SdfSurface>,
     current_text: String,
     in_velocity_decay: bool,
+    in_attenuation: bool,
+    /// Open elements, outermost first, with the offset of their start tag
+    elements: Vec<(String, usize)>,
+    /// Depth inside an unsupported element that is being skipped
+    skip_depth: usize,
 }
 
 impl XmlContext {
     fn new() -> Self {
         Self {
-            current_element: String::new(),
             current_model: None,
             current_link: None,
             current_visual: None,
@@ -157,23 +384,139 @@ impl XmlContext {
             current_inertial: None,
             current_geometry: None,
             current_material: None,
-            current_pose: None,
+            pose_rotation: Ok(PoseRotation::default()),
+            current_light: None,
+            current_joint: None,
+            current_include: None,
+            current_surface: None,
             current_text: String::new(),
             in_velocity_decay: false,
+            in_attenuation: false,
+            elements: Vec::new(),
+            skip_depth: 0,
         }
     }
 }
 
+/// Where in an SDF file an error was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfLocation {
+    pub file: Option<String>,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in bytes
+    pub column: usize,
+    /// Path of the element, e.g. `sdf/world[default]/model[box]/pose`
+    pub element_path: String,
+}
+
+impl fmt::Display for SdfLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}", self.line, self.column)?;
+        if !self.element_path.is_empty() {
+            write!(f, " ({})", self.element_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error raised while parsing SDF
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdfError {
+    /// The file could not be read
+    Io { file: String, message: String },
+    /// The XML is malformed
+    Xml { location: SdfLocation, message: String },
+    /// An element holds a value of the wrong type or arity
+    InvalidValue { location: SdfLocation, message: String },
+    /// An `<include>`d model could not be found or read
+    Include { location: SdfLocation, message: String },
+}
+
+impl fmt::Display for SdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdfError::Io { file, message } => write!(f, "{}: {}", file, message),
+            SdfError::Xml { location, message } => write!(f, "{}: invalid XML: {}", location, message),
+            SdfError::InvalidValue { location, message } | SdfError::Include { location, message } => {
+                write!(f, "{}: {}", location, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SdfError {}
+
+/// Location of the byte `offset` of `content`, inside the open `elements` and then `element`.
+///
+/// `elements` holds the path segments of the open elements with the offsets of their start tags.
+pub fn locate(
+    content: &str,
+    file: Option<&str>,
+    elements: &[(String, usize)],
+    element: Option<&str>,
+    offset: usize,
+) -> SdfLocation {
+    let before = content.get(..offset).unwrap_or(content);
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let path: Vec<&str> = elements.iter().map(|(segment, _)| segment.as_str()).chain(element).collect();
+    SdfLocation {
+        file: file.map(str::to_string),
+        line: before.matches('\n').count() + 1,
+        column: before.len() - line_start + 1,
+        element_path: path.join("/"),
+    }
+}
+
 /// Loads an SDF file and returns the world structure
-pub fn load_sdf(path: &str) -> Result<SdfWorld, String> {
-    let content = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read SDF file: {}", e))?;
-    
-    parse_sdf_content(&content)
+#[allow(dead_code)]
+pub fn load_sdf(path: &str) -> Result<SdfWorld, SdfError> {
+    let content = fs::read_to_string(path).map_err(|e| SdfError::Io {
+        file: path.to_string(),
+        message: format!("Failed to read SDF file: {}", e),
+    })?;
+
+    parse_sdf_file(&content, path, &SdfModelPath::default())
+}
+
+/// Parses SDF XML content, resolving includes from the default model path
+#[allow(dead_code)]
+pub fn parse_sdf_content(content: &str) -> Result<SdfWorld, SdfError> {
+    parse_sdf_with_model_path(content, &SdfModelPath::default())
+}
+
+/// Parses SDF XML content, resolving `<include>`d models from the given model path
+#[allow(dead_code)]
+pub fn parse_sdf_with_model_path(content: &str, model_path: &SdfModelPath) -> Result<SdfWorld, SdfError> {
+    parse_sdf(content, None, model_path, &mut |path| fs::read_to_string(path), &mut Vec::new())
+}
+
+/// Parses the SDF XML content of `file`, naming the file in errors
+pub fn parse_sdf_file(content: &str, file: &str, model_path: &SdfModelPath) -> Result<SdfWorld, SdfError> {
+    parse_sdf_file_with(content, file, model_path, &mut |path| fs::read_to_string(path))
+}
+
+/// Parses the SDF XML content of `file`, reading the files of included models with `read_file`
+pub fn parse_sdf_file_with(
+    content: &str,
+    file: &str,
+    model_path: &SdfModelPath,
+    read_file: &mut dyn FnMut(&Path) -> std::io::Result<String>,
+) -> Result<SdfWorld, SdfError> {
+    parse_sdf(content, Some(file), model_path, read_file, &mut Vec::new())
 }
 
-/// Parses SDF XML content
-fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
+/// Parses SDF XML content; `include_stack` holds the model files being included, outermost first
+fn parse_sdf(
+    content: &str,
+    file: Option<&str>,
+    model_path: &SdfModelPath,
+    read_file: &mut dyn FnMut(&Path) -> std::io::Result<String>,
+    include_stack: &mut Vec<(PathBuf, String)>,
+) -> Result<SdfWorld, SdfError> {
     let mut reader = Reader::from_str(content);
     reader.trim_text(true);
     
@@ -184,19 +527,33 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
         lights: Vec::new(),
         physics: None,
         scene: None,
+        meshes: HashMap::new(),
+        images: HashMap::new(),
     };
     
-    let mut buf = Vec::new();
-    
     loop {
-        match reader.read_event_into(&mut buf) {
+        let event = reader.read_event();
+        let xml_error = |elements: &[(String, usize)], message: String| SdfError::Xml {
+            location: locate(content, file, elements, None, reader.buffer_position()),
+            message,
+        };
+        match event {
             Ok(Event::Start(ref e)) => {
                 let name = e.name();
                 let name_bytes = name.as_ref();
                 let tag_name = std::str::from_utf8(name_bytes)
-                    .map_err(|e| format!("Invalid UTF-8: {}", e))?;
+                    .map_err(|e| xml_error(&context.elements, format!("Invalid UTF-8: {}", e)))?;
                 
-                context.current_element = tag_name.to_string();
+                if context.skip_depth > 0 || !SDF_ELEMENTS.contains(&tag_name) {
+                    context.skip_depth += 1;
+                    continue;
+                }
+                let segment = match get_attribute(e, "name") {
+                    Some(name) => format!("{}[{}]", tag_name, name),
+                    None => tag_name.to_string(),
+                };
+                // The reader is just past the start tag, `e` is what is between its brackets
+                context.elements.push((segment, reader.buffer_position() - e.len() - 2));
                 context.current_text.clear();
                 
                 match tag_name {
@@ -218,8 +575,8 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                         context.current_link = Some(SdfLink {
                             name: get_attribute(e, "name").unwrap_or_default(),
                             pose: SdfPose::default(),
-                            visual: None,
-                            collision: None,
+                            visuals: Vec::new(),
+                            collisions: Vec::new(),
                             inertial: None,
                             linear_damping: None,
                             angular_damping: None,
@@ -238,11 +595,16 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                             name: get_attribute(e, "name").unwrap_or_default(),
                             pose: SdfPose::default(),
                             geometry: SdfGeometry::Box { size: Vec3::ONE }, // Default
+                            surface: None,
                         });
                     }
+                    "surface" => {
+                        context.current_surface = Some(SdfSurface::default());
+                    }
                     "inertial" => {
+                        // SDF's default mass is 1 kg
                         context.current_inertial = Some(SdfInertial {
-                            mass: 0.0,
+                            mass: 1.0,
                             ixx: 0.0,
                             iyy: 0.0,
                             izz: 0.0,
@@ -255,17 +617,103 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                     "velocity_decay" => {
                         context.in_velocity_decay = true;
                     }
+                    "light" => {
+                        context.current_light = Some(SdfLight::new(
+                            get_attribute(e, "name").unwrap_or_default(),
+                            get_attribute(e, "type").unwrap_or_else(|| "point".to_string()),
+                        ));
+                    }
+                    "attenuation" => {
+                        context.in_attenuation = true;
+                    }
+                    "joint" => {
+                        context.current_joint = Some(SdfJoint {
+                            name: get_attribute(e, "name").unwrap_or_default(),
+                            joint_type: get_attribute(e, "type").unwrap_or_default(),
+                            parent: String::new(),
+                            child: String::new(),
+                            pose: SdfPose::default(),
+                            axis: None,
+                        });
+                    }
+                    "axis" => {
+                        if let Some(joint) = &mut context.current_joint {
+                            joint.axis = Some(SdfAxis {
+                                xyz: Vec3::new(0.0, 0.0, 1.0),
+                                limit: None,
+                                dynamics: None,
+                            });
+                        }
+                    }
+                    "limit" => {
+                        if let Some(axis) = context.current_joint.as_mut().and_then(|j| j.axis.as_mut()) {
+                            axis.limit = Some(SdfJointLimit {
+                                lower: -1e16,
+                                upper: 1e16,
+                                effort: -1.0,
+                                velocity: -1.0,
+                            });
+                        }
+                    }
+                    "dynamics" => {
+                        if let Some(axis) = context.current_joint.as_mut().and_then(|j| j.axis.as_mut()) {
+                            axis.dynamics = Some(SdfJointDynamics {
+                                damping: 0.0,
+                                friction: 0.0,
+                            });
+                        }
+                    }
+                    "spot" => {
+                        if let Some(light) = &mut context.current_light {
+                            light.spot = Some(SdfSpot {
+                                inner_angle: 0.0,
+                                outer_angle: 0.0,
+                                falloff: 0.0,
+                            });
+                        }
+                    }
                     "geometry" => {
                         context.current_geometry = None;
                     }
+                    // Shapes start out with the SDF defaults; their child elements fill them in
                     "box" => {
-                        // Box geometry will be set when size is parsed
+                        context.current_geometry = Some(SdfGeometry::Box { size: Vec3::ONE });
                     }
                     "sphere" => {
-                        // Sphere geometry will be set when radius is parsed
+                        context.current_geometry = Some(SdfGeometry::Sphere { radius: 1.0 });
                     }
                     "cylinder" => {
-                        // Cylinder geometry will be set when radius and length are parsed
+                        context.current_geometry = Some(SdfGeometry::Cylinder { radius: 1.0, length: 1.0 });
+                    }
+                    "capsule" => {
+                        context.current_geometry = Some(SdfGeometry::Capsule { radius: 0.5, length: 1.0 });
+                    }
+                    "ellipsoid" => {
+                        context.current_geometry = Some(SdfGeometry::Ellipsoid { radii: Vec3::ONE });
+                    }
+                    "polyline" => {
+                        // A geometry may hold several polylines
+                        if let Some(SdfGeometry::Polyline { polylines }) = &mut context.current_geometry {
+                            polylines.push(SdfPolyline::default());
+                        } else {
+                            context.current_geometry = Some(SdfGeometry::Polyline {
+                                polylines: vec![SdfPolyline::default()],
+                            });
+                        }
+                    }
+                    "heightmap" => {
+                        context.current_geometry = Some(SdfGeometry::Heightmap {
+                            uri: String::new(),
+                            size: Vec3::ONE,
+                            pos: Vec3::ZERO,
+                        });
+                    }
+                    "mesh" => {
+                        // The URI and scale are filled in from the child elements
+                        context.current_geometry = Some(SdfGeometry::Mesh {
+                            uri: String::new(),
+                            scale: None,
+                        });
                     }
                     "plane" => {
                         // Initialize plane geometry - normal and size will be parsed separately
@@ -283,167 +731,288 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                         });
                     }
                     "pose" => {
-                        context.current_pose = Some(SdfPose::default());
+                        context.pose_rotation = PoseRotation::from_attributes(e);
+                    }
+                    "include" => {
+                        context.current_include = Some(SdfInclude::default());
+                    }
+                    "physics" => {
+                        let physics = world.physics.get_or_insert_with(SdfPhysics::default);
+                        if let Some(name) = get_attribute(e, "name") {
+                            physics.name = name;
+                        }
                     }
                     _ => {}
                 }
             }
-            Ok(Event::Text(e)) => {
+            Ok(Event::Text(e)) if context.skip_depth == 0 => {
                 let text = e.unescape()
-                    .map_err(|e| format!("Failed to unescape text: {}", e))?;
+                    .map_err(|e| xml_error(&context.elements, format!("Failed to unescape text: {}", e)))?;
                 context.current_text = text.to_string();
             }
             Ok(Event::End(ref e)) => {
+                if context.skip_depth > 0 {
+                    context.skip_depth -= 1;
+                    continue;
+                }
                 let name = e.name();
                 let name_bytes = name.as_ref();
                 let tag_name = std::str::from_utf8(name_bytes)
-                    .map_err(|e| format!("Invalid UTF-8: {}", e))?;
+                    .map_err(|e| xml_error(&context.elements, format!("Invalid UTF-8: {}", e)))?;
+                
+                // Values are reported at the start tag of the element holding them
+                let (segment, start) = context.elements.pop().unwrap_or_default();
+                let location = || locate(content, file, &context.elements, Some(&segment), start);
+                let invalid = |message: String| SdfError::InvalidValue { location: location(), message };
+                let text = context.current_text.as_str();
                 
                 match tag_name {
                     "static" => {
-                        if let Some(model) = &mut context.current_model {
-                            model.static_ = context.current_text.trim() == "true";
+                        let static_ = parse_bool(text).map_err(&invalid)?;
+                        if let Some(include) = &mut context.current_include {
+                            include.static_ = Some(static_);
+                        } else if let Some(model) = &mut context.current_model {
+                            model.static_ = static_;
                         }
                     }
                     "pose" => {
-                        if let Some(pose) = parse_pose(&context.current_text) {
-                            if let Some(model) = &mut context.current_model {
-                                model.pose = pose;
-                            } else if let Some(link) = &mut context.current_link {
-                                link.pose = pose;
-                            } else if let Some(visual) = &mut context.current_visual {
-                                visual.pose = pose;
-                            } else if let Some(collision) = &mut context.current_collision {
-                                collision.pose = pose;
-                            }
+                        // A pose belongs to the innermost element that is still open
+                        let pose = context.pose_rotation.clone()
+                            .and_then(|rotation| parse_pose(text, rotation))
+                            .map_err(&invalid)?;
+                        if let Some(include) = &mut context.current_include {
+                            include.pose = Some(pose);
+                        } else if let Some(light) = &mut context.current_light {
+                            light.pose = pose;
+                        } else if let Some(visual) = &mut context.current_visual {
+                            visual.pose = pose;
+                        } else if let Some(collision) = &mut context.current_collision {
+                            collision.pose = pose;
+                        } else if let Some(inertial) = &mut context.current_inertial {
+                            inertial.pose = pose;
+                        } else if let Some(joint) = &mut context.current_joint {
+                            joint.pose = pose;
+                        } else if let Some(link) = &mut context.current_link {
+                            link.pose = pose;
+                        } else if let Some(model) = &mut context.current_model {
+                            model.pose = pose;
                         }
                     }
-                    "mass" => {
+                    "mass" | "ixx" | "iyy" | "izz" | "ixy" | "ixz" | "iyz" => {
                         if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(mass) = context.current_text.trim().parse::<f32>() {
-                                inertial.mass = mass;
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "mass" => inertial.mass = v,
+                                "ixx" => inertial.ixx = v,
+                                "iyy" => inertial.iyy = v,
+                                "izz" => inertial.izz = v,
+                                "ixy" => inertial.ixy = v,
+                                "ixz" => inertial.ixz = v,
+                                _ => inertial.iyz = v,
                             }
                         }
                     }
-                    "ixx" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.ixx = v; }
+                    // World gravity is a vector; a link's <gravity> is a flag and is skipped
+                    "gravity" if context.current_link.is_none() => {
+                        let gravity = parse_vec3(text).map_err(&invalid)?;
+                        world.physics.get_or_insert_with(SdfPhysics::default).gravity = gravity;
+                    }
+                    "max_step_size" | "real_time_factor" | "real_time_update_rate" => {
+                        if let Some(physics) = &mut world.physics {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "max_step_size" => physics.max_step_size = v,
+                                "real_time_factor" => physics.real_time_factor = v,
+                                _ => physics.real_time_update_rate = v,
+                            }
                         }
                     }
-                    "iyy" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.iyy = v; }
+                    "ambient" => {
+                        let color = parse_color(text).map_err(&invalid)?;
+                        if let Some(material) = &mut context.current_material {
+                            material.ambient = Some(color);
+                        } else {
+                            world.scene = Some(SdfScene {
+                                ambient: color,
+                                background: Color::srgba(0.7, 0.7, 0.7, 1.0),
+                            });
                         }
                     }
-                    "izz" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.izz = v; }
+                    "linear" => {
+                        if context.in_velocity_decay {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            if let Some(link) = &mut context.current_link {
+                                link.linear_damping = Some(v);
+                            }
+                        } else if context.in_attenuation {
+                            if let Some(light) = &mut context.current_light {
+                                light.attenuation.linear = parse_number(text).map_err(&invalid)?;
+                            }
                         }
                     }
-                    "ixy" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.ixy = v; }
+                    "range" | "constant" | "quadratic" if context.in_attenuation => {
+                        if let Some(light) = &mut context.current_light {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "range" => light.attenuation.range = v,
+                                "constant" => light.attenuation.constant = v,
+                                _ => light.attenuation.quadratic = v,
+                            }
                         }
                     }
-                    "ixz" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.ixz = v; }
+                    "inner_angle" | "outer_angle" | "falloff" => {
+                        if let Some(spot) = context.current_light.as_mut().and_then(|l| l.spot.as_mut()) {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "inner_angle" => spot.inner_angle = v,
+                                "outer_angle" => spot.outer_angle = v,
+                                _ => spot.falloff = v,
+                            }
                         }
                     }
-                    "iyz" => {
-                        if let Some(inertial) = &mut context.current_inertial {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() { inertial.iyz = v; }
+                    "parent" => {
+                        if let Some(joint) = &mut context.current_joint {
+                            joint.parent = text.trim().to_string();
                         }
                     }
-                    "gravity" => {
-                        if let Some(gravity) = parse_vec3(&context.current_text) {
-                            world.physics = Some(SdfPhysics {
-                                name: "default_physics".to_string(),
-                                max_step_size: 0.001,
-                                real_time_factor: 1.0,
-                                real_time_update_rate: 1000.0,
-                                gravity,
-                            });
+                    "child" => {
+                        if let Some(joint) = &mut context.current_joint {
+                            joint.child = text.trim().to_string();
                         }
                     }
-                    "ambient" => {
-                        if let Some(color) = parse_color(&context.current_text) {
-                            if let Some(material) = &mut context.current_material {
-                                material.ambient = Some(color);
-                            } else {
-                                world.scene = Some(SdfScene {
-                                    ambient: color,
-                                    background: Color::srgba(0.7, 0.7, 0.7, 1.0),
-                                });
-                            }
+                    "xyz" => {
+                        if let Some(axis) = context.current_joint.as_mut().and_then(|j| j.axis.as_mut()) {
+                            axis.xyz = parse_vec3(text).map_err(&invalid)?;
                         }
                     }
-                    "linear" => {
-                        if context.in_velocity_decay {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() {
-                                if let Some(link) = &mut context.current_link {
-                                    link.linear_damping = Some(v);
-                                }
+                    "lower" | "upper" | "effort" | "velocity" => {
+                        if let Some(limit) = context.current_joint.as_mut().and_then(|j| j.axis.as_mut()).and_then(|a| a.limit.as_mut()) {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "lower" => limit.lower = v,
+                                "upper" => limit.upper = v,
+                                "effort" => limit.effort = v,
+                                _ => limit.velocity = v,
                             }
                         }
                     }
-                    "angular" => {
-                        if context.in_velocity_decay {
-                            if let Ok(v) = context.current_text.trim().parse::<f32>() {
-                                if let Some(link) = &mut context.current_link {
-                                    link.angular_damping = Some(v);
-                                }
+                    "damping" | "friction" => {
+                        if let Some(dynamics) = context.current_joint.as_mut().and_then(|j| j.axis.as_mut()).and_then(|a| a.dynamics.as_mut()) {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "damping" => dynamics.damping = v,
+                                _ => dynamics.friction = v,
                             }
                         }
                     }
+                    "cast_shadows" => {
+                        if let Some(light) = &mut context.current_light {
+                            light.cast_shadows = parse_bool(text).map_err(&invalid)?;
+                        }
+                    }
+                    "intensity" => {
+                        if let Some(light) = &mut context.current_light {
+                            light.intensity = parse_number(text).map_err(&invalid)?;
+                        }
+                    }
+                    "direction" => {
+                        if let Some(light) = &mut context.current_light {
+                            light.direction = parse_vec3(text).map_err(&invalid)?;
+                        }
+                    }
+                    "angular" if context.in_velocity_decay => {
+                        let v = parse_number(text).map_err(&invalid)?;
+                        if let Some(link) = &mut context.current_link {
+                            link.angular_damping = Some(v);
+                        }
+                    }
                     "background" => {
-                        if let Some(color) = parse_color(&context.current_text) {
-                            if let Some(scene) = &mut world.scene {
-                                scene.background = color;
-                            }
+                        let color = parse_color(text).map_err(&invalid)?;
+                        if let Some(scene) = &mut world.scene {
+                            scene.background = color;
                         }
                     }
-                    "diffuse" => {
-                        if let Some(color) = parse_color(&context.current_text) {
-                            if let Some(material) = &mut context.current_material {
-                                material.diffuse = Some(color);
+                    "diffuse" | "specular" => {
+                        if let Some(material) = &mut context.current_material {
+                            let color = parse_color(text).map_err(&invalid)?;
+                            match tag_name {
+                                "diffuse" => material.diffuse = Some(color),
+                                _ => material.specular = Some(color),
+                            }
+                        } else if let Some(light) = &mut context.current_light {
+                            let color = parse_color(text).map_err(&invalid)?;
+                            match tag_name {
+                                "diffuse" => light.diffuse = color,
+                                _ => light.specular = color,
                             }
                         }
                     }
+                    // Shape dimensions fill in the shape element they are in
                     "size" => {
-                        // Check if this is for a plane (needs Vec2) or box (needs Vec3)
-                        if let Some(SdfGeometry::Plane { normal, size: _ }) = &context.current_geometry {
-                            if let Some(size) = parse_vec2(&context.current_text) {
-                                context.current_geometry = Some(SdfGeometry::Plane { normal: *normal, size });
+                        match &mut context.current_geometry {
+                            Some(SdfGeometry::Plane { size, .. }) => *size = parse_vec2(text).map_err(&invalid)?,
+                            Some(SdfGeometry::Box { size } | SdfGeometry::Heightmap { size, .. }) => {
+                                *size = parse_vec3(text).map_err(&invalid)?;
                             }
-                        } else if let Some(size) = parse_vec3(&context.current_text) {
-                            context.current_geometry = Some(SdfGeometry::Box { size });
+                            _ => {}
                         }
                     }
                     "radius" => {
-                        if let Ok(radius) = context.current_text.trim().parse::<f32>() {
-                            context.current_geometry = Some(SdfGeometry::Sphere { radius });
+                        if let Some(
+                            SdfGeometry::Sphere { radius }
+                            | SdfGeometry::Cylinder { radius, .. }
+                            | SdfGeometry::Capsule { radius, .. },
+                        ) = &mut context.current_geometry {
+                            *radius = parse_number(text).map_err(&invalid)?;
                         }
                     }
                     "length" => {
-                        if let Ok(length) = context.current_text.trim().parse::<f32>() {
-                            // For cylinders, we need both radius and length
-                            if let Some(SdfGeometry::Cylinder { radius, .. }) = context.current_geometry {
-                                context.current_geometry = Some(SdfGeometry::Cylinder { radius, length });
-                            }
+                        if let Some(SdfGeometry::Cylinder { length, .. } | SdfGeometry::Capsule { length, .. }) = &mut context.current_geometry {
+                            *length = parse_number(text).map_err(&invalid)?;
+                        }
+                    }
+                    "radii" => {
+                        if let Some(SdfGeometry::Ellipsoid { radii }) = &mut context.current_geometry {
+                            *radii = parse_vec3(text).map_err(&invalid)?;
                         }
                     }
                     "normal" => {
-                        if let Some(normal) = parse_vec3(&context.current_text) {
-                            // Store normal for plane geometry - size will be parsed separately
-                            if let Some(SdfGeometry::Plane { normal: _, size }) = &context.current_geometry {
-                                context.current_geometry = Some(SdfGeometry::Plane { normal, size: *size });
-                            } else {
-                                // Create plane with default size, will be updated when size is parsed
-                                context.current_geometry = Some(SdfGeometry::Plane { normal, size: Vec2::new(1.0, 1.0) });
+                        if let Some(SdfGeometry::Plane { normal, .. }) = &mut context.current_geometry {
+                            *normal = parse_vec3(text).map_err(&invalid)?;
+                        }
+                    }
+                    "point" | "height" => {
+                        if let Some(SdfGeometry::Polyline { polylines }) = &mut context.current_geometry {
+                            if let Some(polyline) = polylines.last_mut() {
+                                match tag_name {
+                                    "point" => polyline.points.push(parse_vec2(text).map_err(&invalid)?),
+                                    _ => polyline.height = parse_number(text).map_err(&invalid)?,
+                                }
                             }
                         }
                     }
+                    "pos" => {
+                        if let Some(SdfGeometry::Heightmap { pos, .. }) = &mut context.current_geometry {
+                            *pos = parse_vec3(text).map_err(&invalid)?;
+                        }
+                    }
+                    "uri" => {
+                        if let Some(include) = &mut context.current_include {
+                            include.uri = text.trim().to_string();
+                        } else if let Some(SdfGeometry::Mesh { uri, .. } | SdfGeometry::Heightmap { uri, .. }) = &mut context.current_geometry {
+                            *uri = text.trim().to_string();
+                        }
+                    }
+                    "name" => {
+                        if let Some(include) = &mut context.current_include {
+                            include.name = Some(text.trim().to_string());
+                        }
+                    }
+                    "scale" => {
+                        if let Some(SdfGeometry::Mesh { scale, .. }) = &mut context.current_geometry {
+                            *scale = Some(parse_vec3(text).map_err(&invalid)?);
+                        }
+                    }
                     "geometry" => {
                         if let Some(geometry) = context.current_geometry.take() {
                             if let Some(visual) = &mut context.current_visual {
@@ -463,14 +1032,33 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                     "visual" => {
                         if let Some(visual) = context.current_visual.take() {
                             if let Some(link) = &mut context.current_link {
-                                link.visual = Some(visual);
+                                link.visuals.push(visual);
+                            }
+                        }
+                    }
+                    "mu" | "mu2" | "restitution_coefficient" | "kp" | "kd" => {
+                        if let Some(surface) = &mut context.current_surface {
+                            let v = parse_number(text).map_err(&invalid)?;
+                            match tag_name {
+                                "mu" => surface.mu = v,
+                                "mu2" => surface.mu2 = v,
+                                "restitution_coefficient" => surface.restitution_coefficient = v,
+                                "kp" => surface.kp = Some(v),
+                                _ => surface.kd = Some(v),
+                            }
+                        }
+                    }
+                    "surface" => {
+                        if let Some(surface) = context.current_surface.take() {
+                            if let Some(collision) = &mut context.current_collision {
+                                collision.surface = Some(surface);
                             }
                         }
                     }
                     "collision" => {
                         if let Some(collision) = context.current_collision.take() {
                             if let Some(link) = &mut context.current_link {
-                                link.collision = Some(collision);
+                                link.collisions.push(collision);
                             }
                         }
                     }
@@ -484,6 +1072,24 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                     "velocity_decay" => {
                         context.in_velocity_decay = false;
                     }
+                    "attenuation" => {
+                        context.in_attenuation = false;
+                    }
+                    "light" => {
+                        // Only world-level lights are spawned; lights attached to links are skipped
+                        if let Some(light) = context.current_light.take() {
+                            if context.current_model.is_none() {
+                                world.lights.push(light);
+                            }
+                        }
+                    }
+                    "joint" => {
+                        if let Some(joint) = context.current_joint.take() {
+                            if let Some(model) = &mut context.current_model {
+                                model.joints.push(joint);
+                            }
+                        }
+                    }
                     "link" => {
                         if let Some(link) = context.current_link.take() {
                             if let Some(model) = &mut context.current_model {
@@ -496,201 +1102,802 @@ fn parse_sdf_content(content: &str) -> Result<SdfWorld, String> {
                             world.models.push(model);
                         }
                     }
+                    "include" => {
+                        if let Some(include) = context.current_include.take() {
+                            let included = resolve_include(&include, location(), model_path, read_file, include_stack)?;
+                            // An include inside a model nests the included model in it
+                            if let Some(model) = &mut context.current_model {
+                                merge_nested_model(model, included);
+                            } else {
+                                world.models.push(included);
+                            }
+                        }
+                    }
                     _ => {}
                 }
+                // The text of an element is not carried over to the elements enclosing it
+                context.current_text.clear();
             }
             Ok(Event::Eof) => break,
-            Err(e) => return Err(format!("Error parsing XML: {}", e)),
+            Err(e) => return Err(xml_error(&context.elements, e.to_string())),
             _ => {}
         }
-        
-        buf.clear();
     }
     
     Ok(world)
 }
 
+/// Reads the model an `<include>` refers to and applies the include's overrides
+fn resolve_include(
+    include: &SdfInclude,
+    location: SdfLocation,
+    model_path: &SdfModelPath,
+    read_file: &mut dyn FnMut(&Path) -> std::io::Result<String>,
+    include_stack: &mut Vec<(PathBuf, String)>,
+) -> Result<SdfModel, SdfError> {
+    let error = |message: String| SdfError::Include { location: location.clone(), message };
+    let uri = include.uri.as_str();
+    let model_dir = match uri.strip_prefix("file://") {
+        Some(path) => Some(PathBuf::from(path)),
+        None if uri.starts_with("model://") => model_path.find_model(uri),
+        None => Some(PathBuf::from(uri)),
+    }
+    .filter(|dir| dir.is_dir())
+    .ok_or_else(|| error(format!("Included model {} not found in model path {:?}", uri, model_path.dirs)))?;
+
+    let config = read_file(&model_dir.join("model.config")).ok();
+    let model_file = model_sdf_file(&model_dir, config.as_deref());
+    let key = model_file.canonicalize().unwrap_or_else(|_| model_file.clone());
+    if include_stack.iter().any(|(file, _)| *file == key) {
+        let cycle: Vec<&str> = include_stack.iter().map(|(_, uri)| uri.as_str()).chain([uri]).collect();
+        return Err(error(format!("Include cycle: {}", cycle.join(" -> "))));
+    }
+    let content = read_file(&model_file)
+        .map_err(|e| error(format!("Failed to read included model {}: {}", model_file.display(), e)))?;
+
+    include_stack.push((key, uri.to_string()));
+    let included = parse_sdf(&content, Some(&model_file.to_string_lossy()), model_path, read_file, include_stack);
+    include_stack.pop();
+    let mut model = included?.models.into_iter().next()
+        .ok_or_else(|| error(format!("Included file {} has no model", model_file.display())))?;
+
+    if let Some(name) = &include.name {
+        model.name = name.clone();
+    }
+    if let Some(pose) = &include.pose {
+        model.pose = pose.clone();
+    }
+    if let Some(static_) = include.static_ {
+        model.static_ = static_;
+    }
+
+    // Mesh and heightmap URIs relative to the model file are found through the model path as well
+    if let (Some(model_name), Some(model_root)) = (uri.strip_prefix("model://"), model_file.parent()) {
+        let model_name = model_name.split('/').next().unwrap_or_default();
+        let subdir = model_root.strip_prefix(&model_dir).unwrap_or(Path::new(""));
+        for link in &mut model.links {
+            let geometries = link.visuals.iter_mut().map(|visual| &mut visual.geometry)
+                .chain(link.collisions.iter_mut().map(|collision| &mut collision.geometry));
+            for geometry in geometries {
+                if let SdfGeometry::Mesh { uri: mesh_uri, .. } | SdfGeometry::Heightmap { uri: mesh_uri, .. } = geometry {
+                    if !mesh_uri.is_empty() && !mesh_uri.contains("://") && !mesh_uri.starts_with('/') {
+                        let path = Path::new(model_name).join(subdir).join(&*mesh_uri);
+                        *mesh_uri = format!("model://{}", path.to_string_lossy());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(model)
+}
+
+/// Adds the links and joints of an included model to the model including it.
+///
+/// Names are scoped with the nested model's name (`name::link`) and link poses are made relative
+/// to the outer model.
+fn merge_nested_model(model: &mut SdfModel, nested: SdfModel) {
+    let scoped = |name: &str| format!("{}::{}", nested.name, name);
+    for mut link in nested.links {
+        link.name = scoped(&link.name);
+        link.pose = compose_poses(&nested.pose, &link.pose);
+        model.links.push(link);
+    }
+    for mut joint in nested.joints {
+        joint.name = scoped(&joint.name);
+        if joint.parent != "world" {
+            joint.parent = scoped(&joint.parent);
+        }
+        if joint.child != "world" {
+            joint.child = scoped(&joint.child);
+        }
+        model.joints.push(joint);
+    }
+}
+
+/// Pose of a frame given relative to `parent`, expressed in the frame `parent` is relative to
+pub fn compose_poses(parent: &SdfPose, pose: &SdfPose) -> SdfPose {
+    let rotation = |pose: &SdfPose| Quat::from_euler(EulerRot::ZYX, pose.rpy.z, pose.rpy.y, pose.rpy.x);
+    let parent_rotation = rotation(parent);
+    let (yaw, pitch, roll) = (parent_rotation * rotation(pose)).to_euler(EulerRot::ZYX);
+    SdfPose {
+        xyz: parent.xyz + parent_rotation * pose.xyz,
+        rpy: Vec3::new(roll, pitch, yaw),
+    }
+}
+
 /// Helper function to get attribute value
-fn get_attribute(e: &BytesStart, name: &str) -> Option<String> {
+pub fn get_attribute(e: &BytesStart, name: &str) -> Option<String> {
     e.attributes()
         .find(|attr| attr.as_ref().map(|a| a.key.as_ref() == name.as_bytes()).unwrap_or(false))
         .and_then(|attr| attr.ok())
         .and_then(|attr| String::from_utf8(attr.value.to_vec()).ok())
 }
 
-/// Parse pose string (x y z roll pitch yaw)
-fn parse_pose(text: &str) -> Option<SdfPose> {
-    let parts: Vec<f32> = text.split_whitespace()
-        .filter_map(|s| s.parse::<f32>().ok())
-        .collect();
+/// Parses whitespace separated numbers, of which there must be one of the `counts` given
+fn parse_numbers(text: &str, counts: &[usize]) -> Result<Vec<f32>, String> {
+    let parts = text.split_whitespace()
+        .map(|s| s.parse::<f32>().map_err(|_| format!("expected a number, got {:?}", s)))
+        .collect::<Result<Vec<f32>, String>>()?;
     
-    if parts.len() >= 6 {
-        Some(SdfPose {
-            xyz: Vec3::new(parts[0], parts[1], parts[2]),
-            rpy: Vec3::new(parts[3], parts[4], parts[5]),
-        })
+    if counts.contains(&parts.len()) {
+        Ok(parts)
     } else {
-        None
+        let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+        Err(format!("expected {} numbers, got {}", counts.join(" or "), parts.len()))
     }
 }
 
-/// Parse Vec3 string (x y z)
-fn parse_vec3(text: &str) -> Option<Vec3> {
-    let parts: Vec<f32> = text.split_whitespace()
-        .filter_map(|s| s.parse::<f32>().ok())
-        .collect();
-    
-    if parts.len() >= 3 {
-        Some(Vec3::new(parts[0], parts[1], parts[2]))
-    } else {
-        None
+/// Parse a single number
+pub fn parse_number(text: &str) -> Result<f32, String> {
+    text.trim().parse::<f32>()
+        .map_err(|_| format!("expected a number, got {:?}", text.trim()))
+}
+
+/// Parse a boolean (`true`, `false`, `1` or `0`)
+pub fn parse_bool(text: &str) -> Result<bool, String> {
+    match text.trim() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        other => Err(format!("expected true or false, got {:?}", other)),
+    }
+}
+
+/// Parse pose string (x y z, then roll pitch yaw or qx qy qz qw as `rotation` says); an empty pose is the identity
+pub fn parse_pose(text: &str, rotation: PoseRotation) -> Result<SdfPose, String> {
+    if text.trim().is_empty() {
+        return Ok(SdfPose::default());
     }
+    let count = match rotation {
+        PoseRotation::Radians | PoseRotation::Degrees => 6,
+        PoseRotation::Quaternion => 7,
+    };
+    let parts = parse_numbers(text, &[count])?;
+    let rpy = match rotation {
+        PoseRotation::Radians => Vec3::new(parts[3], parts[4], parts[5]),
+        PoseRotation::Degrees => Vec3::new(parts[3].to_radians(), parts[4].to_radians(), parts[5].to_radians()),
+        PoseRotation::Quaternion => {
+            let quat = Quat::from_xyzw(parts[3], parts[4], parts[5], parts[6]);
+            if quat.length_squared() == 0.0 {
+                return Err("expected a non-zero quaternion".to_string());
+            }
+            let (yaw, pitch, roll) = quat.normalize().to_euler(EulerRot::ZYX);
+            Vec3::new(roll, pitch, yaw)
+        }
+    };
+    Ok(SdfPose {
+        xyz: Vec3::new(parts[0], parts[1], parts[2]),
+        rpy,
+    })
+}
+
+/// Parse Vec3 string (x y z)
+pub fn parse_vec3(text: &str) -> Result<Vec3, String> {
+    let parts = parse_numbers(text, &[3])?;
+    Ok(Vec3::new(parts[0], parts[1], parts[2]))
 }
 
 /// Parse Vec2 string (x y)
-fn parse_vec2(text: &str) -> Option<Vec2> {
-    let parts: Vec<f32> = text.split_whitespace()
-        .filter_map(|s| s.parse::<f32>().ok())
-        .collect();
-    
-    if parts.len() >= 2 {
-        Some(Vec2::new(parts[0], parts[1]))
+pub fn parse_vec2(text: &str) -> Result<Vec2, String> {
+    let parts = parse_numbers(text, &[2])?;
+    Ok(Vec2::new(parts[0], parts[1]))
+}
+
+/// Parse color string (r g b [a])
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    let parts = parse_numbers(text, &[3, 4])?;
+    if let [r, g, b, a] = parts[..] {
+        Ok(Color::srgba(r, g, b, a))
     } else {
-        None
+        Ok(Color::srgb(parts[0], parts[1], parts[2]))
     }
 }
 
-/// Parse color string (r g b a)
-fn parse_color(text: &str) -> Option<Color> {
-    let parts: Vec<f32> = text.split_whitespace()
-        .filter_map(|s| s.parse::<f32>().ok())
-        .collect();
-    
-    if parts.len() >= 4 {
-        Some(Color::srgba(parts[0], parts[1], parts[2], parts[3]))
-    } else if parts.len() >= 3 {
-        Some(Color::srgb(parts[0], parts[1], parts[2]))
-    } else {
-        None
+/// SDF element an entity was spawned from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdfElement {
+    World,
+    Model,
+    Link,
+    Visual,
+    Collision,
+    Light,
+}
+
+/// Tags every entity spawned from SDF.
+///
+/// The entities form a world → model → link → visual/collision hierarchy; lights are children of the world.
+#[allow(dead_code)]
+#[derive(Component, Debug, Clone)]
+pub struct SdfEntity {
+    /// Element the entity was spawned from
+    pub element: SdfElement,
+    /// Name of the element itself
+    pub name: String,
+    /// Name of the world the element belongs to
+    pub world_name: String,
+    /// Name of the model the element is or belongs to, if any
+    pub model_name: Option<String>,
+    /// Name of the link the element is or belongs to, if any
+    pub link_name: Option<String>,
+}
+
+/// Event to request loading an SDF world from a file
+#[derive(Event, Debug, Clone)]
+pub struct LoadSdfWorldRequest {
+    /// Asset path of the SDF file
+    pub sdf_path: String,
+    pub spawn_position: Vec3,
+    pub spawn_rotation: Quat,
+}
+
+impl LoadSdfWorldRequest {
+    /// Request to load a world at the origin
+    pub fn new(sdf_path: impl Into<String>) -> Self {
+        LoadSdfWorldRequest {
+            sdf_path: sdf_path.into(),
+            spawn_position: Vec3::ZERO,
+            spawn_rotation: Quat::IDENTITY,
+        }
     }
 }
 
-/// Spawns a complete Bevy world from a parsed SDF world
+/// Event sent once an SDF world asset has been spawned
+#[allow(dead_code)]
+#[derive(Event, Debug, Clone)]
+pub struct SdfWorldLoaded {
+    /// Asset path of the SDF file
+    pub sdf_path: String,
+    /// Name of the SDF world
+    pub world_name: String,
+    /// Root entity of the world hierarchy
+    pub entity: Entity,
+}
+
+/// Event sent when an SDF world asset could not be loaded
+#[allow(dead_code)]
+#[derive(Event, Debug, Clone)]
+pub struct SdfWorldLoadFailed {
+    /// Asset path of the SDF file
+    pub sdf_path: String,
+    /// Why loading failed
+    pub error: String,
+}
+
+/// Root of a world spawned from an `SdfWorld` asset.
+///
+/// The world is spawned as children of this entity once the asset has loaded. When the asset is
+/// reloaded, the models and lights that changed are despawned and respawned in place.
+#[derive(Component, Debug, Clone)]
+pub struct SdfWorldHandle(pub Handle<SdfWorld>);
+
+/// The version of an `SdfWorld` asset that is currently spawned under an `SdfWorldHandle`
+#[derive(Component)]
+pub struct SpawnedSdfWorld(SdfWorld);
+
+/// The SDF model a model entity was spawned from, kept so the scene can be written back to SDF
+#[derive(Component, Debug, Clone)]
+pub struct SpawnedSdfModel(pub SdfModel);
+
+/// The SDF light a light entity was spawned from
+#[derive(Component, Debug, Clone)]
+pub struct SpawnedSdfLight(pub SdfLight);
+
+/// Spawns a complete Bevy world from a parsed SDF world and returns its root entity
+#[allow(dead_code)]
 pub fn spawn_sdf_world(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    _asset_server: &Res<AssetServer>,
+    asset_server: &Res<AssetServer>,
+    world: &SdfWorld,
+) -> Entity {
+    let world_entity = commands.spawn((
+        Transform::IDENTITY,
+        Visibility::default(),
+        InheritedVisibility::default(),
+        ViewVisibility::default(),
+    )).id();
+    populate_sdf_world(commands, meshes, materials, asset_server, world_entity, world);
+    world_entity
+}
+
+/// Spawns the models and lights of an SDF world as children of its root entity
+fn populate_sdf_world(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    asset_server: &Res<AssetServer>,
+    world_entity: Entity,
     world: &SdfWorld,
 ) {
-    // Apply scene settings
-    if let Some(scene) = &world.scene {
-        commands.insert_resource(ClearColor(scene.background));
-        commands.insert_resource(AmbientLight {
-            color: scene.ambient,
-            brightness: 500.0,
-            affects_lightmapped_meshes: true,
-        });
-    }
+    apply_sdf_scene(commands, world);
     
-    // Apply physics settings (informative)
-    if let Some(physics) = &world.physics {
-        println!("Physics settings (SDF): gravity={:?}, max_step_size={}", physics.gravity, physics.max_step_size);
-    }
+    commands.entity(world_entity).insert((
+        Name::new(world.name.clone()),
+        SdfEntity {
+            element: SdfElement::World,
+            name: world.name.clone(),
+            world_name: world.name.clone(),
+            model_name: None,
+            link_name: None,
+        },
+    ));
     
     // Spawn all models
     for model in &world.models {
-        spawn_sdf_model(commands, meshes, materials, model);
+        spawn_sdf_model(commands, meshes, materials, asset_server, world_entity, world, model);
     }
     
     // Spawn all lights
     for light in &world.lights {
-        spawn_sdf_light(commands, light);
+        spawn_sdf_light(commands, world_entity, world, light);
     }
 }
 
-/// Spawns a single SDF model as Bevy entities
-fn spawn_sdf_model(
+/// Respawns the models and lights of a reloaded SDF world that differ from the spawned version
+#[allow(clippy::too_many_arguments)]
+fn respawn_changed_sdf_world(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    model: &SdfModel,
+    asset_server: &Res<AssetServer>,
+    world_entity: Entity,
+    children: &[Entity],
+    sdf_entities: &Query<&SdfEntity>,
+    old_world: &SdfWorld,
+    world: &SdfWorld,
 ) {
+    if old_world.name != world.name {
+        // Every entity is tagged with the world name
+        for &child in children {
+            commands.entity(child).despawn();
+        }
+        populate_sdf_world(commands, meshes, materials, asset_server, world_entity, world);
+        return;
+    }
+    apply_sdf_scene(commands, world);
+    
+    // Unchanged models are left alone, so whatever they are doing in the simulation carries on
+    let unchanged = |model: &SdfModel| {
+        old_world.models.iter().find(|old_model| old_model.name == model.name) == Some(model)
+    };
+    let lights_changed = old_world.lights != world.lights;
+    let mut kept_models = HashSet::new();
+    for &child in children {
+        let Ok(sdf_entity) = sdf_entities.get(child) else {
+            continue;
+        };
+        let keep = match sdf_entity.element {
+            SdfElement::Model => world.models.iter()
+                .find(|model| model.name == sdf_entity.name)
+                .is_some_and(unchanged),
+            SdfElement::Light => !lights_changed,
+            _ => true,
+        };
+        if !keep {
+            commands.entity(child).despawn();
+        } else if sdf_entity.element == SdfElement::Model {
+            kept_models.insert(sdf_entity.name.as_str());
+        }
+    }
+    
+    for model in &world.models {
+        if !kept_models.contains(model.name.as_str()) {
+            info!("Respawning SDF model: {}", model.name);
+            spawn_sdf_model(commands, meshes, materials, asset_server, world_entity, world, model);
+        }
+    }
+    if lights_changed {
+        for light in &world.lights {
+            spawn_sdf_light(commands, world_entity, world, light);
+        }
+    }
+}
+
+/// Applies the scene settings of an SDF world
+fn apply_sdf_scene(commands: &mut Commands, world: &SdfWorld) {
+    if let Some(scene) = &world.scene {
+        commands.insert_resource(ClearColor(scene.background));
+        commands.insert_resource(AmbientLight {
+            color: scene.ambient,
+            brightness: 500.0,
+            affects_lightmapped_meshes: true,
+        });
+    }
+    
+    // Picked up by sdf_physics_system
+    commands.insert_resource(world.physics.clone().unwrap_or_default());
+}
+
+/// Spawns a single SDF model as Bevy entities and returns its link entities by name
+pub fn spawn_sdf_model(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    asset_server: &Res<AssetServer>,
+    world_entity: Entity,
+    world: &SdfWorld,
+    model: &SdfModel,
+) -> HashMap<String, Entity> {
     println!("Spawning SDF model: {}", model.name);
     
     // Create model transform from pose
     let model_transform = sdf_pose_to_transform(&model.pose);
+    let model_entity = commands.spawn((
+        model_transform,
+        Name::new(model.name.clone()),
+        SdfEntity {
+            element: SdfElement::Model,
+            name: model.name.clone(),
+            world_name: world.name.clone(),
+            model_name: Some(model.name.clone()),
+            link_name: None,
+        },
+        SpawnedSdfModel(model.clone()),
+        Visibility::default(),
+        InheritedVisibility::default(),
+        ViewVisibility::default(),
+        ChildOf(world_entity),
+    )).id();
     
-    // Spawn each link in the model
+    // Spawn each link in the model, remembering where it is within the world
+    let mut links = HashMap::new();
     for link in &model.links {
-        spawn_sdf_link(commands, meshes, materials, model, link, model_transform);
+        let link_transform = model_transform.mul_transform(sdf_pose_to_transform(&link.pose));
+        let entity = spawn_sdf_link(commands, meshes, materials, asset_server, model_entity, world, model, link);
+        links.insert(link.name.as_str(), (entity, link_transform));
+    }
+    
+    // Connect the links with their joints
+    for joint in &model.joints {
+        spawn_sdf_joint(commands, model_entity, model_transform, model, joint, &links);
     }
+    
+    links.into_iter().map(|(name, (entity, _))| (name.to_string(), entity)).collect()
 }
 
-/// Spawns a single SDF link as a Bevy entity
+/// Spawns a single SDF link as a child of its model, with its visuals and collisions as children
+#[allow(clippy::too_many_arguments)]
 fn spawn_sdf_link(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
+    asset_server: &Res<AssetServer>,
+    model_entity: Entity,
+    world: &SdfWorld,
     model: &SdfModel,
     link: &SdfLink,
-    model_transform: Transform,
-) {
-    let link_transform = model_transform.mul_transform(sdf_pose_to_transform(&link.pose));
+) -> Entity {
+    let link_entity = |element, name: &str| SdfEntity {
+        element,
+        name: name.to_string(),
+        world_name: world.name.clone(),
+        model_name: Some(model.name.clone()),
+        link_name: Some(link.name.clone()),
+    };
     
-    // Create visual mesh and material
-    if let Some(visual) = &link.visual {
-        let (mesh_handle, material_handle) = create_visual_geometry(
-            meshes, materials, &visual.geometry, &visual.material
-        );
-        
-        // Create the entity
-        let mut entity_cmd = commands.spawn((
-            Mesh3d(mesh_handle),
-            MeshMaterial3d(material_handle),
-            link_transform,
-            Name::new(format!("{}_{}", model.name, link.name)),
-            Visibility::default(),
-            InheritedVisibility::default(),
-            ViewVisibility::default(),
-        ));
-        
-        // Add collision if specified
-        if let Some(collision) = &link.collision {
-            let collider = create_collider(&collision.geometry);
-            entity_cmd.insert(collider);
-            
-            // Determine mass from inertial properties
-            let mass = link.inertial.as_ref().map(|i| i.mass).unwrap_or(1.0);
-
-            // Add rigid body and collision groups based on whether the model is static
-            if model.static_ || mass <= 0.0 {
-                entity_cmd.insert(RigidBody::Fixed);
-                entity_cmd.insert(CollisionGroups::new(
-                    STATIC_GROUP,
-                    CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP,
+    // Create the link entity
+    let mut entity_cmd = commands.spawn((
+        sdf_pose_to_transform(&link.pose),
+        Name::new(format!("{}_{}", model.name, link.name)),
+        link_entity(SdfElement::Link, &link.name),
+        Visibility::default(),
+        InheritedVisibility::default(),
+        ViewVisibility::default(),
+        ChildOf(model_entity),
+    ));
+    
+    // Create visual meshes and materials, each at its pose within the link
+    entity_cmd.with_children(|parent| {
+        for visual in &link.visuals {
+            let name = Name::new(format!("{}_{}_{}", model.name, link.name, visual.name));
+            let sdf_entity = link_entity(SdfElement::Visual, &visual.name);
+            let visual_transform = sdf_pose_to_transform(&visual.pose);
+            match &visual.geometry {
+                SdfGeometry::Mesh { uri, scale } => {
+                    let Some(path) = resolve_mesh_uri(uri) else {
+                        println!("Warning: Could not resolve mesh URI: {}", uri);
+                        continue;
+                    };
+                    let transform = visual_transform * mesh_transform(&path, *scale);
+                    if is_gltf(&path) {
+                        parent.spawn((
+                            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(path))),
+                            transform,
+                            name,
+                            sdf_entity,
+                            Visibility::default(),
+                            InheritedVisibility::default(),
+                            ViewVisibility::default(),
+                        ));
+                    } else {
+                        let mesh = world.meshes.get(uri).cloned().unwrap_or_else(|| asset_server.load(path));
+                        parent.spawn((
+                            Mesh3d(mesh),
+                            MeshMaterial3d(create_material(materials, &visual.material)),
+                            transform,
+                            name,
+                            sdf_entity,
+                            Visibility::default(),
+                            InheritedVisibility::default(),
+                            ViewVisibility::default(),
+                        ));
+                    }
+                }
+                SdfGeometry::Heightmap { uri, size, pos } => {
+                    let Some(path) = resolve_mesh_uri(uri) else {
+                        println!("Warning: Could not resolve heightmap URI: {}", uri);
+                        continue;
+                    };
+                    let image = world.images.get(uri).cloned().unwrap_or_else(|| asset_server.load(path));
+                    parent.spawn((
+                        SdfHeightmap { image, size: *size },
+                        MeshMaterial3d(create_material(materials, &visual.material)),
+                        visual_transform * Transform::from_translation(sdf_to_bevy_vector(*pos)),
+                        name,
+                        sdf_entity,
+                        Visibility::default(),
+                        InheritedVisibility::default(),
+                        ViewVisibility::default(),
+                    ));
+                }
+                geometry => {
+                    let (mesh_handle, material_handle) = create_visual_geometry(
+                        meshes, materials, geometry, &visual.material
+                    );
+                    parent.spawn((
+                        Mesh3d(mesh_handle),
+                        MeshMaterial3d(material_handle),
+                        visual_transform,
+                        name,
+                        sdf_entity,
+                        Visibility::default(),
+                        InheritedVisibility::default(),
+                        ViewVisibility::default(),
+                    ));
+                }
+            }
+        }
+    });
+    
+    // A link takes part in the simulation if it collides or has mass, so joints can attach to it
+    if !link.collisions.is_empty() || link.inertial.is_some() {
+        // Determine mass from inertial properties
+        let mass = link.inertial.as_ref().map(|i| i.mass).unwrap_or(1.0);
+        let is_static = model.static_;
+        // Without an inertia tensor the inertia follows from the collision shapes
+        let mass_properties = link.inertial.as_ref()
+            .filter(|inertial| inertial.has_inertia())
+            .map(inertial_mass_properties);
+
+        // Add rigid body and collision groups based on whether the model is static
+        let collision_groups = if is_static {
+            entity_cmd.insert(RigidBody::Fixed);
+            CollisionGroups::new(
+                STATIC_GROUP,
+                CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP | LIDAR_GROUP,
+            )
+        } else {
+            entity_cmd.insert(RigidBody::Dynamic);
+            entity_cmd.insert(match mass_properties {
+                Some(mass_properties) => AdditionalMassProperties::MassProperties(mass_properties),
+                None => AdditionalMassProperties::Mass(mass),
+            });
+            CollisionGroups::new(
+                CHASSIS_GROUP,
+                STATIC_GROUP | CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP | LIDAR_GROUP,
+            )
+        };
+
+        // Each collision is a child collider of the link body, at its pose within the link
+        entity_cmd.with_children(|parent| {
+            for collision in &link.collisions {
+                let mut collision_cmd = parent.spawn((
+                    sdf_pose_to_transform(&collision.pose),
+                    collision_groups,
+                    Name::new(format!("{}_{}_{}", model.name, link.name, collision.name)),
+                    link_entity(SdfElement::Collision, &collision.name),
                 ));
-            } else {
-                entity_cmd.insert(RigidBody::Dynamic);
-                // Apply mass; inertia tensor support will be wired via Rapier MassProperties in a follow-up system
-                entity_cmd.insert(AdditionalMassProperties::Mass(mass));
-                entity_cmd.insert(CollisionGroups::new(
-                    CHASSIS_GROUP,
-                    STATIC_GROUP | CHASSIS_INTERNAL_GROUP | CHASSIS_GROUP,
+                if let Some(surface) = &collision.surface {
+                    collision_cmd.insert(surface_material(surface));
+                }
+                if mass_properties.is_some() {
+                    // The inertial element alone sets the mass of the link
+                    collision_cmd.insert(ColliderMassProperties::Density(0.0));
+                }
+                if let SdfGeometry::Heightmap { uri, size, pos } = &collision.geometry {
+                    // Heightmap collisions become colliders once their image has loaded
+                    let Some(path) = resolve_mesh_uri(uri) else {
+                        println!("Warning: Could not resolve heightmap URI: {}", uri);
+                        continue;
+                    };
+                    let image = world.images.get(uri).cloned().unwrap_or_else(|| asset_server.load(path));
+                    let transform = sdf_pose_to_transform(&collision.pose) * Transform::from_translation(sdf_to_bevy_vector(*pos));
+                    collision_cmd.insert((SdfHeightmap { image, size: *size }, transform));
+                    continue;
+                }
+                let SdfGeometry::Mesh { uri, scale } = &collision.geometry else {
+                    if let Some(collider) = create_collider(&collision.geometry) {
+                        collision_cmd.insert(collider);
+                    }
+                    continue;
+                };
+                
+                // Mesh collisions become colliders once their mesh has loaded
+                let Some(path) = resolve_mesh_uri(uri) else {
+                    println!("Warning: Could not resolve mesh URI: {}", uri);
+                    continue;
+                };
+                let mesh = if is_gltf(&path) {
+                    asset_server.load(GltfAssetLabel::Primitive { mesh: 0, primitive: 0 }.from_asset(path.clone()))
+                } else if let Some(mesh) = world.meshes.get(uri) {
+                    mesh.clone()
+                } else {
+                    asset_server.load(path.clone())
+                };
+                let transform = sdf_pose_to_transform(&collision.pose) * mesh_transform(&path, *scale);
+                collision_cmd.insert((
+                    SdfMeshCollider {
+                        mesh,
+                        // Rapier triangle meshes have no volume, so moving bodies get convex pieces
+                        shape: if is_static {
+                            ComputedColliderShape::TriMesh(TriMeshFlags::MERGE_DUPLICATE_VERTICES)
+                        } else {
+                            ComputedColliderShape::ConvexDecomposition(VHACDParameters::default())
+                        },
+                    },
+                    transform,
                 ));
-                // Use Rapier default gravity
             }
+        });
 
-            // Apply per-link damping if specified
-            if link.linear_damping.is_some() || link.angular_damping.is_some() {
-                let d = Damping {
-                    linear_damping: link.linear_damping.unwrap_or(0.0),
-                    angular_damping: link.angular_damping.unwrap_or(0.0),
-                };
-                entity_cmd.insert(d);
+        // Apply per-link damping if specified
+        if link.linear_damping.is_some() || link.angular_damping.is_some() {
+            let d = Damping {
+                linear_damping: link.linear_damping.unwrap_or(0.0),
+                angular_damping: link.angular_damping.unwrap_or(0.0),
+            };
+            entity_cmd.insert(d);
+        }
+    }
+    
+    entity_cmd.id()
+}
+
+/// Attaches an SDF joint to its child link as a Rapier impulse joint
+fn spawn_sdf_joint(
+    commands: &mut Commands,
+    model_entity: Entity,
+    model_transform: Transform,
+    model: &SdfModel,
+    joint: &SdfJoint,
+    links: &HashMap<&str, (Entity, Transform)>,
+) {
+    let Some(&(child, child_transform)) = links.get(joint.child.as_str()) else {
+        println!("Warning: Joint {} has unknown child link {}", joint.name, joint.child);
+        return;
+    };
+    let (parent, parent_transform) = if joint.parent == "world" {
+        // The world is a fixed body at the origin, kept with the model so they are despawned together
+        let world = commands.spawn((
+            RigidBody::Fixed,
+            Transform::from_matrix(model_transform.compute_matrix().inverse()),
+            Name::new(format!("{}_{}_world", model.name, joint.name)),
+            ChildOf(model_entity),
+        )).id();
+        (world, Transform::IDENTITY)
+    } else if let Some(&parent) = links.get(joint.parent.as_str()) {
+        parent
+    } else {
+        println!("Warning: Joint {} has unknown parent link {}", joint.name, joint.parent);
+        return;
+    };
+    
+    if let Some(generic_joint) = create_joint(joint, &parent_transform, &child_transform) {
+        commands.entity(child).insert(ImpulseJoint::new(parent, TypedJoint::GenericJoint(generic_joint)));
+        if let Some(motor) = joint_motor(joint) {
+            commands.entity(child).insert(motor);
+        }
+    }
+}
+
+/// Damping, friction and limits of an SDF joint axis, applied through the motor of its joint
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct SdfJointMotor {
+    pub axis: JointAxis,
+    pub dynamics: SdfJointDynamics,
+    pub effort: f32,   // N·m or N, negative for unlimited
+    pub velocity: f32, // rad/s or m/s, negative for unlimited
+}
+
+/// The motor an SDF joint needs, if it has damping, friction or a velocity limit.
+///
+/// The effort limit only caps the motor, so on its own it needs none.
+fn joint_motor(joint: &SdfJoint) -> Option<SdfJointMotor> {
+    let axis = match joint.joint_type.as_str() {
+        "revolute" | "continuous" => JointAxis::AngX,
+        "prismatic" => JointAxis::LinX,
+        _ => return None,
+    };
+    let sdf_axis = joint.axis.as_ref()?;
+    let dynamics = sdf_axis.dynamics.clone().unwrap_or(SdfJointDynamics { damping: 0.0, friction: 0.0 });
+    let (effort, velocity) = sdf_axis.limit.as_ref().map_or((-1.0, -1.0), |limit| (limit.effort, limit.velocity));
+    if dynamics.damping <= 0.0 && dynamics.friction <= 0.0 && velocity < 0.0 {
+        return None;
+    }
+    Some(SdfJointMotor { axis, dynamics, effort, velocity })
+}
+
+/// Creates a Rapier joint from an SDF joint and the world transforms of its parent and child links.
+///
+/// The joint frame is the SDF joint pose with its X axis turned onto the SDF axis, so the joint
+/// position is zero in the pose the model was authored in.
+fn create_joint(joint: &SdfJoint, parent_transform: &Transform, child_transform: &Transform) -> Option<GenericJoint> {
+    let (locked_axes, free_axis) = match joint.joint_type.as_str() {
+        "revolute" | "continuous" => (JointAxesMask::LOCKED_REVOLUTE_AXES, JointAxis::AngX),
+        "prismatic" => (JointAxesMask::LOCKED_PRISMATIC_AXES, JointAxis::LinX),
+        "fixed" => (JointAxesMask::LOCKED_FIXED_AXES, JointAxis::AngX),
+        "ball" => (JointAxesMask::LOCKED_SPHERICAL_AXES, JointAxis::AngX),
+        _ => {
+            println!("Warning: Unsupported joint type {} for joint {}", joint.joint_type, joint.name);
+            return None;
+        }
+    };
+    
+    // Joint frame in the child link, then in the parent link
+    let joint_in_child = sdf_pose_to_transform(&joint.pose);
+    let axis = joint.axis.as_ref().map(|axis| sdf_to_bevy_vector(axis.xyz)).unwrap_or(Vec3::Y);
+    let basis2 = joint_in_child.rotation
+        * Quat::from_rotation_arc(Vec3::X, axis.try_normalize().unwrap_or(Vec3::Y));
+    let basis1 = parent_transform.rotation.inverse() * child_transform.rotation * basis2;
+    let anchor = child_transform.transform_point(joint_in_child.translation);
+    let anchor1 = parent_transform.rotation.inverse() * (anchor - parent_transform.translation);
+    
+    let mut builder = GenericJointBuilder::new(locked_axes)
+        .local_basis1(basis1)
+        .local_basis2(basis2)
+        .local_anchor1(anchor1)
+        .local_anchor2(joint_in_child.translation);
+    
+    if matches!(joint.joint_type.as_str(), "revolute" | "prismatic") {
+        // SDF uses +/-1e16 for an unbounded axis
+        if let Some(limit) = joint.axis.as_ref().and_then(|axis| axis.limit.as_ref()) {
+            if limit.lower <= limit.upper && limit.lower > -1e15 && limit.upper < 1e15 {
+                builder = builder.limits(free_axis, [limit.lower, limit.upper]);
             }
         }
     }
+    
+    // Damping, friction and the velocity limit act through a motor set before every physics step
+    if joint_motor(joint).is_some() {
+        builder = builder.motor_model(free_axis, MotorModel::ForceBased);
+    }
+    
+    // Like Gazebo, links connected by a joint do not collide with each other
+    let mut generic_joint = builder.build();
+    generic_joint.set_contacts_enabled(false);
+    Some(generic_joint)
 }
 
 /// Creates visual geometry from SDF geometry
@@ -702,7 +1909,8 @@ fn create_visual_geometry(
 ) -> (Handle<Mesh>, Handle<StandardMaterial>) {
     let mesh_handle = match geometry {
         SdfGeometry::Box { size } => {
-            meshes.add(Mesh::from(Cuboid::new(size.x, size.y, size.z)))
+            // SDF sizes are Z-up
+            meshes.add(Mesh::from(Cuboid::new(size.x, size.z, size.y)))
         }
         SdfGeometry::Sphere { radius } => {
             meshes.add(Mesh::from(Sphere { radius: *radius, ..Default::default() }))
@@ -710,17 +1918,33 @@ fn create_visual_geometry(
         SdfGeometry::Cylinder { radius, length } => {
             meshes.add(Mesh::from(Cylinder { radius: *radius, half_height: *length / 2.0, ..Default::default() }))
         }
+        SdfGeometry::Capsule { radius, length } => {
+            meshes.add(Mesh::from(Capsule3d::new(*radius, *length)))
+        }
+        SdfGeometry::Ellipsoid { radii } => {
+            meshes.add(ellipsoid_mesh(*radii))
+        }
         SdfGeometry::Plane { normal: _, size } => {
             meshes.add(Plane3d::default().mesh().size(size.x, size.y))
         }
-        SdfGeometry::Mesh { uri: _, scale: _ } => {
-            // For now, use a simple box as fallback
-            println!("Warning: Mesh loading not fully implemented");
-            meshes.add(Mesh::from(Cuboid::new(1.0, 1.0, 1.0)))
+        SdfGeometry::Polyline { polylines } => {
+            meshes.add(polyline_mesh(polylines))
+        }
+        SdfGeometry::Mesh { .. } | SdfGeometry::Heightmap { .. } => {
+            // Mesh and heightmap visuals are loaded from their asset by spawn_sdf_link
+            Handle::default()
         }
     };
     
-    let material_handle = if let Some(sdf_material) = material {
+    (mesh_handle, create_material(materials, material))
+}
+
+/// Creates a Bevy material from an SDF material
+fn create_material(
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    material: &Option<SdfMaterial>,
+) -> Handle<StandardMaterial> {
+    if let Some(sdf_material) = material {
         let color = sdf_material.diffuse.unwrap_or(Color::srgb(0.7, 0.7, 0.7));
         materials.add(StandardMaterial {
             base_color: color,
@@ -731,91 +1955,521 @@ fn create_visual_geometry(
             base_color: Color::srgb(0.7, 0.7, 0.7),
             ..Default::default()
         })
-    };
-    
-    (mesh_handle, material_handle)
+    }
+}
+
+/// Rapier mass properties of an SDF inertial, in the Y-up frame of its link.
+///
+/// The inertia tensor is rotated by the inertial pose, then diagonalised into principal axes.
+fn inertial_mass_properties(inertial: &SdfInertial) -> MassProperties {
+    let inertial_transform = sdf_pose_to_transform(&inertial.pose);
+    let conv = Mat3::from_quat(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
+    let rotation = Mat3::from_quat(inertial_transform.rotation);
+    let tensor = Mat3::from_cols_array(&[
+        inertial.ixx, inertial.ixy, inertial.ixz,
+        inertial.ixy, inertial.iyy, inertial.iyz,
+        inertial.ixz, inertial.iyz, inertial.izz,
+    ]);
+    let inertia = rotation * conv * tensor * conv.transpose() * rotation.transpose();
+    MassProperties::from_rapier(RapierMassProperties::with_inertia_matrix(
+        inertial_transform.translation.into(),
+        inertial.mass,
+        inertia.into(),
+    ))
 }
 
-/// Creates a collider from SDF geometry
-fn create_collider(geometry: &SdfGeometry) -> Collider {
+/// Rapier contact materials of an SDF surface.
+///
+/// Rapier friction is isotropic, so `mu2` is not used. Like ODE, the lower friction and the higher
+/// restitution of two touching surfaces apply.
+fn surface_material(surface: &SdfSurface) -> (Friction, Restitution) {
+    (
+        Friction {
+            coefficient: surface.mu,
+            combine_rule: CoefficientCombineRule::Min,
+        },
+        Restitution {
+            coefficient: surface.restitution_coefficient,
+            combine_rule: CoefficientCombineRule::Max,
+        },
+    )
+}
+
+/// Creates a collider from primitive SDF geometry
+fn create_collider(geometry: &SdfGeometry) -> Option<Collider> {
     match geometry {
         SdfGeometry::Box { size } => {
-            Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0)
+            Some(Collider::cuboid(size.x / 2.0, size.z / 2.0, size.y / 2.0))
         }
         SdfGeometry::Sphere { radius } => {
-            Collider::ball(*radius)
+            Some(Collider::ball(*radius))
         }
         SdfGeometry::Cylinder { radius, length } => {
-            Collider::cylinder(*length / 2.0, *radius)
+            Some(Collider::cylinder(*length / 2.0, *radius))
+        }
+        SdfGeometry::Capsule { radius, length } => {
+            Some(Collider::capsule_y(*length / 2.0, *radius))
+        }
+        SdfGeometry::Ellipsoid { radii } => {
+            ellipsoid_collider(*radii)
         }
         SdfGeometry::Plane { normal: _, size } => {
             // Create a thin box for the plane
-            Collider::cuboid(size.x / 2.0, 0.01, size.y / 2.0)
+            Some(Collider::cuboid(size.x / 2.0, 0.01, size.y / 2.0))
         }
-        SdfGeometry::Mesh { uri: _, scale: _ } => {
-            // Fallback to a box collider for meshes
-            Collider::cuboid(0.5, 0.5, 0.5)
+        SdfGeometry::Polyline { polylines } => {
+            polyline_collider(polylines)
+        }
+        SdfGeometry::Mesh { .. } | SdfGeometry::Heightmap { .. } => {
+            // Mesh and heightmap colliders are built from the loaded asset by
+            // sdf_mesh_collider_system and sdf_heightmap_system
+            None
         }
     }
 }
 
-/// Spawns a single SDF light as a Bevy light
-fn spawn_sdf_light(commands: &mut Commands, light: &SdfLight) {
-    let light_transform = sdf_pose_to_transform(&light.pose);
+/// System to apply the damping, friction and limits of SDF joints through their motors.
+///
+/// Runs before every physics step. Within the velocity limit, the motor holds the joint still with
+/// up to the Coulomb friction plus the viscous damping at the current joint velocity; beyond it,
+/// the motor brakes the joint back to the limit. It never pushes harder than the effort limit.
+pub fn sdf_joint_motor_system(
+    rapier_context: ReadRapierContext,
+    mut joints: Query<(Entity, &SdfJointMotor, &mut ImpulseJoint)>,
+) {
+    let Ok(rapier_context) = rapier_context.single() else {
+        return;
+    };
+    let body_set = &rapier_context.rigidbody_set;
+    let body = |entity: Entity| body_set.entity2body().get(&entity).and_then(|handle| body_set.bodies.get(*handle));
+    let velocity_at = |body: &RapierRigidBody, point: Vec3| {
+        Vec3::from(*body.linvel())
+            + Vec3::from(*body.angvel()).cross(point - Vec3::from(body.center_of_mass().coords))
+    };
     
-    match light.light_type.as_str() {
+    for (entity, motor, mut joint) in joints.iter_mut() {
+        // Rapier bodies only exist from the first physics step after spawning
+        let (Some(child), Some(parent)) = (body(entity), body(joint.parent)) else {
+            continue;
+        };
+        // Global transforms are only propagated once a frame, so the joint is posed from Rapier
+        let child_pose = iso_to_transform(child.position());
+        let axis = child_pose.rotation * joint.data.as_ref().local_axis2();
+        let speed = match motor.axis {
+            JointAxis::AngX => (Vec3::from(*child.angvel()) - Vec3::from(*parent.angvel())).dot(axis),
+            _ => {
+                let anchor = child_pose.transform_point(joint.data.as_ref().local_anchor2());
+                (velocity_at(child, anchor) - velocity_at(parent, anchor)).dot(axis)
+            }
+        };
+        
+        let SdfJointDynamics { damping, friction } = motor.dynamics;
+        let (target, gain, max_force) = if motor.velocity >= 0.0 && speed.abs() > motor.velocity {
+            (motor.velocity.copysign(speed), damping.max(JOINT_FRICTION_GAIN), f32::MAX)
+        } else if friction > 0.0 || damping <= 0.0 {
+            (0.0, damping.max(JOINT_FRICTION_GAIN), friction + damping * speed.abs())
+        } else {
+            // Without friction the motor gain is exactly the viscous damping
+            (0.0, damping, f32::MAX)
+        };
+        let max_force = if motor.effort >= 0.0 { max_force.min(motor.effort) } else { max_force };
+        
+        // Only touch the joint when the motor changes so Rapier is not re-synced every step
+        let unchanged = joint.data.as_ref().motor(motor.axis).is_some_and(|current| {
+            current.target_vel == target && current.damping == gain && current.max_force == max_force
+        });
+        if !unchanged {
+            joint.data.as_mut()
+                .set_motor_velocity(motor.axis, target, gain)
+                .set_motor_max_force(motor.axis, max_force);
+        }
+    }
+}
+
+/// Collision mesh waiting for its asset to load before it becomes a collider
+#[derive(Component)]
+pub struct SdfMeshCollider {
+    pub mesh: Handle<Mesh>,
+    pub shape: ComputedColliderShape,
+}
+
+/// System to turn collision meshes into colliders once they have loaded
+pub fn sdf_mesh_collider_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    meshes: Res<Assets<Mesh>>,
+    pending: Query<(Entity, &SdfMeshCollider, &Name)>,
+) {
+    for (entity, mesh_collider, name) in pending.iter() {
+        if let Some(mesh) = meshes.get(&mesh_collider.mesh) {
+            match Collider::from_bevy_mesh(mesh, &mesh_collider.shape) {
+                Some(collider) => {
+                    commands.entity(entity).insert(collider);
+                }
+                None => warn!("Collision mesh of {} has no usable triangles", name),
+            }
+            commands.entity(entity).remove::<SdfMeshCollider>();
+        } else if let Some(LoadState::Failed(e)) = asset_server.get_load_state(&mesh_collider.mesh) {
+            warn!("Failed to load collision mesh of {}: {}", name, e);
+            commands.entity(entity).remove::<SdfMeshCollider>();
+        }
+    }
+}
+
+/// Heightmap waiting for its image to load before its mesh, or collider for a collision, is built
+#[derive(Component)]
+pub struct SdfHeightmap {
+    pub image: Handle<Image>,
+    pub size: Vec3,
+}
+
+/// System to build heightmap meshes and colliders once their images have loaded
+pub fn sdf_heightmap_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    pending: Query<(Entity, &SdfHeightmap, &SdfEntity, &Name)>,
+) {
+    for (entity, heightmap, sdf_entity, name) in pending.iter() {
+        if let Some(image) = images.get(&heightmap.image) {
+            match HeightGrid::from_image(image) {
+                Some(grid) if sdf_entity.element == SdfElement::Visual => {
+                    commands.entity(entity).insert(Mesh3d(meshes.add(heightmap_mesh(&grid, heightmap.size))));
+                }
+                Some(grid) => {
+                    commands.entity(entity).insert(heightmap_collider(&grid, heightmap.size));
+                }
+                None => warn!("Heightmap image of {} is smaller than 2x2 pixels", name),
+            }
+            commands.entity(entity).remove::<SdfHeightmap>();
+        } else if let Some(LoadState::Failed(e)) = asset_server.get_load_state(&heightmap.image) {
+            warn!("Failed to load heightmap image of {}: {}", name, e);
+            commands.entity(entity).remove::<SdfHeightmap>();
+        }
+    }
+}
+
+/// Resolves an SDF mesh URI to an asset path
+pub fn resolve_mesh_uri(uri: &str) -> Option<String> {
+    // Handle different URI schemes
+    if let Some(path) = uri.strip_prefix("file://") {
+        Some(path.to_string())
+    } else if uri.starts_with("model://") {
+        // Gazebo model:// URIs load from the "model" asset source, which searches the model path
+        Some(uri.to_string())
+    } else if uri.starts_with("package://") {
+        // Convert ROS package:// to relative path
+        let parts: Vec<&str> = uri.split('/').collect();
+        if parts.len() >= 3 {
+            let package_name = parts[2];
+            let path_parts = &parts[3..];
+            Some(format!("packages/{}/{}", package_name, path_parts.join("/")))
+        } else {
+            None
+        }
+    } else if uri.is_empty() {
+        None
+    } else {
+        // Asset paths are relative to the assets directory
+        Some(uri.strip_prefix("assets/").unwrap_or(uri).to_string())
+    }
+}
+
+/// Whether an asset path names a glTF file, which loads as a scene rather than a mesh
+pub fn is_gltf(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    path.ends_with(".glb") || path.ends_with(".gltf")
+}
+
+/// Local transform applying a mesh's SDF scale, and turning Z-up mesh formats to Y-up
+fn mesh_transform(path: &str, scale: Option<Vec3>) -> Transform {
+    let scale = scale.unwrap_or(Vec3::ONE);
+    if is_gltf(path) {
+        // glTF is Y-up already
+        Transform::from_scale(sdf_to_bevy_vector(scale).abs())
+    } else {
+        // STL, OBJ and COLLADA vertices are in the Z-up link frame
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)).with_scale(scale)
+    }
+}
+
+/// System to load the SDF worlds requested with `LoadSdfWorldRequest` through the asset server
+pub fn load_sdf_world_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut requests: EventReader<LoadSdfWorldRequest>,
+) {
+    for request in requests.read() {
+        info!("Loading SDF world: {}", request.sdf_path);
+        commands.spawn((
+            SdfWorldHandle(asset_server.load(&request.sdf_path)),
+            Transform::from_translation(request.spawn_position).with_rotation(request.spawn_rotation),
+            Visibility::default(),
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+        ));
+    }
+}
+
+/// System to spawn SDF world assets once they have loaded, and respawn what changes when they are reloaded
+#[allow(clippy::too_many_arguments)]
+pub fn sdf_world_asset_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    worlds: Res<Assets<SdfWorld>>,
+    mut asset_events: EventReader<AssetEvent<SdfWorld>>,
+    roots: Query<(Entity, &SdfWorldHandle, Option<&SpawnedSdfWorld>, Option<&Children>)>,
+    sdf_entities: Query<&SdfEntity>,
+    mut loaded_events: EventWriter<SdfWorldLoaded>,
+    mut failed_events: EventWriter<SdfWorldLoadFailed>,
+) {
+    let modified: HashSet<AssetId<SdfWorld>> = asset_events.read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    
+    for (entity, handle, spawned, children) in roots.iter() {
+        let sdf_path = handle.0.path().map(|path| path.to_string()).unwrap_or_default();
+        match spawned {
+            None => {
+                if let Some(world) = worlds.get(&handle.0) {
+                    populate_sdf_world(&mut commands, &mut meshes, &mut materials, &asset_server, entity, world);
+                    commands.entity(entity).insert(SpawnedSdfWorld(world.clone()));
+                    loaded_events.write(SdfWorldLoaded {
+                        sdf_path,
+                        world_name: world.name.clone(),
+                        entity,
+                    });
+                } else if let Some(LoadState::Failed(error)) = asset_server.get_load_state(&handle.0) {
+                    error!("Failed to load SDF world {}: {}", sdf_path, error);
+                    commands.entity(entity).despawn();
+                    failed_events.write(SdfWorldLoadFailed {
+                        sdf_path,
+                        error: error.to_string(),
+                    });
+                }
+            }
+            Some(SpawnedSdfWorld(old_world)) if modified.contains(&handle.0.id()) => {
+                let Some(world) = worlds.get(&handle.0) else {
+                    continue;
+                };
+                info!("Reloading SDF world: {}", sdf_path);
+                respawn_changed_sdf_world(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &asset_server,
+                    entity,
+                    children.map_or(&[], |children| &children[..]),
+                    &sdf_entities,
+                    old_world,
+                    world,
+                );
+                commands.entity(entity).insert(SpawnedSdfWorld(world.clone()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to apply the physics settings of the spawned SDF world.
+///
+/// Gravity goes to the Rapier configuration, `max_step_size` becomes the fixed timestep that
+/// Rapier steps at and `real_time_factor` sets the speed of the virtual clock that the fixed
+/// steps follow.
+pub fn sdf_physics_system(
+    physics: Res<SdfPhysics>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut rapier_configs: Query<&mut RapierConfiguration>,
+) {
+    info!(
+        "SDF physics {}: gravity={:?}, max_step_size={}, real_time_factor={}",
+        physics.name, physics.gravity, physics.max_step_size, physics.real_time_factor
+    );
+    for mut config in rapier_configs.iter_mut() {
+        config.gravity = sdf_to_bevy_vector(physics.gravity);
+    }
+    if physics.max_step_size > 0.0 {
+        // Steps are taken while the physics lags behind the virtual clock, each exactly max_step_size long
+        fixed_time.set_timestep_seconds(physics.max_step_size as f64);
+    } else {
+        warn!("Ignoring SDF max_step_size {}", physics.max_step_size);
+    }
+    if physics.real_time_factor > 0.0 {
+        virtual_time.set_relative_speed(physics.real_time_factor);
+    } else {
+        warn!("Ignoring SDF real_time_factor {}", physics.real_time_factor);
+    }
+}
+
+/// Plugin for loading SDF worlds, their assets and the systems that finish spawning them.
+///
+/// SDF worlds are assets, so with Bevy's `file_watcher` feature enabled
+/// (`cargo run --features bevy/file_watcher`) edits to a `.sdf` file show up without a restart.
+/// Included models and `model://` URIs are looked up in the [`SdfModelPath`] resource.
+/// The physics settings of the spawned world, or the SDF defaults, are applied to Rapier.
+pub struct SdfPlugin;
+
+impl Plugin for SdfPlugin {
+    fn build(&self, app: &mut App) {
+        let has_model_source = app.world().get_resource::<AssetServer>()
+            .is_some_and(|asset_server| asset_server.get_source(MODEL_ASSET_SOURCE).is_ok());
+        if !has_model_source {
+            warn!("No \"{}\" asset source, model:// meshes will not load; add SdfModelPathPlugin before the AssetPlugin", MODEL_ASSET_SOURCE);
+        }
+
+        app.init_resource::<SdfModelPath>()
+            .init_asset::<SdfWorld>()
+            .init_asset_loader::<SdfWorldLoader>()
+            .init_asset_loader::<ColladaLoader>()
+            .add_event::<LoadSdfWorldRequest>()
+            .add_event::<SdfWorldLoaded>()
+            .add_event::<SdfWorldLoadFailed>()
+            .add_systems(
+                Update,
+                (
+                    load_sdf_world_system,
+                    sdf_world_asset_system,
+                    sdf_physics_system.run_if(resource_exists_and_changed::<SdfPhysics>),
+                    sdf_mesh_collider_system,
+                    sdf_heightmap_system,
+                ).chain(),
+            )
+            .add_systems(FixedUpdate, sdf_joint_motor_system.before(PhysicsSet::SyncBackend));
+    }
+}
+
+/// Spawns a single SDF light as a Bevy light in the world
+fn spawn_sdf_light(commands: &mut Commands, world_entity: Entity, world: &SdfWorld, light: &SdfLight) {
+    let mut light_transform = sdf_pose_to_transform(&light.pose);
+    // Bevy spot and directional lights shine along their local -Z
+    if let Some(direction) = sdf_to_bevy_vector(light.direction).try_normalize() {
+        light_transform.rotate_local(Quat::from_rotation_arc(Vec3::NEG_Z, direction));
+    }
+    let name = Name::new(format!("SDF_Light_{}", light.name));
+    
+    let entity = match light.light_type.as_str() {
         "point" => {
             commands.spawn((
-                PointLight::default(),
+                PointLight {
+                    color: light.diffuse,
+                    intensity: POINT_LIGHT_INTENSITY * light.intensity,
+                    range: light.attenuation.range,
+                    shadows_enabled: light.cast_shadows,
+                    ..default()
+                },
                 light_transform,
+                name,
                 Visibility::default(),
                 InheritedVisibility::default(),
                 ViewVisibility::default(),
-            ));
+            )).id()
         }
         "directional" => {
             commands.spawn((
                 DirectionalLight {
-                    shadows_enabled: false,
-                    illuminance: 1000.0,
+                    color: light.diffuse,
+                    illuminance: DIRECTIONAL_LIGHT_ILLUMINANCE * light.intensity,
+                    shadows_enabled: light.cast_shadows,
                     ..default()
                 },
                 light_transform,
+                name,
                 Visibility::default(),
                 InheritedVisibility::default(),
                 ViewVisibility::default(),
-            ));
+            )).id()
         }
         "spot" => {
-            // Bevy doesn't have a built-in spot light, so we'll use a point light
+            // SDF cone angles are full angles, Bevy's are measured from the axis; Bevy has no falloff exponent
+            let (inner_angle, outer_angle) = light.spot.as_ref()
+                .map(|spot| (0.5 * spot.inner_angle, 0.5 * spot.outer_angle))
+                .unwrap_or((0.0, std::f32::consts::FRAC_PI_4));
+            let outer_angle = outer_angle.clamp(0.0, std::f32::consts::FRAC_PI_2);
             commands.spawn((
-                PointLight::default(),
+                SpotLight {
+                    color: light.diffuse,
+                    intensity: POINT_LIGHT_INTENSITY * light.intensity,
+                    range: light.attenuation.range,
+                    shadows_enabled: light.cast_shadows,
+                    inner_angle: inner_angle.clamp(0.0, outer_angle),
+                    outer_angle,
+                    ..default()
+                },
                 light_transform,
+                name,
                 Visibility::default(),
                 InheritedVisibility::default(),
                 ViewVisibility::default(),
-            ));
+            )).id()
         }
         _ => {
             println!("Warning: Unknown light type: {}", light.light_type);
+            return;
         }
-    }
+    };
+    commands.entity(entity).insert((
+        SdfEntity {
+            element: SdfElement::Light,
+            name: light.name.clone(),
+            world_name: world.name.clone(),
+            model_name: None,
+            link_name: None,
+        },
+        SpawnedSdfLight(light.clone()),
+        ChildOf(world_entity),
+    ));
+}
+
+/// Converts an SDF (Z-up) vector to Bevy/Rapier (Y-up) axes
+fn sdf_to_bevy_vector(v: Vec3) -> Vec3 {
+    // Rotating -90° around X: (x, y, z) -> (x, z, -y)
+    Vec3::new(v.x, v.z, -v.y)
+}
+
+/// Converts a Bevy/Rapier (Y-up) vector to SDF (Z-up) axes
+pub fn bevy_to_sdf_vector(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, -v.z, v.y)
 }
 
-/// Converts SDF pose to Bevy transform
-fn sdf_pose_to_transform(pose: &SdfPose) -> Transform {
-    // Convert SDF (Z-up) coordinates to Bevy/Rapier (Y-up): (x, y, z) -> (x, z, y)
-    let translation = Vec3::new(pose.xyz.x, pose.xyz.z, pose.xyz.y);
-    // Convert orientation: rotate frames -90° around X so Z-up becomes Y-up
-    let rot_sdf = Quat::from_euler(EulerRot::XYZ, pose.rpy.x, pose.rpy.y, pose.rpy.z);
+/// Converts an SDF pose relative to its parent frame to a Bevy transform.
+///
+/// Every frame stays Y-up, so nested poses compose with plain transform multiplication.
+pub fn sdf_pose_to_transform(pose: &SdfPose) -> Transform {
     let conv = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
-    let rotation = conv * rot_sdf;
+    let translation = sdf_to_bevy_vector(pose.xyz);
+    // SDF roll, pitch and yaw are about the fixed X, Y and Z axes: R = Rz(yaw) * Ry(pitch) * Rx(roll)
+    let rot_sdf = Quat::from_euler(EulerRot::ZYX, pose.rpy.z, pose.rpy.y, pose.rpy.x);
+    let rotation = conv * rot_sdf * conv.inverse();
     Transform::from_translation(translation).with_rotation(rotation)
 }
 
+/// Converts a Bevy transform to an SDF pose, the inverse of `sdf_pose_to_transform`; scale is dropped
+pub fn transform_to_sdf_pose(transform: &Transform) -> SdfPose {
+    let conv = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+    let rot_sdf = conv.inverse() * transform.rotation * conv;
+    let (yaw, pitch, roll) = rot_sdf.to_euler(EulerRot::ZYX);
+    SdfPose {
+        xyz: bevy_to_sdf_vector(transform.translation),
+        rpy: Vec3::new(roll, pitch, yaw),
+    }
+}
+
+// Velocity motor gain emulating Coulomb joint friction and the velocity limit (N·m·s/rad or N·s/m)
+const JOINT_FRICTION_GAIN: f32 = 1000.0;
+
+// Brightness of an SDF light with intensity 1
+const POINT_LIGHT_INTENSITY: f32 = 1_000_000.0; // lumens, Bevy's default point light
+const DIRECTIONAL_LIGHT_ILLUMINANCE: f32 = 1000.0; // lux
+
 // Re-export collision groups for consistency
 pub const STATIC_GROUP: Group = Group::GROUP_1;
 pub const CHASSIS_INTERNAL_GROUP: Group = Group::GROUP_2;
 pub const CHASSIS_GROUP: Group = Group::GROUP_3;
-
-// No custom gravity system; Rapier's global gravity (0, -9.81, 0) applies.
+pub const LIDAR_GROUP: Group = Group::GROUP_4;
@@ -0,0 +1,209 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::io::{
+    AssetReader, AssetReaderError, AssetSource, AssetSourceBuilder, AssetWatcher, PathStream,
+    Reader,
+};
+use bevy::prelude::*;
+use quick_xml::Reader as XmlReader;
+use quick_xml::events::Event;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable listing model directories, separated like `PATH`
+pub const MODEL_PATH_ENV: &str = "GZ_SIM_RESOURCE_PATH";
+
+/// Asset source that `model://` URIs are loaded from
+pub const MODEL_ASSET_SOURCE: &str = "model";
+
+/// Directories searched for the models that `model://name` URIs refer to.
+///
+/// A model is a directory named after it, holding a `model.config` and the SDF file it lists.
+/// Directories are searched in order; the `models` directory of the assets comes last.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SdfModelPath {
+    pub dirs: Vec<PathBuf>,
+}
+
+impl SdfModelPath {
+    /// Search path made of `dirs`, then the directories in the model path environment variable
+    pub fn new(dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut dirs: Vec<PathBuf> = dirs.into_iter().collect();
+        if let Some(env_dirs) = std::env::var_os(MODEL_PATH_ENV) {
+            dirs.extend(std::env::split_paths(&env_dirs).filter(|dir| !dir.as_os_str().is_empty()));
+        }
+        dirs.push(FileAssetReader::get_base_path().join("assets/models"));
+        // Relative directories are relative to the working directory, not the asset root
+        let dirs = dirs
+            .into_iter()
+            .map(|dir| std::path::absolute(&dir).unwrap_or(dir))
+            .collect();
+        SdfModelPath { dirs }
+    }
+
+    /// Finds the directory of the model a `model://name/...` URI refers to
+    pub fn find_model(&self, uri: &str) -> Option<PathBuf> {
+        let name = uri.strip_prefix("model://")?.split('/').next()?;
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|dir| dir.is_dir())
+    }
+}
+
+impl Default for SdfModelPath {
+    fn default() -> Self {
+        SdfModelPath::new([])
+    }
+}
+
+/// Returns the SDF file of a model directory, as listed in its `model.config` content.
+///
+/// The highest SDF version listed wins; without a config, `model.sdf` is assumed.
+pub fn model_sdf_file(model_dir: &Path, config: Option<&str>) -> PathBuf {
+    let file = config
+        .and_then(newest_sdf_entry)
+        .unwrap_or_else(|| "model.sdf".to_string());
+    model_dir.join(file)
+}
+
+/// `<sdf version="...">file</sdf>` entry of a `model.config` with the highest version
+fn newest_sdf_entry(config: &str) -> Option<String> {
+    let mut reader = XmlReader::from_str(config);
+    reader.trim_text(true);
+
+    let mut newest: Option<(Vec<u32>, String)> = None;
+    let mut version = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"sdf" => {
+                let attribute = e.try_get_attribute("version").ok().flatten();
+                version = Some(
+                    attribute
+                        .and_then(|attribute| String::from_utf8(attribute.value.to_vec()).ok())
+                        .map(|version| {
+                            version
+                                .split('.')
+                                .filter_map(|part| part.trim().parse().ok())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                );
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some(version), Ok(file)) = (version.take(), text.unescape()) {
+                    if newest.as_ref().is_none_or(|(newest, _)| version > *newest) {
+                        newest = Some((version, file.trim().to_string()));
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    newest.map(|(_, file)| file)
+}
+
+/// Reads `model://` assets from the first model directory that has them
+struct ModelPathReader {
+    readers: Vec<FileAssetReader>,
+}
+
+impl ModelPathReader {
+    fn new(model_path: &SdfModelPath) -> Self {
+        ModelPathReader {
+            readers: model_path.dirs.iter().map(FileAssetReader::new).collect(),
+        }
+    }
+}
+
+impl AssetReader for ModelPathReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        for reader in &self.readers {
+            match reader.read(path).await {
+                Err(AssetReaderError::NotFound(_)) => continue,
+                result => return result,
+            }
+        }
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        for reader in &self.readers {
+            match reader.read_meta(path).await {
+                Err(AssetReaderError::NotFound(_)) => continue,
+                result => return result,
+            }
+        }
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        for reader in &self.readers {
+            match reader.read_directory(path).await {
+                Err(AssetReaderError::NotFound(_)) => continue,
+                result => return result,
+            }
+        }
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        for reader in &self.readers {
+            match reader.is_directory(path).await {
+                Err(AssetReaderError::NotFound(_)) | Ok(false) => continue,
+                result => return result,
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Watches every model directory for changes
+struct ModelPathWatcher {
+    _watchers: Vec<Box<dyn AssetWatcher>>,
+}
+
+impl AssetWatcher for ModelPathWatcher {}
+
+/// Plugin registering the model search path and the `model://` asset source.
+///
+/// Asset sources must be registered before the `AssetPlugin`, so add this plugin before
+/// `DefaultPlugins`.
+#[derive(Default)]
+pub struct SdfModelPathPlugin(pub SdfModelPath);
+
+impl Plugin for SdfModelPathPlugin {
+    fn build(&self, app: &mut App) {
+        let model_path = self.0.clone();
+        let dirs = model_path.dirs.clone();
+        app.insert_resource(model_path.clone())
+            .register_asset_source(
+                MODEL_ASSET_SOURCE,
+                AssetSourceBuilder::default()
+                    .with_reader(move || Box::new(ModelPathReader::new(&model_path)))
+                    .with_watcher(move |sender| {
+                        let watchers: Vec<Box<dyn AssetWatcher>> = dirs
+                            .iter()
+                            .filter(|dir| dir.is_dir())
+                            .filter_map(|dir| {
+                                let path = dir.to_string_lossy().into_owned();
+                                let mut watcher = AssetSource::get_default_watcher(
+                                    path,
+                                    Duration::from_millis(300),
+                                );
+                                watcher(sender.clone())
+                            })
+                            .collect();
+                        if watchers.is_empty() {
+                            return None;
+                        }
+                        Some(Box::new(ModelPathWatcher {
+                            _watchers: watchers,
+                        }))
+                    }),
+            );
+    }
+}
@@ -0,0 +1,308 @@
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::fmt;
+use std::path::Path;
+
+use crate::sdf_loader::{
+    PoseRotation, SDF_ELEMENTS, SdfError, SdfLocation, locate, parse_bool, parse_color,
+    parse_number, parse_pose, parse_sdf_file, parse_vec2, parse_vec3,
+};
+use crate::sdf_model_path::SdfModelPath;
+
+/// Oldest and newest SDF versions the loader is written against
+const SUPPORTED_VERSIONS: ((u32, u32), (u32, u32)) = ((1, 4), (1, 11));
+
+/// How serious a validation finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdfSeverity {
+    /// The file loads, but part of it is ignored or may load differently than intended
+    Warning,
+    /// The file does not load
+    Error,
+}
+
+impl fmt::Display for SdfSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdfSeverity::Warning => write!(f, "warning"),
+            SdfSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in an SDF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfDiagnostic {
+    pub severity: SdfSeverity,
+    /// Missing when the file could not be read at all
+    pub location: Option<SdfLocation>,
+    pub message: String,
+}
+
+impl fmt::Display for SdfDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}: {}", self.severity, location, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+impl From<SdfError> for SdfDiagnostic {
+    fn from(error: SdfError) -> Self {
+        let (location, message) = match error {
+            SdfError::Io { file, message } => (None, format!("{}: {}", file, message)),
+            SdfError::Xml { location, message } => {
+                (Some(location), format!("invalid XML: {}", message))
+            }
+            SdfError::InvalidValue { location, message }
+            | SdfError::Include { location, message } => (Some(location), message),
+        };
+        SdfDiagnostic {
+            severity: SdfSeverity::Error,
+            location,
+            message,
+        }
+    }
+}
+
+/// Elements holding a single number
+const NUMBER_ELEMENTS: &[&str] = &[
+    "mass",
+    "ixx",
+    "iyy",
+    "izz",
+    "ixy",
+    "ixz",
+    "iyz",
+    "radius",
+    "length",
+    "linear",
+    "angular",
+    "max_step_size",
+    "real_time_factor",
+    "real_time_update_rate",
+    "mu",
+    "mu2",
+    "restitution_coefficient",
+    "kp",
+    "kd",
+    "lower",
+    "upper",
+    "effort",
+    "velocity",
+    "damping",
+    "range",
+    "constant",
+    "quadratic",
+    "inner_angle",
+    "outer_angle",
+    "falloff",
+    "intensity",
+    "height",
+];
+
+/// Type of the value an element holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Number,
+    Bool,
+    Vec2,
+    Vec3,
+    Pose,
+    Color,
+}
+
+/// Type of the value of `element`, when it holds one, given the element it is in
+fn value_kind(element: &str, parent: &str) -> Option<ValueKind> {
+    Some(match (element, parent) {
+        ("pose", _) => ValueKind::Pose,
+        // A link's <gravity> turns gravity on or off; elsewhere it is the gravity vector
+        ("gravity", "link") => ValueKind::Bool,
+        ("gravity", _) => ValueKind::Vec3,
+        ("size", "plane") => ValueKind::Vec2,
+        ("point", _) => ValueKind::Vec2,
+        ("size", _) | ("xyz" | "normal" | "scale" | "direction" | "radii" | "pos", _) => {
+            ValueKind::Vec3
+        }
+        ("ambient" | "diffuse" | "specular" | "emissive" | "background", _) => ValueKind::Color,
+        ("static" | "cast_shadows", _) => ValueKind::Bool,
+        // A surface's <friction> only groups the friction coefficients
+        ("friction", "dynamics") => ValueKind::Number,
+        (element, _) if NUMBER_ELEMENTS.contains(&element) => ValueKind::Number,
+        _ => return None,
+    })
+}
+
+/// Checks that `text` is a value of the given kind; a pose's rotation is written as `rotation` says
+fn check_value(kind: ValueKind, text: &str, rotation: PoseRotation) -> Result<(), String> {
+    match kind {
+        ValueKind::Number => parse_number(text).map(drop),
+        ValueKind::Bool => parse_bool(text).map(drop),
+        ValueKind::Vec2 => parse_vec2(text).map(drop),
+        ValueKind::Vec3 => parse_vec3(text).map(drop),
+        ValueKind::Pose => parse_pose(text, rotation).map(drop),
+        ValueKind::Color => parse_color(text).map(drop),
+    }
+}
+
+/// Checks the `version` attribute of the `<sdf>` element
+fn check_version(e: &BytesStart) -> Option<(SdfSeverity, String)> {
+    let Some(version) = e
+        .try_get_attribute("version")
+        .ok()
+        .flatten()
+        .and_then(|attribute| String::from_utf8(attribute.value.to_vec()).ok())
+    else {
+        return Some((SdfSeverity::Warning, "<sdf> has no version".to_string()));
+    };
+    let parsed = version
+        .split_once('.')
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+    let Some(parsed) = parsed else {
+        return Some((
+            SdfSeverity::Error,
+            format!("malformed SDF version {:?}", version),
+        ));
+    };
+    let (oldest, newest) = SUPPORTED_VERSIONS;
+    if parsed < oldest || parsed > newest {
+        return Some((
+            SdfSeverity::Warning,
+            format!(
+                "SDF version {} is not supported (supported: {}.{} to {}.{})",
+                version, oldest.0, oldest.1, newest.0, newest.1
+            ),
+        ));
+    }
+    None
+}
+
+/// Validates SDF content, reporting everything that would make it load wrongly or not at all.
+///
+/// Unknown elements, which the loader skips, and unsupported SDF versions are warnings. Values of
+/// the wrong type or arity, negative masses and missing includes are errors. `file` names the file
+/// in the diagnostics.
+pub fn validate_sdf(content: &str, file: &str, model_path: &SdfModelPath) -> Vec<SdfDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+
+    // Open elements as path segments with their start offsets, and their tag names
+    let mut elements: Vec<(String, usize)> = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut skip_depth = 0;
+    let mut text = String::new();
+    let mut pose_rotation = PoseRotation::default();
+    loop {
+        let event = reader.read_event();
+        let position = reader.buffer_position();
+        let mut report = |severity, location, message| {
+            diagnostics.push(SdfDiagnostic {
+                severity,
+                location: Some(location),
+                message,
+            })
+        };
+        match event {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let is_empty = matches!(event, Ok(Event::Empty(_)));
+                if skip_depth > 0 {
+                    skip_depth += usize::from(!is_empty);
+                    continue;
+                }
+                let tag = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                let start = position - e.len() - 2 - usize::from(is_empty);
+                let location = locate(content, Some(file), &elements, Some(&tag), start);
+                if !SDF_ELEMENTS.contains(&tag.as_str()) {
+                    report(
+                        SdfSeverity::Warning,
+                        location,
+                        format!("unknown element <{}> is ignored", tag),
+                    );
+                    skip_depth += usize::from(!is_empty);
+                    continue;
+                }
+                if tag == "sdf" {
+                    if let Some((severity, message)) = check_version(e) {
+                        report(severity, location.clone(), message);
+                    }
+                }
+                if tag == "pose" {
+                    pose_rotation = PoseRotation::from_attributes(e).unwrap_or_else(|message| {
+                        report(SdfSeverity::Error, location.clone(), message);
+                        PoseRotation::default()
+                    });
+                }
+                if !is_empty {
+                    let segment = match e.try_get_attribute("name").ok().flatten() {
+                        Some(name) => format!("{}[{}]", tag, String::from_utf8_lossy(&name.value)),
+                        None => tag.clone(),
+                    };
+                    elements.push((segment, start));
+                    tags.push(tag);
+                    text.clear();
+                }
+            }
+            Ok(Event::Text(e)) if skip_depth == 0 => {
+                text = e
+                    .unescape()
+                    .map(|text| text.into_owned())
+                    .unwrap_or_default();
+            }
+            Ok(Event::End(_)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    continue;
+                }
+                let (Some((segment, start)), Some(tag)) = (elements.pop(), tags.pop()) else {
+                    continue;
+                };
+                let parent = tags.last().map(String::as_str).unwrap_or_default();
+                if let Some(kind) = value_kind(&tag, parent) {
+                    let location = || locate(content, Some(file), &elements, Some(&segment), start);
+                    if let Err(message) = check_value(kind, &text, pose_rotation) {
+                        report(SdfSeverity::Error, location(), message);
+                    } else if tag == "mass" && parse_number(&text).is_ok_and(|mass| mass < 0.0) {
+                        report(
+                            SdfSeverity::Error,
+                            location(),
+                            format!("mass must not be negative, got {}", text.trim()),
+                        );
+                    }
+                }
+                text.clear();
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                let location = locate(content, Some(file), &elements, None, position);
+                report(SdfSeverity::Error, location, format!("invalid XML: {}", e));
+                return diagnostics;
+            }
+            _ => {}
+        }
+    }
+
+    // Loading also resolves includes, which only the loader can check
+    if !diagnostics.iter().any(|d| d.severity == SdfSeverity::Error) {
+        if let Err(e) = parse_sdf_file(content, file, model_path) {
+            diagnostics.push(e.into());
+        }
+    }
+    diagnostics
+}
+
+/// Reads and validates an SDF file, see [`validate_sdf`]
+pub fn validate_sdf_file(path: &Path, model_path: &SdfModelPath) -> Vec<SdfDiagnostic> {
+    let file = path.to_string_lossy();
+    match std::fs::read_to_string(path) {
+        Ok(content) => validate_sdf(&content, &file, model_path),
+        Err(e) => vec![
+            SdfError::Io {
+                file: file.into_owned(),
+                message: format!("Failed to read SDF file: {}", e),
+            }
+            .into(),
+        ],
+    }
+}
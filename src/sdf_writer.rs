@@ -0,0 +1,911 @@
+use bevy::asset::{AssetPath, UntypedAssetId};
+use bevy::ecs::world::EntityRef;
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::{
+    Damping, GenericJoint, ImpulseJoint, JointAxesMask, JointAxis, MassProperties, RigidBody,
+};
+use bevy_rapier3d::geometry::{Collider, ColliderView, Friction, Restitution, Sensor};
+use bevy_rapier3d::plugin::context::RapierRigidBodySet;
+use bevy_rapier3d::rapier::dynamics::RigidBody as RapierBody;
+use bevy_rapier3d::utils::iso_to_transform;
+use quick_xml::escape::escape;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::path::PathBuf;
+
+use crate::RobotChassis;
+use crate::robotic_arm::{ArmLink, PickupBlock};
+use crate::sdf_loader::{
+    SdfAxis, SdfCollision, SdfElement, SdfEntity, SdfGeometry, SdfInertial, SdfJoint,
+    SdfJointLimit, SdfLight, SdfLink, SdfMaterial, SdfModel, SdfPhysics, SdfPose, SdfScene,
+    SdfSurface, SdfVisual, SdfWorld, SpawnedSdfLight, SpawnedSdfModel, bevy_to_sdf_vector, is_gltf,
+    sdf_pose_to_transform, transform_to_sdf_pose,
+};
+use crate::turtlebot4::Wheel;
+
+/// Writes the current scene as an SDF 1.9 world, see [`capture_world`]
+pub fn write_world(world: &World) -> String {
+    write_sdf(&capture_world(world))
+}
+
+/// The current scene as an SDF world.
+///
+/// Models spawned from SDF keep the elements they were spawned from, with their links at their
+/// current poses. Every other set of rigid bodies connected by joints, such as a robot or a pickup
+/// block, becomes a model posed at its root body, with geometry from its colliders and mass from
+/// Rapier. Joints keep their frames, so a joint's position is zero again in the saved pose.
+pub fn capture_world(world: &World) -> SdfWorld {
+    let name = world
+        .iter_entities()
+        .filter_map(|entity| entity.get::<SdfEntity>())
+        .find(|sdf_entity| sdf_entity.element == SdfElement::World)
+        .map(|sdf_entity| sdf_entity.name.clone())
+        .unwrap_or_else(|| "default".to_string());
+    let scene = match (
+        world.get_resource::<AmbientLight>(),
+        world.get_resource::<ClearColor>(),
+    ) {
+        (Some(ambient), Some(background)) => Some(SdfScene {
+            ambient: ambient.color,
+            background: background.0,
+        }),
+        _ => None,
+    };
+    let body_sets: Vec<&RapierRigidBodySet> = world
+        .iter_entities()
+        .filter_map(|entity| entity.get::<RapierRigidBodySet>())
+        .collect();
+    let mut models = sdf_models(world, &body_sets);
+    models.extend(body_models(world, &body_sets));
+    SdfWorld {
+        name,
+        models,
+        lights: sdf_lights(world),
+        physics: world.get_resource::<SdfPhysics>().cloned(),
+        scene,
+        meshes: HashMap::new(),
+        images: HashMap::new(),
+    }
+}
+
+/// Entities in the order they were spawned
+fn sorted_entities(world: &World) -> Vec<EntityRef<'_>> {
+    let mut entities: Vec<EntityRef> = world.iter_entities().collect();
+    entities.sort_by_key(EntityRef::id);
+    entities
+}
+
+fn children<'w>(entity: &EntityRef<'w>) -> &'w [Entity] {
+    entity
+        .get::<Children>()
+        .map(|children| &**children)
+        .unwrap_or_default()
+}
+
+/// The Rapier body simulating an entity
+fn rapier_body<'w>(body_sets: &[&'w RapierRigidBodySet], entity: Entity) -> Option<&'w RapierBody> {
+    body_sets.iter().find_map(|body_set| {
+        let handle = body_set.entity2body().get(&entity)?;
+        body_set.bodies.get(*handle)
+    })
+}
+
+/// Where an entity is now.
+///
+/// bevy_rapier writes the transforms of bodies with rotated parents back wrongly, so bodies are
+/// posed from Rapier instead.
+fn world_pose(body_sets: &[&RapierRigidBodySet], entity: &EntityRef) -> GlobalTransform {
+    match rapier_body(body_sets, entity.id()) {
+        Some(body) => iso_to_transform(body.position()).into(),
+        None => entity.get::<GlobalTransform>().copied().unwrap_or_default(),
+    }
+}
+
+/// Models spawned from SDF, with their links where they are now
+fn sdf_models(world: &World, body_sets: &[&RapierRigidBodySet]) -> Vec<SdfModel> {
+    let mut models = Vec::new();
+    for entity in sorted_entities(world) {
+        let (Some(SpawnedSdfModel(source)), Some(model_global)) = (
+            entity.get::<SpawnedSdfModel>(),
+            entity.get::<GlobalTransform>(),
+        ) else {
+            continue;
+        };
+        let mut model = source.clone();
+        model.pose = transform_to_sdf_pose(&model_global.compute_transform());
+        for &child in children(&entity) {
+            let Ok(child) = world.get_entity(child) else {
+                continue;
+            };
+            let Some(sdf_entity) = child.get::<SdfEntity>() else {
+                continue;
+            };
+            if sdf_entity.element != SdfElement::Link {
+                continue;
+            }
+            if let Some(link) = model
+                .links
+                .iter_mut()
+                .find(|link| link.name == sdf_entity.name)
+            {
+                let global = world_pose(body_sets, &child);
+                link.pose = transform_to_sdf_pose(&global.reparented_to(model_global));
+            }
+        }
+        models.push(model);
+    }
+    models
+}
+
+/// Lights spawned from SDF, in the frame of the world they were spawned in
+fn sdf_lights(world: &World) -> Vec<SdfLight> {
+    sorted_entities(world)
+        .into_iter()
+        .filter_map(|entity| {
+            let SpawnedSdfLight(source) = entity.get::<SpawnedSdfLight>()?;
+            let mut light = source.clone();
+            let parent = entity
+                .get::<ChildOf>()
+                .and_then(|child_of| world.get::<GlobalTransform>(child_of.parent()));
+            if let Some(parent) = parent {
+                let global = parent.mul_transform(sdf_pose_to_transform(&light.pose));
+                light.pose = transform_to_sdf_pose(&global.compute_transform());
+            }
+            Some(light)
+        })
+        .collect()
+}
+
+/// Models of the rigid bodies not spawned from SDF, one per set of bodies connected by joints
+fn body_models(world: &World, body_sets: &[&RapierRigidBodySet]) -> Vec<SdfModel> {
+    let is_sdf = |entity: Entity| world.get::<SdfEntity>(entity).is_some();
+    // SDF joints to the world attach to fixed bodies that are children of their model
+    let bodies: Vec<EntityRef> = sorted_entities(world)
+        .into_iter()
+        .filter(|entity| entity.contains::<RigidBody>() && !is_sdf(entity.id()))
+        .filter(|entity| {
+            !entity
+                .get::<ChildOf>()
+                .is_some_and(|child_of| is_sdf(child_of.parent()))
+        })
+        .collect();
+    let body_entities: HashSet<Entity> = bodies.iter().map(EntityRef::id).collect();
+
+    // Each body belongs to the model of the body its chain of joints starts from
+    let root = |mut entity: Entity| {
+        let mut visited = HashSet::new();
+        while let Some(joint) = world.get::<ImpulseJoint>(entity) {
+            if !body_entities.contains(&joint.parent) || !visited.insert(entity) {
+                break;
+            }
+            entity = joint.parent;
+        }
+        entity
+    };
+    let mut groups: BTreeMap<Entity, Vec<EntityRef>> = BTreeMap::new();
+    for body in bodies {
+        groups.entry(root(body.id())).or_default().push(body);
+    }
+
+    let models: Vec<SdfModel> = groups
+        .into_iter()
+        .filter_map(|(root, bodies)| {
+            let root = world.get_entity(root).ok()?;
+            Some(body_model(world, body_sets, &root, &bodies))
+        })
+        .collect();
+    let names = unique_names(models.iter().map(|model| model.name.clone()).collect());
+    models
+        .into_iter()
+        .zip(names)
+        .map(|(model, name)| SdfModel { name, ..model })
+        .collect()
+}
+
+/// Model of a set of bodies connected by joints, posed at their root body
+fn body_model(
+    world: &World,
+    body_sets: &[&RapierRigidBodySet],
+    root: &EntityRef,
+    bodies: &[EntityRef],
+) -> SdfModel {
+    let root_global = world_pose(body_sets, root);
+    let names = unique_names(bodies.iter().map(link_name).collect());
+    let link_names: HashMap<Entity, &str> = bodies
+        .iter()
+        .map(EntityRef::id)
+        .zip(names.iter().map(String::as_str))
+        .collect();
+    // A lone fixed body is a static model; fixed bodies among moving ones are fixed to the world
+    let is_dynamic = |body: &EntityRef| matches!(body.get::<RigidBody>(), Some(RigidBody::Dynamic));
+    let is_static = bodies.len() == 1 && !is_dynamic(root);
+
+    let mut links = Vec::new();
+    let mut joints = Vec::new();
+    for body in bodies {
+        let name = link_names[&body.id()];
+        let inertial = rapier_body(body_sets, body.id())
+            .filter(|_| !is_static)
+            .map(|rigid_body| {
+                MassProperties::from_rapier(rigid_body.mass_properties().local_mprops)
+            })
+            .filter(|mass_properties| mass_properties.mass > 0.0)
+            .map(|mass_properties| mass_properties_inertial(&mass_properties));
+        // Shapes are placed relative to the body's Bevy transform, which their own follow
+        let bevy_global = body.get::<GlobalTransform>().copied().unwrap_or_default();
+        let (visuals, collisions) = body_shapes(world, body, &bevy_global);
+        let damping = body.get::<Damping>();
+        links.push(SdfLink {
+            name: name.to_string(),
+            pose: transform_to_sdf_pose(&world_pose(body_sets, body).reparented_to(&root_global)),
+            visuals,
+            collisions,
+            inertial,
+            linear_damping: damping.map(|damping| damping.linear_damping),
+            angular_damping: damping.map(|damping| damping.angular_damping),
+        });
+
+        let parent = body.get::<ImpulseJoint>().and_then(|joint| {
+            let parent = link_names.get(&joint.parent)?;
+            Some((*parent, joint.data.as_ref()))
+        });
+        if let Some((parent, joint)) = parent {
+            joints.extend(body_joint(parent, name, joint));
+        } else if !is_static && !is_dynamic(body) {
+            joints.push(SdfJoint {
+                name: format!("{}_world", name),
+                joint_type: "fixed".to_string(),
+                parent: "world".to_string(),
+                child: name.to_string(),
+                pose: SdfPose::default(),
+                axis: None,
+            });
+        }
+    }
+
+    SdfModel {
+        name: model_name(root),
+        static_: is_static,
+        pose: transform_to_sdf_pose(&root_global.compute_transform()),
+        links,
+        joints,
+    }
+}
+
+/// Name of the model of the bodies joined to `root`
+fn model_name(root: &EntityRef) -> String {
+    if root.contains::<RobotChassis>() {
+        "turtlebot4".to_string()
+    } else if root.contains::<ArmLink>() {
+        "ur3e".to_string()
+    } else if root.contains::<PickupBlock>() {
+        "pickup_block".to_string()
+    } else if let Some(name) = root.get::<Name>() {
+        name.to_string()
+    } else {
+        "model".to_string()
+    }
+}
+
+/// Name of the link of a body: its `Name`, or the part of a robot it is
+fn link_name(body: &EntityRef) -> String {
+    if let Some(name) = body.get::<Name>() {
+        return name.to_string();
+    }
+    if let Some(arm_link) = body.get::<ArmLink>() {
+        return format!("{:?}", arm_link).to_lowercase();
+    }
+    match body.get::<Wheel>() {
+        Some(Wheel::Left) => "left_wheel",
+        Some(Wheel::Right) => "right_wheel",
+        None if body.contains::<RobotChassis>() => "chassis",
+        None => "link",
+    }
+    .to_string()
+}
+
+/// Numbers the names that occur more than once, so that every name is unique
+fn unique_names(names: Vec<String>) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for name in &names {
+        *counts.entry(name.clone()).or_default() += 1;
+    }
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    names
+        .into_iter()
+        .map(|name| {
+            if counts[&name] == 1 {
+                return name;
+            }
+            let number = numbers.entry(name.clone()).or_default();
+            *number += 1;
+            format!("{}_{}", name, number)
+        })
+        .collect()
+}
+
+/// SDF inertial of Rapier mass properties in the Y-up frame of a link
+fn mass_properties_inertial(mass_properties: &MassProperties) -> SdfInertial {
+    let conv = Mat3::from_quat(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
+    let frame = Mat3::from_quat(mass_properties.principal_inertia_local_frame);
+    let tensor = conv.transpose()
+        * frame
+        * Mat3::from_diagonal(mass_properties.principal_inertia)
+        * frame.transpose()
+        * conv;
+    // Diagonalising leaves rounding noise in the products of inertia
+    let diagonal = Vec3::new(tensor.x_axis.x, tensor.y_axis.y, tensor.z_axis.z);
+    let product = |v: f32| {
+        if v.abs() < 1e-6 * diagonal.max_element() {
+            0.0
+        } else {
+            v
+        }
+    };
+    SdfInertial {
+        mass: mass_properties.mass,
+        ixx: diagonal.x,
+        iyy: diagonal.y,
+        izz: diagonal.z,
+        ixy: product(tensor.y_axis.x),
+        ixz: product(tensor.z_axis.x),
+        iyz: product(tensor.z_axis.y),
+        pose: SdfPose {
+            xyz: bevy_to_sdf_vector(mass_properties.local_center_of_mass),
+            rpy: Vec3::ZERO,
+        },
+    }
+}
+
+/// SDF joint attaching the link `child` to `parent`, with its frame at the child's anchor
+fn body_joint(parent: &str, child: &str, joint: &GenericJoint) -> Option<SdfJoint> {
+    let locked_axes = joint.locked_axes();
+    let (joint_type, free_axis) = if locked_axes == JointAxesMask::LOCKED_REVOLUTE_AXES {
+        match joint.limits(JointAxis::AngX) {
+            Some(_) => ("revolute", Some(JointAxis::AngX)),
+            None => ("continuous", Some(JointAxis::AngX)),
+        }
+    } else if locked_axes == JointAxesMask::LOCKED_PRISMATIC_AXES {
+        ("prismatic", Some(JointAxis::LinX))
+    } else if locked_axes == JointAxesMask::LOCKED_FIXED_AXES {
+        ("fixed", None)
+    } else if locked_axes == JointAxesMask::LOCKED_SPHERICAL_AXES {
+        ("ball", None)
+    } else {
+        warn!(
+            "Not writing the joint of {}: SDF has no joint with its free axes",
+            child
+        );
+        return None;
+    };
+    // Rapier joints move about or along the X axis of their frame
+    let axis = free_axis.map(|free_axis| SdfAxis {
+        xyz: bevy_to_sdf_vector(joint.local_basis2() * Vec3::X),
+        limit: joint.limits(free_axis).map(|limits| SdfJointLimit {
+            lower: limits.min,
+            upper: limits.max,
+            effort: -1.0,
+            velocity: -1.0,
+        }),
+        dynamics: None,
+    });
+    Some(SdfJoint {
+        name: format!("{}_{}", parent, child),
+        joint_type: joint_type.to_string(),
+        parent: parent.to_string(),
+        child: child.to_string(),
+        pose: SdfPose {
+            xyz: bevy_to_sdf_vector(joint.local_anchor2()),
+            rpy: Vec3::ZERO,
+        },
+        axis,
+    })
+}
+
+/// Visuals and collisions of a body, from it and from its descendants that are not bodies
+fn body_shapes(
+    world: &World,
+    body: &EntityRef,
+    body_global: &GlobalTransform,
+) -> (Vec<SdfVisual>, Vec<SdfCollision>) {
+    let asset_path = |id: UntypedAssetId| world.get_resource::<AssetServer>()?.get_path(id);
+    let mut visuals = Vec::new();
+    let mut collisions = Vec::new();
+    let mut entities = vec![*body];
+    while let Some(entity) = entities.pop() {
+        entities.extend(
+            children(&entity)
+                .iter()
+                .filter_map(|&child| world.get_entity(child).ok())
+                .filter(|child| !child.contains::<RigidBody>()),
+        );
+        let transform = entity
+            .get::<GlobalTransform>()
+            .map(|global| global.reparented_to(body_global))
+            .unwrap_or_default();
+
+        let mut geometries = Vec::new();
+        if let Some(collider) = entity.get::<Collider>() {
+            // Sensors only detect contacts
+            if !entity.contains::<Sensor>() {
+                collider_geometries(collider.as_typed_shape(), transform, &mut geometries);
+            }
+        }
+        let surface = surface(&entity);
+        for (transform, geometry) in &geometries {
+            collisions.push(SdfCollision {
+                name: "collision".to_string(),
+                pose: transform_to_sdf_pose(transform),
+                geometry: geometry.clone(),
+                surface: surface.clone(),
+            });
+        }
+
+        let material = material(world, &entity);
+        let mesh_path = entity
+            .get::<SceneRoot>()
+            .and_then(|scene| asset_path(scene.0.id().untyped()))
+            .or_else(|| {
+                let mesh = entity.get::<Mesh3d>()?;
+                asset_path(mesh.0.id().untyped())
+            });
+        if let Some(path) = mesh_path {
+            let (pose, geometry) = mesh_visual(path, transform);
+            visuals.push(SdfVisual {
+                name: "visual".to_string(),
+                pose,
+                geometry,
+                material: material.clone(),
+            });
+        } else if entity.contains::<Mesh3d>() {
+            // A generated mesh is taken to have the shape of its collider
+            for (transform, geometry) in geometries {
+                visuals.push(SdfVisual {
+                    name: "visual".to_string(),
+                    pose: transform_to_sdf_pose(&transform),
+                    geometry,
+                    material: material.clone(),
+                });
+            }
+        }
+    }
+
+    let visual_names = unique_names(visuals.iter().map(|visual| visual.name.clone()).collect());
+    for (visual, name) in visuals.iter_mut().zip(visual_names) {
+        visual.name = name;
+    }
+    let collision_names = unique_names(
+        collisions
+            .iter()
+            .map(|collision| collision.name.clone())
+            .collect(),
+    );
+    for (collision, name) in collisions.iter_mut().zip(collision_names) {
+        collision.name = name;
+    }
+    (visuals, collisions)
+}
+
+/// SDF geometries of a collider shape, with their transforms within the collider's entity
+fn collider_geometries(
+    shape: ColliderView,
+    transform: Transform,
+    geometries: &mut Vec<(Transform, SdfGeometry)>,
+) {
+    // Rapier shapes are Y-up like their entity, SDF shapes are Z-up
+    let geometry = match shape {
+        ColliderView::Ball(ball) => SdfGeometry::Sphere {
+            radius: ball.radius(),
+        },
+        ColliderView::Cuboid(cuboid) => SdfGeometry::Box {
+            size: 2.0 * bevy_to_sdf_vector(cuboid.half_extents()).abs(),
+        },
+        ColliderView::Cylinder(cylinder) => SdfGeometry::Cylinder {
+            radius: cylinder.radius(),
+            length: 2.0 * cylinder.half_height(),
+        },
+        ColliderView::Capsule(capsule) => {
+            let (a, b) = (capsule.segment().a(), capsule.segment().b());
+            let axis = (b - a).try_normalize().unwrap_or(Vec3::Y);
+            let segment = Transform::from_translation((a + b) / 2.0)
+                .with_rotation(Quat::from_rotation_arc(Vec3::Y, axis));
+            geometries.push((
+                transform * segment,
+                SdfGeometry::Capsule {
+                    radius: capsule.radius(),
+                    length: a.distance(b),
+                },
+            ));
+            return;
+        }
+        ColliderView::Compound(compound) => {
+            for (translation, rotation, shape) in compound.shapes() {
+                let part = Transform::from_translation(translation).with_rotation(rotation);
+                collider_geometries(shape, transform * part, geometries);
+            }
+            return;
+        }
+        _ => {
+            warn!("Not writing a collider: SDF has no geometry for its shape");
+            return;
+        }
+    };
+    geometries.push((transform, geometry));
+}
+
+/// Mesh visual of a mesh or glTF scene asset drawn with the given transform within its body
+fn mesh_visual(path: AssetPath, transform: Transform) -> (SdfPose, SdfGeometry) {
+    let uri = path.without_label().to_string();
+    let (rotation, scale) = if is_gltf(&uri) {
+        (
+            transform.rotation,
+            bevy_to_sdf_vector(transform.scale).abs(),
+        )
+    } else {
+        // The loader turns Z-up mesh formats to Y-up
+        (
+            transform.rotation * Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            transform.scale,
+        )
+    };
+    let pose = transform_to_sdf_pose(
+        &Transform::from_translation(transform.translation).with_rotation(rotation),
+    );
+    let scale = (!scale.abs_diff_eq(Vec3::ONE, 1e-6)).then_some(scale);
+    (pose, SdfGeometry::Mesh { uri, scale })
+}
+
+/// SDF material of an entity's `StandardMaterial`
+fn material(world: &World, entity: &EntityRef) -> Option<SdfMaterial> {
+    let handle = entity.get::<MeshMaterial3d<StandardMaterial>>()?;
+    let material = world
+        .get_resource::<Assets<StandardMaterial>>()?
+        .get(&handle.0)?;
+    // Gazebo lights the ambient color too, the loader only uses the diffuse color
+    Some(SdfMaterial {
+        ambient: Some(material.base_color),
+        diffuse: Some(material.base_color),
+        specular: None,
+        emissive: None,
+    })
+}
+
+/// SDF surface of an entity's Rapier contact materials
+fn surface(entity: &EntityRef) -> Option<SdfSurface> {
+    let friction = entity.get::<Friction>();
+    let restitution = entity.get::<Restitution>();
+    if friction.is_none() && restitution.is_none() {
+        return None;
+    }
+    let mut surface = SdfSurface::default();
+    if let Some(friction) = friction {
+        surface.mu = friction.coefficient;
+        surface.mu2 = friction.coefficient;
+    }
+    if let Some(restitution) = restitution {
+        surface.restitution_coefficient = restitution.coefficient;
+    }
+    Some(surface)
+}
+
+/// Indented XML output
+#[derive(Default)]
+struct XmlWriter {
+    xml: String,
+    open: Vec<&'static str>,
+}
+
+impl XmlWriter {
+    fn indent(&mut self) {
+        self.xml.push_str(&"  ".repeat(self.open.len()));
+    }
+
+    fn open(&mut self, tag: &'static str, attributes: &[(&str, &str)]) {
+        self.indent();
+        self.xml.push('<');
+        self.xml.push_str(tag);
+        for (name, value) in attributes {
+            let _ = write!(self.xml, " {}=\"{}\"", name, escape(value));
+        }
+        self.xml.push_str(">\n");
+        self.open.push(tag);
+    }
+
+    fn close(&mut self) {
+        if let Some(tag) = self.open.pop() {
+            self.indent();
+            let _ = writeln!(self.xml, "</{}>", tag);
+        }
+    }
+
+    /// An element holding a value
+    fn value(&mut self, tag: &str, value: impl Display) {
+        self.indent();
+        let _ = writeln!(self.xml, "<{0}>{1}</{0}>", tag, escape(&value.to_string()));
+    }
+}
+
+/// Numbers separated by spaces, without the rounding noise of converting between frames
+fn numbers(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|&v| if v.abs() < 1e-6 { 0.0 } else { v }.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn pose(pose: &SdfPose) -> String {
+    numbers(&[
+        pose.xyz.x, pose.xyz.y, pose.xyz.z, pose.rpy.x, pose.rpy.y, pose.rpy.z,
+    ])
+}
+
+fn color(color: Color) -> String {
+    numbers(&color.to_srgba().to_f32_array())
+}
+
+/// Writes an SDF world as an SDF 1.9 document
+pub fn write_sdf(world: &SdfWorld) -> String {
+    let mut xml = XmlWriter::default();
+    xml.xml.push_str("<?xml version=\"1.0\"?>\n");
+    xml.open("sdf", &[("version", "1.9")]);
+    xml.open("world", &[("name", &world.name)]);
+    if let Some(physics) = &world.physics {
+        xml.open("physics", &[("name", &physics.name), ("type", "ode")]);
+        xml.value("max_step_size", physics.max_step_size);
+        xml.value("real_time_factor", physics.real_time_factor);
+        xml.value("real_time_update_rate", physics.real_time_update_rate);
+        xml.close();
+        xml.value("gravity", numbers(&physics.gravity.to_array()));
+    }
+    if let Some(scene) = &world.scene {
+        xml.open("scene", &[]);
+        xml.value("ambient", color(scene.ambient));
+        xml.value("background", color(scene.background));
+        xml.close();
+    }
+    for light in &world.lights {
+        write_light(&mut xml, light);
+    }
+    for model in &world.models {
+        write_model(&mut xml, model);
+    }
+    xml.close();
+    xml.close();
+    xml.xml
+}
+
+fn write_light(xml: &mut XmlWriter, light: &SdfLight) {
+    xml.open(
+        "light",
+        &[("name", &light.name), ("type", &light.light_type)],
+    );
+    xml.value("pose", pose(&light.pose));
+    xml.value("diffuse", color(light.diffuse));
+    xml.value("specular", color(light.specular));
+    xml.value("cast_shadows", light.cast_shadows);
+    xml.value("intensity", light.intensity);
+    xml.value("direction", numbers(&light.direction.to_array()));
+    xml.open("attenuation", &[]);
+    xml.value("range", light.attenuation.range);
+    xml.value("constant", light.attenuation.constant);
+    xml.value("linear", light.attenuation.linear);
+    xml.value("quadratic", light.attenuation.quadratic);
+    xml.close();
+    if let Some(spot) = &light.spot {
+        xml.open("spot", &[]);
+        xml.value("inner_angle", spot.inner_angle);
+        xml.value("outer_angle", spot.outer_angle);
+        xml.value("falloff", spot.falloff);
+        xml.close();
+    }
+    xml.close();
+}
+
+fn write_model(xml: &mut XmlWriter, model: &SdfModel) {
+    xml.open("model", &[("name", &model.name)]);
+    xml.value("static", model.static_);
+    xml.value("pose", pose(&model.pose));
+    for link in &model.links {
+        write_link(xml, link);
+    }
+    for joint in &model.joints {
+        write_joint(xml, joint);
+    }
+    xml.close();
+}
+
+fn write_link(xml: &mut XmlWriter, link: &SdfLink) {
+    xml.open("link", &[("name", &link.name)]);
+    xml.value("pose", pose(&link.pose));
+    if let Some(inertial) = &link.inertial {
+        xml.open("inertial", &[]);
+        xml.value("pose", pose(&inertial.pose));
+        xml.value("mass", inertial.mass);
+        xml.open("inertia", &[]);
+        xml.value("ixx", inertial.ixx);
+        xml.value("ixy", inertial.ixy);
+        xml.value("ixz", inertial.ixz);
+        xml.value("iyy", inertial.iyy);
+        xml.value("iyz", inertial.iyz);
+        xml.value("izz", inertial.izz);
+        xml.close();
+        xml.close();
+    }
+    if link.linear_damping.is_some() || link.angular_damping.is_some() {
+        xml.open("velocity_decay", &[]);
+        xml.value("linear", link.linear_damping.unwrap_or(0.0));
+        xml.value("angular", link.angular_damping.unwrap_or(0.0));
+        xml.close();
+    }
+    for collision in &link.collisions {
+        xml.open("collision", &[("name", &collision.name)]);
+        xml.value("pose", pose(&collision.pose));
+        write_geometry(xml, &collision.geometry);
+        if let Some(surface) = &collision.surface {
+            write_surface(xml, surface);
+        }
+        xml.close();
+    }
+    for visual in &link.visuals {
+        xml.open("visual", &[("name", &visual.name)]);
+        xml.value("pose", pose(&visual.pose));
+        write_geometry(xml, &visual.geometry);
+        if let Some(material) = &visual.material {
+            xml.open("material", &[]);
+            let colors = [
+                ("ambient", material.ambient),
+                ("diffuse", material.diffuse),
+                ("specular", material.specular),
+                ("emissive", material.emissive),
+            ];
+            for (tag, value) in colors {
+                if let Some(value) = value {
+                    xml.value(tag, color(value));
+                }
+            }
+            xml.close();
+        }
+        xml.close();
+    }
+    xml.close();
+}
+
+fn write_geometry(xml: &mut XmlWriter, geometry: &SdfGeometry) {
+    xml.open("geometry", &[]);
+    match geometry {
+        SdfGeometry::Box { size } => {
+            xml.open("box", &[]);
+            xml.value("size", numbers(&size.to_array()));
+            xml.close();
+        }
+        SdfGeometry::Sphere { radius } => {
+            xml.open("sphere", &[]);
+            xml.value("radius", radius);
+            xml.close();
+        }
+        SdfGeometry::Cylinder { radius, length } => {
+            xml.open("cylinder", &[]);
+            xml.value("radius", radius);
+            xml.value("length", length);
+            xml.close();
+        }
+        SdfGeometry::Capsule { radius, length } => {
+            xml.open("capsule", &[]);
+            xml.value("radius", radius);
+            xml.value("length", length);
+            xml.close();
+        }
+        SdfGeometry::Ellipsoid { radii } => {
+            xml.open("ellipsoid", &[]);
+            xml.value("radii", numbers(&radii.to_array()));
+            xml.close();
+        }
+        SdfGeometry::Plane { normal, size } => {
+            xml.open("plane", &[]);
+            xml.value("normal", numbers(&normal.to_array()));
+            xml.value("size", numbers(&size.to_array()));
+            xml.close();
+        }
+        SdfGeometry::Mesh { uri, scale } => {
+            xml.open("mesh", &[]);
+            xml.value("uri", uri);
+            if let Some(scale) = scale {
+                xml.value("scale", numbers(&scale.to_array()));
+            }
+            xml.close();
+        }
+        SdfGeometry::Polyline { polylines } => {
+            for polyline in polylines {
+                xml.open("polyline", &[]);
+                for point in &polyline.points {
+                    xml.value("point", numbers(&point.to_array()));
+                }
+                xml.value("height", polyline.height);
+                xml.close();
+            }
+        }
+        SdfGeometry::Heightmap { uri, size, pos } => {
+            xml.open("heightmap", &[]);
+            xml.value("uri", uri);
+            xml.value("size", numbers(&size.to_array()));
+            xml.value("pos", numbers(&pos.to_array()));
+            xml.close();
+        }
+    }
+    xml.close();
+}
+
+fn write_surface(xml: &mut XmlWriter, surface: &SdfSurface) {
+    xml.open("surface", &[]);
+    xml.open("friction", &[]);
+    xml.open("ode", &[]);
+    xml.value("mu", surface.mu);
+    xml.value("mu2", surface.mu2);
+    xml.close();
+    xml.close();
+    xml.open("bounce", &[]);
+    xml.value("restitution_coefficient", surface.restitution_coefficient);
+    xml.close();
+    if surface.kp.is_some() || surface.kd.is_some() {
+        xml.open("contact", &[]);
+        xml.open("ode", &[]);
+        if let Some(kp) = surface.kp {
+            xml.value("kp", kp);
+        }
+        if let Some(kd) = surface.kd {
+            xml.value("kd", kd);
+        }
+        xml.close();
+        xml.close();
+    }
+    xml.close();
+}
+
+fn write_joint(xml: &mut XmlWriter, joint: &SdfJoint) {
+    xml.open(
+        "joint",
+        &[("name", &joint.name), ("type", &joint.joint_type)],
+    );
+    xml.value("parent", &joint.parent);
+    xml.value("child", &joint.child);
+    xml.value("pose", pose(&joint.pose));
+    if let Some(axis) = &joint.axis {
+        xml.open("axis", &[]);
+        xml.value("xyz", numbers(&axis.xyz.to_array()));
+        if let Some(limit) = &axis.limit {
+            xml.open("limit", &[]);
+            xml.value("lower", limit.lower);
+            xml.value("upper", limit.upper);
+            xml.value("effort", limit.effort);
+            xml.value("velocity", limit.velocity);
+            xml.close();
+        }
+        if let Some(dynamics) = &axis.dynamics {
+            xml.open("dynamics", &[]);
+            xml.value("damping", dynamics.damping);
+            xml.value("friction", dynamics.friction);
+            xml.close();
+        }
+        xml.close();
+    }
+    xml.close();
+}
+
+/// File the scene is saved to when F5 is pressed
+#[derive(Resource, Debug, Clone)]
+pub struct SdfSavePath(pub PathBuf);
+
+/// System saving the scene as an SDF world when F5 is pressed
+pub fn save_sdf_world_system(world: &World) {
+    let pressed = world
+        .get_resource::<ButtonInput<KeyCode>>()
+        .is_some_and(|keyboard| keyboard.just_pressed(KeyCode::F5));
+    let Some(SdfSavePath(path)) = world.get_resource::<SdfSavePath>().filter(|_| pressed) else {
+        return;
+    };
+    match std::fs::write(path, write_world(world)) {
+        Ok(()) => info!("Saved the scene to {}", path.display()),
+        Err(e) => error!("Failed to save the scene to {}: {}", path.display(), e),
+    }
+}
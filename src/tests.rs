@@ -3,7 +3,7 @@ use bevy_rapier3d::prelude::*;
 use std::f32::consts::PI;
 
 use crate::{
-    CHASSIS_GROUP, RobotChassis, STATIC_GROUP,
+    CHASSIS_GROUP, PhysicsPlugin, RobotChassis, STATIC_GROUP,
     camera::PanOrbitCamera,
     lidar::{LaserScan, LidarSensor},
 };
@@ -80,6 +80,28 @@ mod lidar_tests {
         assert!(laser_scan.ranges[3].is_infinite());
     }
 
+    #[test]
+    fn test_lidar_parameter_update_keeps_scan_progress() {
+        let mut lidar = LidarSensor::default();
+        lidar
+            .scan_timer
+            .tick(std::time::Duration::from_secs_f32(0.05));
+
+        // Unchanged scan rate must not restart the scan timer
+        lidar.update_parameters();
+        assert_relative_eq!(lidar.scan_timer.elapsed_secs(), 0.05, epsilon = 0.001);
+
+        // A new scan rate does
+        lidar.scan_rate = 5.0;
+        lidar.update_parameters();
+        assert_relative_eq!(lidar.scan_timer.elapsed_secs(), 0.0, epsilon = 0.001);
+        assert_relative_eq!(
+            lidar.scan_timer.duration().as_secs_f32(),
+            0.2,
+            epsilon = 0.001
+        );
+    }
+
     #[test]
     fn test_lidar_range_validation() {
         let lidar = LidarSensor::default();
@@ -360,3 +382,3453 @@ mod sensor_integration_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod lidar_raycast_tests {
+    use super::*;
+    use crate::lidar::{LidarScanEvent, lidar_scanning_system, ray_direction};
+    use crate::{CHASSIS_INTERNAL_GROUP, LIDAR_GROUP};
+    use approx::assert_relative_eq;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    /// Headless app with physics and the LIDAR scanner, stepping 10 ms per update
+    pub(super) fn lidar_test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::render::mesh::MeshPlugin,
+            PhysicsPlugin,
+        ))
+        .add_event::<LidarScanEvent>()
+        .add_systems(Update, lidar_scanning_system)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )))
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(10)));
+        app
+    }
+
+    pub(super) fn spawn_wall(app: &mut App, position: Vec3) {
+        app.world_mut().spawn((
+            Collider::cuboid(0.05, 1.0, 2.0),
+            CollisionGroups::new(STATIC_GROUP, CHASSIS_GROUP | LIDAR_GROUP),
+            Transform::from_translation(position),
+        ));
+    }
+
+    pub(super) fn spawn_lidar(app: &mut App, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                LidarSensor {
+                    enable_logging: false,
+                    visualize: false,
+                    ..default()
+                },
+                Transform::from_translation(position),
+            ))
+            .id()
+    }
+
+    pub(super) fn run_one_scan(app: &mut App) {
+        // 10 Hz scan rate at 10 ms per frame
+        for _ in 0..12 {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn test_ray_direction_matches_ros_convention() {
+        assert_relative_eq!(ray_direction(0.0).x, 1.0, epsilon = 0.001);
+        // 90° counter-clockwise about +Y points along -Z
+        assert_relative_eq!(ray_direction(PI / 2.0).z, -1.0, epsilon = 0.001);
+        assert_relative_eq!(ray_direction(PI).x, -1.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_scan_hits_wall_surface_not_centre() {
+        let mut app = lidar_test_app();
+        // Wall face is 2.0 m away along +X, its centre 2.05 m away
+        spawn_wall(&mut app, Vec3::new(2.05, 0.5, 0.0));
+        let lidar = spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        run_one_scan(&mut app);
+
+        let sensor = app.world().get::<LidarSensor>(lidar).unwrap();
+        let (angle, distance, hit) = sensor.scan_results[0];
+        assert_relative_eq!(angle, 0.0, epsilon = 0.001);
+        assert!(hit, "Forward ray should hit the wall");
+        assert_relative_eq!(distance, 2.0, epsilon = 0.01);
+
+        // Rays pointing away from the wall see nothing
+        let (_, _, back_hit) = sensor.scan_results[sensor.rays_per_scan / 2];
+        assert!(!back_hit);
+
+        // An oblique ray still hits the flat face, further away than the face normal
+        let (angle, distance, hit) = sensor.scan_results[2];
+        assert!(hit, "20° ray should hit the 4 m wide wall");
+        assert_relative_eq!(distance, 2.0 / angle.cos(), epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_scan_ignores_colliders_outside_lidar_filter() {
+        let mut app = lidar_test_app();
+        // Behaves like a wheel: never accepts the LIDAR group
+        app.world_mut().spawn((
+            Collider::ball(0.2),
+            CollisionGroups::new(CHASSIS_INTERNAL_GROUP, STATIC_GROUP),
+            Transform::from_xyz(1.0, 0.5, 0.0),
+        ));
+        let lidar = spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        run_one_scan(&mut app);
+
+        let sensor = app.world().get::<LidarSensor>(lidar).unwrap();
+        assert!(!sensor.scan_results.is_empty());
+        assert!(sensor.scan_results.iter().all(|&(_, _, hit)| !hit));
+    }
+
+    #[test]
+    fn test_scan_discards_returns_below_range_min() {
+        let mut app = lidar_test_app();
+        spawn_wall(&mut app, Vec3::new(0.15, 0.5, 0.0));
+        let lidar = spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        run_one_scan(&mut app);
+
+        let sensor = app.world().get::<LidarSensor>(lidar).unwrap();
+        let (_, _, hit) = sensor.scan_results[0];
+        assert!(!hit, "Returns closer than range_min must be dropped");
+    }
+}
+
+#[cfg(test)]
+mod lidar_publish_tests {
+    use super::lidar_raycast_tests::{lidar_test_app, run_one_scan, spawn_lidar, spawn_wall};
+    use super::*;
+    use crate::lidar::{
+        LatestScan, LidarScanEvent, lidar_parameter_update_system, lidar_scanning_system,
+    };
+    use approx::assert_relative_eq;
+
+    #[derive(Resource, Default)]
+    struct ReceivedScans(Vec<LidarScanEvent>);
+
+    fn collect_scans(mut events: EventReader<LidarScanEvent>, mut received: ResMut<ReceivedScans>) {
+        received.0.extend(events.read().cloned());
+    }
+
+    #[test]
+    fn test_completed_scan_is_published_as_event() {
+        let mut app = lidar_test_app();
+        app.init_resource::<ReceivedScans>()
+            .add_systems(PostUpdate, collect_scans);
+        spawn_wall(&mut app, Vec3::new(2.05, 0.5, 0.0));
+        let lidar = spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        run_one_scan(&mut app);
+
+        let received = &app.world().resource::<ReceivedScans>().0;
+        assert_eq!(received.len(), 1, "Exactly one scan should complete");
+        let event = &received[0];
+        assert_eq!(event.sensor, lidar);
+        assert_eq!(event.frame_id, "lidar_link");
+        assert!(event.stamp > 0.0);
+        assert_eq!(event.scan.ranges.len(), 36);
+        assert_eq!(event.scan.intensities.len(), 36);
+        assert_relative_eq!(event.scan.ranges[0], 2.0, epsilon = 0.01);
+        assert!(event.scan.ranges[18].is_infinite());
+    }
+
+    #[test]
+    fn test_scans_complete_while_parameters_are_watched() {
+        let mut app = lidar_test_app();
+        // As in LidarPlugin, which also updates the parameters of changed sensors
+        app.init_resource::<ReceivedScans>()
+            .add_systems(
+                Update,
+                lidar_parameter_update_system.before(lidar_scanning_system),
+            )
+            .add_systems(PostUpdate, collect_scans);
+        spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        // Two and a half scan periods at 10 Hz
+        for _ in 0..25 {
+            app.update();
+        }
+
+        let received = &app.world().resource::<ReceivedScans>().0;
+        assert_eq!(received.len(), 2, "{} scans completed", received.len());
+    }
+
+    #[test]
+    fn test_latest_scan_is_stored_on_sensor() {
+        let mut app = lidar_test_app();
+        spawn_wall(&mut app, Vec3::new(2.05, 0.5, 0.0));
+        let lidar = spawn_lidar(&mut app, Vec3::new(0.0, 0.5, 0.0));
+
+        assert!(app.world().get::<LatestScan>(lidar).is_none());
+        run_one_scan(&mut app);
+
+        let latest = app.world().get::<LatestScan>(lidar).unwrap();
+        assert_eq!(latest.frame_id, "lidar_link");
+        assert_relative_eq!(latest.scan.ranges[0], 2.0, epsilon = 0.01);
+        // Head-on return is at full intensity
+        assert_relative_eq!(latest.scan.intensities[0], 1.0, epsilon = 0.01);
+    }
+}
+
+#[cfg(test)]
+mod diff_drive_tests {
+    use super::*;
+    use crate::diff_drive::{CmdVel, DiffDriveController, DiffDrivePlugin, Twist};
+    use approx::assert_relative_eq;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    pub(super) fn drive_test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::render::mesh::MeshPlugin,
+            PhysicsPlugin,
+            DiffDrivePlugin,
+        ))
+        .add_systems(
+            Startup,
+            |mut commands: Commands, asset_server: Res<AssetServer>| {
+                commands.spawn((
+                    Collider::cuboid(10.0, 0.1, 10.0),
+                    CollisionGroups::new(STATIC_GROUP, Group::ALL),
+                    Transform::from_xyz(0.0, -0.1, 0.0),
+                ));
+                crate::turtlebot4::spawn(&mut commands, &asset_server, &Transform::IDENTITY);
+            },
+        )
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )))
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(10)));
+        app
+    }
+
+    pub(super) fn run(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    pub(super) fn chassis_transform(app: &mut App) -> Transform {
+        *app.world_mut()
+            .query_filtered::<&Transform, With<RobotChassis>>()
+            .single(app.world())
+            .unwrap()
+    }
+
+    pub(super) fn robot(app: &mut App) -> Entity {
+        app.world_mut()
+            .query_filtered::<Entity, With<RobotChassis>>()
+            .single(app.world())
+            .unwrap()
+    }
+
+    fn controller(app: &mut App) -> DiffDriveController {
+        app.world_mut()
+            .query::<&DiffDriveController>()
+            .single(app.world())
+            .unwrap()
+            .clone()
+    }
+
+    /// Publish the same command every frame, like a teleop node would
+    pub(super) fn drive(app: &mut App, linear: f32, angular: f32, frames: usize) {
+        let robot = robot(app);
+        let twist = Twist {
+            linear: Vec3::new(linear, 0.0, 0.0),
+            angular: Vec3::new(0.0, 0.0, angular),
+        };
+        for _ in 0..frames {
+            app.world_mut().send_event(CmdVel { robot, twist });
+            app.update();
+        }
+    }
+
+    #[test]
+    fn test_wheel_velocities_from_twist() {
+        let mut drive = DiffDriveController::new(0.05, 0.2);
+        drive.current_linear_velocity = 0.1;
+        drive.current_angular_velocity = 1.0;
+
+        let (left, right) = drive.wheel_velocities();
+        // (0.1 -/+ 1.0 * 0.1) / 0.05
+        assert_relative_eq!(left, 0.0);
+        assert_relative_eq!(right, 4.0);
+    }
+
+    #[test]
+    fn test_command_is_acceleration_and_velocity_limited() {
+        let mut drive = DiffDriveController::new(0.05, 0.2);
+        drive.max_linear_velocity = 0.5;
+        drive.max_linear_acceleration = 1.0;
+        drive.linear_velocity = 2.0;
+
+        drive.update(0.1);
+        assert_relative_eq!(drive.current_linear_velocity, 0.1);
+
+        for _ in 0..10 {
+            drive.update(0.1);
+        }
+        assert_relative_eq!(drive.current_linear_velocity, 0.5);
+    }
+
+    #[test]
+    fn test_forward_command_drives_straight_without_slip() {
+        let mut app = drive_test_app();
+        run(&mut app, 50);
+        let start = chassis_transform(&mut app);
+
+        drive(&mut app, 0.3, 0.0, 300);
+        let end = chassis_transform(&mut app);
+
+        let travelled = end.translation - start.translation;
+        assert!(travelled.x > 0.6, "robot only moved {travelled:?}");
+        assert!(
+            travelled.z.abs() < 0.05,
+            "robot drifted sideways {travelled:?}"
+        );
+        let (yaw, _, _) = end.rotation.to_euler(EulerRot::YXZ);
+        assert!(yaw.abs() < 0.05, "robot turned by {yaw} rad");
+    }
+
+    #[test]
+    fn test_angular_command_turns_in_place() {
+        let mut app = drive_test_app();
+        run(&mut app, 50);
+        let start = chassis_transform(&mut app);
+
+        drive(&mut app, 0.0, 1.0, 100);
+        let end = chassis_transform(&mut app);
+
+        let (yaw, _, _) = end.rotation.to_euler(EulerRot::YXZ);
+        assert!(yaw > 0.5, "robot only turned {yaw} rad counter-clockwise");
+        let drift = (end.translation - start.translation).xz().length();
+        assert!(drift < 0.05, "robot drifted {drift} m while turning");
+    }
+
+    #[test]
+    fn test_cmd_vel_watchdog_stops_robot() {
+        let mut app = drive_test_app();
+        run(&mut app, 10);
+        drive(&mut app, 0.2, 0.5, 1);
+
+        // Still within the 0.5 s timeout
+        run(&mut app, 30);
+        let drive_state = controller(&mut app);
+        assert_relative_eq!(drive_state.linear_velocity, 0.2);
+        assert_relative_eq!(drive_state.angular_velocity, 0.5);
+
+        run(&mut app, 30);
+        let drive_state = controller(&mut app);
+        assert_eq!(drive_state.linear_velocity, 0.0);
+        assert_eq!(drive_state.angular_velocity, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod odometry_tests {
+    use super::diff_drive_tests::{chassis_transform, drive, drive_test_app, run};
+    use super::*;
+    use crate::odometry::{Odometry, OdometryNoise, OdometryPlugin};
+    use approx::assert_relative_eq;
+    use std::time::Duration;
+
+    fn odometry_test_app(noise: OdometryNoise) -> App {
+        let mut app = drive_test_app();
+        app.add_plugins(OdometryPlugin);
+        app.update();
+        odometry(&mut app).noise = noise;
+        app
+    }
+
+    fn odometry(app: &mut App) -> Mut<'_, Odometry> {
+        app.world_mut()
+            .query::<&mut Odometry>()
+            .single_mut(app.world_mut())
+            .unwrap()
+    }
+
+    fn ground_truth_yaw(app: &mut App) -> f32 {
+        chassis_transform(app).rotation.to_euler(EulerRot::YXZ).0
+    }
+
+    #[test]
+    fn test_odometry_defaults() {
+        let odom = Odometry::new(0.036, 0.237);
+        assert_eq!(odom.frame_id, "odom");
+        assert_eq!(odom.child_frame_id, "base_link");
+        assert_eq!(odom.position, Vec3::ZERO);
+        assert!(odom.noise.ticks_per_revolution > 0.0);
+        assert!(odom.noise.slip_variance > 0.0);
+    }
+
+    #[test]
+    fn test_ideal_odometry_follows_ground_truth() {
+        let mut app = odometry_test_app(OdometryNoise::NONE);
+        run(&mut app, 50);
+        drive(&mut app, 0.3, 0.5, 300);
+
+        let truth = chassis_transform(&mut app);
+        let truth_yaw = ground_truth_yaw(&mut app);
+        let odom = odometry(&mut app);
+        // Bevy (x forward, -z left) to ROS odom (x forward, y left)
+        let truth_position = Vec3::new(truth.translation.x, -truth.translation.z, 0.0);
+        // Only contact slop in the physics separates the two, a few percent of the 0.9 m path
+        assert!(
+            odom.position.distance(truth_position) < 0.05,
+            "odometry {:?} vs ground truth {truth_position:?}",
+            odom.position
+        );
+        assert_relative_eq!(odom.yaw(), truth_yaw, epsilon = 0.1);
+        assert_relative_eq!(odom.twist.linear.x, 0.3, epsilon = 0.03);
+    }
+
+    #[test]
+    fn test_odometry_follows_physics_steps_not_frames() {
+        // 2.5 fixed steps of 4 ms per 10 ms update, so updates take two or three steps
+        let mut app = drive_test_app();
+        app.insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(4)))
+            .add_plugins(OdometryPlugin);
+        app.update();
+        odometry(&mut app).noise = OdometryNoise::NONE;
+        run(&mut app, 50);
+        // An odd number of updates leaves half a step pending, so the clocks differ by 2 ms
+        drive(&mut app, 0.3, 0.0, 201);
+
+        let truth = chassis_transform(&mut app);
+        let odom = odometry(&mut app);
+        let truth_position = Vec3::new(truth.translation.x, -truth.translation.z, 0.0);
+        assert!(
+            odom.position.distance(truth_position) < 0.02,
+            "odometry {:?} vs ground truth {truth_position:?}",
+            odom.position
+        );
+        assert_relative_eq!(odom.twist.linear.x, 0.3, epsilon = 0.03);
+        // Stamped with the time of the physics step, not of the frame
+        let elapsed = app.world().resource::<Time<Fixed>>().elapsed_secs();
+        assert_eq!(odometry(&mut app).stamp, elapsed);
+        assert_ne!(elapsed, app.world().resource::<Time>().elapsed_secs());
+    }
+
+    #[test]
+    fn test_track_width_error_biases_heading() {
+        let mut app = odometry_test_app(OdometryNoise {
+            track_width_error: 0.25,
+            ..OdometryNoise::NONE
+        });
+        run(&mut app, 50);
+        drive(&mut app, 0.0, 1.0, 100);
+
+        let truth_yaw = ground_truth_yaw(&mut app);
+        let odom_yaw = odometry(&mut app).yaw();
+        assert!(truth_yaw > 0.5);
+        // A track width assumed 25% too wide under-reports rotation by about the same factor
+        assert_relative_eq!(odom_yaw, truth_yaw / 1.25, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_covariance_grows_with_distance() {
+        let mut app = odometry_test_app(OdometryNoise::default());
+        run(&mut app, 50);
+        drive(&mut app, 0.3, 0.0, 50);
+        let early = odometry(&mut app).pose_covariance;
+        drive(&mut app, 0.3, 0.0, 100);
+        let late = odometry(&mut app).pose_covariance;
+
+        assert!(early[0] > 0.0);
+        assert!(late[0] > early[0], "x variance did not grow");
+        assert!(late[35] > early[35], "yaw variance did not grow");
+        // Symmetric between (y, yaw) and (yaw, y)
+        assert_relative_eq!(late[11], late[31], epsilon = 1e-9);
+    }
+}
+
+#[cfg(test)]
+mod imu_tests {
+    use super::diff_drive_tests::{drive, drive_test_app, run};
+    use super::*;
+    use crate::imu::{ImuEvent, ImuNoise, ImuPlugin, ImuSensor, LatestImu};
+    use approx::assert_relative_eq;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[derive(Resource, Default)]
+    struct SampleCount(usize);
+
+    fn imu_test_app(noise: ImuNoise) -> App {
+        let mut app = drive_test_app();
+        // The SDF world's default physics step
+        app.add_plugins(ImuPlugin)
+            .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(1)))
+            .init_resource::<SampleCount>()
+            .add_systems(
+                PostUpdate,
+                |mut events: EventReader<ImuEvent>, mut count: ResMut<SampleCount>| {
+                    count.0 += events.read().count();
+                },
+            );
+        app.update();
+        imu(&mut app).noise = noise;
+        app
+    }
+
+    fn imu(app: &mut App) -> Mut<'_, ImuSensor> {
+        app.world_mut()
+            .query::<&mut ImuSensor>()
+            .single_mut(app.world_mut())
+            .unwrap()
+    }
+
+    fn latest(app: &mut App) -> LatestImu {
+        app.world_mut()
+            .query::<&LatestImu>()
+            .single(app.world())
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_imu_sensor_defaults() {
+        let imu = ImuSensor::default();
+        assert_eq!(imu.sample_rate, 100.0);
+        assert_eq!(imu.frame_id, "imu_link");
+        assert!(imu.accel_limit > 9.81);
+        assert_eq!(imu.accel_bias, Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_resting_imu_measures_gravity() {
+        let mut app = imu_test_app(ImuNoise::NONE);
+        run(&mut app, 100);
+
+        // Average out the contact jitter of the settled robot
+        let mut acceleration = Vec3::ZERO;
+        for _ in 0..50 {
+            run(&mut app, 1);
+            acceleration += latest(&mut app).imu.linear_acceleration / 50.0;
+        }
+        let sample = latest(&mut app);
+        assert_eq!(sample.frame_id, "imu_link");
+        // ROS axes: z up
+        assert_relative_eq!(acceleration.z, 9.81, epsilon = 0.1);
+        assert_relative_eq!(acceleration.x, 0.0, epsilon = 0.1);
+        assert_relative_eq!(acceleration.y, 0.0, epsilon = 0.1);
+        assert!(sample.imu.angular_velocity.length() < 0.01);
+        assert!(sample.imu.orientation.angle_between(Quat::IDENTITY) < 0.01);
+    }
+
+    #[test]
+    fn test_turning_robot_measures_yaw_rate() {
+        let mut app = imu_test_app(ImuNoise::NONE);
+        run(&mut app, 50);
+        drive(&mut app, 0.0, 1.0, 100);
+
+        let yaw_rate = app
+            .world_mut()
+            .query_filtered::<&Velocity, With<RobotChassis>>()
+            .single(app.world())
+            .unwrap()
+            .angvel
+            .y;
+        let sample = latest(&mut app);
+        assert!(yaw_rate > 0.5);
+        // Counter-clockwise yaw is +z in ROS axes
+        assert_relative_eq!(sample.imu.angular_velocity.z, yaw_rate, epsilon = 0.05);
+        let (yaw, _, _) = sample.imu.orientation.to_euler(EulerRot::ZYX);
+        assert!(yaw > 0.5, "orientation yaw {yaw}");
+    }
+
+    #[test]
+    fn test_accelerometer_saturates() {
+        let mut app = imu_test_app(ImuNoise::NONE);
+        imu(&mut app).accel_limit = 5.0;
+        run(&mut app, 50);
+
+        assert_relative_eq!(latest(&mut app).imu.linear_acceleration.z, 5.0);
+    }
+
+    #[test]
+    fn test_gyro_bias_random_walk_and_events() {
+        let mut app = imu_test_app(ImuNoise {
+            gyro_bias_random_walk: Vec3::splat(0.01),
+            ..ImuNoise::NONE
+        });
+        app.world_mut().resource_mut::<SampleCount>().0 = 0;
+        run(&mut app, 100);
+
+        assert_ne!(imu(&mut app).gyro_bias, Vec3::ZERO);
+        // 100 Hz at 10 ms per frame
+        let samples = app.world().resource::<SampleCount>().0;
+        assert!((99..=101).contains(&samples), "{samples} samples");
+    }
+
+    #[test]
+    fn test_sample_rate_does_not_depend_on_frame_rate() {
+        let mut app = imu_test_app(ImuNoise::NONE);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            25,
+        )));
+        run(&mut app, 1);
+        app.world_mut().resource_mut::<SampleCount>().0 = 0;
+        run(&mut app, 40);
+
+        // Several samples per 25 ms frame, each from its own physics step
+        let samples = app.world().resource::<SampleCount>().0;
+        assert!((99..=101).contains(&samples), "{samples} samples in 1 s");
+        let sample = latest(&mut app);
+        assert_relative_eq!(sample.imu.linear_acceleration.z, 9.81, epsilon = 0.1);
+        // Stamped with the physics step it was taken at, the last one within a sample period
+        let since_sample = app.world().resource::<Time<Fixed>>().elapsed_secs() - sample.stamp;
+        assert!((0.0..0.01).contains(&since_sample), "{since_sample} s");
+    }
+}
+
+#[cfg(test)]
+mod sdf_tests {
+    use super::*;
+    use crate::collada::load_collada;
+    use crate::robotic_arm::{ArmLink, PickupBlock};
+    use crate::sdf_loader::{
+        LoadSdfWorldRequest, SdfElement, SdfEntity, SdfError, SdfGeometry, SdfLocation, SdfPlugin,
+        SdfPose, SdfWorld, SdfWorldHandle, SdfWorldLoadFailed, SdfWorldLoaded, parse_sdf_content,
+        parse_sdf_file, parse_sdf_with_model_path, spawn_sdf_world,
+    };
+    use crate::sdf_model_path::{SdfModelPath, SdfModelPathPlugin};
+    use crate::sdf_validation::{SdfSeverity, validate_sdf};
+    use crate::sdf_writer::write_world;
+    use approx::assert_relative_eq;
+    use bevy::time::TimeUpdateStrategy;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    const TABLE_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="table_world">
+    <model name="ground_plane">
+      <static>true</static>
+      <link name="ground_link">
+        <collision name="ground_collision">
+          <geometry><plane><normal>0 0 1</normal><size>10 10</size></plane></geometry>
+        </collision>
+      </link>
+    </model>
+    <model name="table">
+      <pose>1 2 0 0 0 0</pose>
+      <link name="table_link">
+        <inertial><mass>5.0</mass></inertial>
+        <collision name="top">
+          <pose>0 0 0.725 0 0 0</pose>
+          <geometry><box><size>1.0 0.8 0.05</size></box></geometry>
+        </collision>
+        <collision name="leg_front_left">
+          <pose>0.45 0.35 0.35 0 0 0</pose>
+          <geometry><box><size>0.05 0.05 0.7</size></box></geometry>
+        </collision>
+        <collision name="leg_front_right">
+          <pose>0.45 -0.35 0.35 0 0 0</pose>
+          <geometry><box><size>0.05 0.05 0.7</size></box></geometry>
+        </collision>
+        <collision name="leg_back_left">
+          <pose>-0.45 0.35 0.35 0 0 0</pose>
+          <geometry><box><size>0.05 0.05 0.7</size></box></geometry>
+        </collision>
+        <collision name="leg_back_right">
+          <pose>-0.45 -0.35 0.35 0 0 0</pose>
+          <geometry><box><size>0.05 0.05 0.7</size></box></geometry>
+        </collision>
+        <visual name="top_visual">
+          <pose>0 0 0.725 0 0 0</pose>
+          <geometry><box><size>1.0 0.8 0.05</size></box></geometry>
+        </visual>
+        <visual name="legs_visual">
+          <pose>0 0 0.35 0 0 0</pose>
+          <geometry><box><size>0.95 0.75 0.7</size></box></geometry>
+        </visual>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    fn sdf_test_app(world: SdfWorld) -> App {
+        sdf_test_app_with_model_path(world, SdfModelPath::default())
+    }
+
+    fn sdf_test_app_with_model_path(world: SdfWorld, model_path: SdfModelPath) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            SdfModelPathPlugin(model_path),
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::render::mesh::MeshPlugin,
+            PhysicsPlugin,
+            SdfPlugin,
+        ))
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .add_systems(
+            Startup,
+            move |mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  mut materials: ResMut<Assets<StandardMaterial>>,
+                  asset_server: Res<AssetServer>| {
+                spawn_sdf_world(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &asset_server,
+                    &world,
+                );
+            },
+        )
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )));
+        app
+    }
+
+    fn link(app: &mut App, name: &str) -> Entity {
+        app.world_mut()
+            .query::<(Entity, &Name)>()
+            .iter(app.world())
+            .find(|(_, entity_name)| entity_name.as_str() == name)
+            .map(|(entity, _)| entity)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_multiple_collisions_and_visuals() {
+        let world = parse_sdf_content(TABLE_WORLD).unwrap();
+        let table = &world.models[1];
+        let link = &table.links[0];
+
+        assert_eq!(link.collisions.len(), 5);
+        assert_eq!(link.visuals.len(), 2);
+        assert_eq!(link.collisions[2].name, "leg_front_right");
+        assert_eq!(link.collisions[2].pose.xyz, Vec3::new(0.45, -0.35, 0.35));
+        assert!(matches!(link.collisions[2].geometry, SdfGeometry::Box { size } if size.z == 0.7));
+        assert_eq!(link.visuals[1].pose.xyz, Vec3::new(0.0, 0.0, 0.35));
+        // Element poses must not leak into the enclosing link or model
+        assert_eq!(link.pose.xyz, Vec3::ZERO);
+        assert_eq!(table.pose.xyz, Vec3::new(1.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_spawn_sdf_hierarchy() {
+        let mut app = sdf_test_app(parse_sdf_content(TABLE_WORLD).unwrap());
+        app.update();
+
+        // world → model → link → visual/collision
+        let table = link(&mut app, "table_table_link");
+        let world = app.world();
+        let model = world.get::<ChildOf>(table).unwrap().parent();
+        let sdf_model = world.get::<SdfEntity>(model).unwrap();
+        assert_eq!(sdf_model.element, SdfElement::Model);
+        assert_eq!(sdf_model.name, "table");
+        let root = world.get::<ChildOf>(model).unwrap().parent();
+        let sdf_world = world.get::<SdfEntity>(root).unwrap();
+        assert_eq!(sdf_world.element, SdfElement::World);
+        assert_eq!(sdf_world.name, "table_world");
+
+        // SDF (x, y, z) is Bevy (x, z, -y)
+        let translation = world.get::<GlobalTransform>(table).unwrap().translation();
+        assert!(
+            translation.abs_diff_eq(Vec3::new(1.0, 0.0, -2.0), 1e-5),
+            "{translation}"
+        );
+
+        let children = world.get::<Children>(table).unwrap();
+        let elements = |element| {
+            children
+                .iter()
+                .filter(|&child| world.get::<SdfEntity>(child).unwrap().element == element)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(elements(SdfElement::Visual).len(), 2);
+        let collisions = elements(SdfElement::Collision);
+        assert_eq!(collisions.len(), 5);
+
+        let leg = collisions[2];
+        let sdf_leg = world.get::<SdfEntity>(leg).unwrap();
+        assert_eq!(sdf_leg.name, "leg_front_right");
+        assert_eq!(sdf_leg.model_name.as_deref(), Some("table"));
+        assert_eq!(sdf_leg.link_name.as_deref(), Some("table_link"));
+        let leg_position = world.get::<Transform>(leg).unwrap().translation;
+        assert!(
+            leg_position.abs_diff_eq(Vec3::new(0.45, 0.35, 0.35), 1e-5),
+            "{leg_position}"
+        );
+        let ColliderView::Cuboid(leg_shape) = world
+            .get::<Collider>(leg)
+            .unwrap()
+            .as_unscaled_typed_shape()
+        else {
+            panic!("leg is not a cuboid");
+        };
+        let half_extents = leg_shape.half_extents();
+        assert!(
+            half_extents.abs_diff_eq(Vec3::new(0.025, 0.35, 0.025), 1e-5),
+            "{half_extents}"
+        );
+    }
+
+    const EMPTY_WORLD: &str = r#"<sdf version="1.6"><world name="empty"></world></sdf>"#;
+
+    #[test]
+    fn test_load_sdf_world_request() {
+        let mut app = sdf_test_app(parse_sdf_content(EMPTY_WORLD).unwrap());
+        app.world_mut().send_event(LoadSdfWorldRequest {
+            spawn_position: Vec3::new(0.0, 0.0, 5.0),
+            ..LoadSdfWorldRequest::new("worlds/simple_world.sdf")
+        });
+        app.world_mut()
+            .send_event(LoadSdfWorldRequest::new("worlds/missing.sdf"));
+
+        // Worlds are loaded by the asset server
+        let mut loaded = Vec::new();
+        let mut failed = Vec::new();
+        for _ in 0..500 {
+            app.update();
+            let world = app.world_mut();
+            loaded.extend(world.resource_mut::<Events<SdfWorldLoaded>>().drain());
+            failed.extend(world.resource_mut::<Events<SdfWorldLoadFailed>>().drain());
+            if !loaded.is_empty() && !failed.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].sdf_path, "worlds/simple_world.sdf");
+        let root = app.world().get::<Transform>(loaded[0].entity).unwrap();
+        assert_eq!(root.translation, Vec3::new(0.0, 0.0, 5.0));
+        let sdf_world = app.world().get::<SdfEntity>(loaded[0].entity).unwrap();
+        assert_eq!(sdf_world.name, loaded[0].world_name);
+        assert!(app.world().get::<Children>(loaded[0].entity).is_some());
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].sdf_path, "worlds/missing.sdf");
+    }
+
+    #[test]
+    fn test_robot_waits_for_sdf_world() {
+        let mut app = sdf_test_app(parse_sdf_content(EMPTY_WORLD).unwrap());
+        app.add_systems(Update, crate::spawn_robot_system);
+        app.world_mut()
+            .send_event(LoadSdfWorldRequest::new("worlds/simple_world.sdf"));
+
+        // No robot to fall through the floor while the world is loading
+        let mut loaded = false;
+        for _ in 0..500 {
+            app.update();
+            loaded = !app.world().resource::<Events<SdfWorldLoaded>>().is_empty();
+            if loaded {
+                break;
+            }
+            let mut robots = app.world_mut().query_filtered::<(), With<RobotChassis>>();
+            assert_eq!(robots.iter(app.world()).count(), 0);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(loaded);
+
+        for _ in 0..200 {
+            app.update();
+        }
+        let chassis = app
+            .world_mut()
+            .query_filtered::<&Transform, With<RobotChassis>>()
+            .single(app.world())
+            .unwrap()
+            .translation;
+        assert!(chassis.y > 0.0, "{chassis}");
+    }
+
+    #[test]
+    fn test_sdf_world_asset_loads_meshes() {
+        let mut app = sdf_test_app(parse_sdf_content(EMPTY_WORLD).unwrap());
+        let handle: Handle<SdfWorld> = app
+            .world()
+            .resource::<AssetServer>()
+            .load("worlds/wedge_world.sdf");
+
+        for _ in 0..500 {
+            app.update();
+            if app.world().resource::<Assets<SdfWorld>>().contains(&handle) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // The mesh is a labeled sub-asset, loaded along with the world
+        let world = app.world();
+        let sdf_world = world.resource::<Assets<SdfWorld>>().get(&handle).unwrap();
+        assert_eq!(sdf_world.name, "wedge_world");
+        let mesh = &sdf_world.meshes["model://wedge/meshes/wedge.dae"];
+        assert_eq!(
+            mesh.path().unwrap().label(),
+            Some("model/wedge/meshes/wedge.dae")
+        );
+        assert!(world.resource::<Assets<Mesh>>().contains(mesh));
+    }
+
+    #[test]
+    fn test_sdf_world_reload_respawns_changed_models() {
+        let mut app = sdf_test_app(parse_sdf_content(EMPTY_WORLD).unwrap());
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<SdfWorld>>()
+            .add(parse_sdf_content(TABLE_WORLD).unwrap());
+        app.world_mut().spawn((
+            SdfWorldHandle(handle.clone()),
+            Transform::default(),
+            Visibility::default(),
+        ));
+        app.update();
+
+        let ground = link(&mut app, "ground_plane");
+        let table = link(&mut app, "table");
+        assert!(app.world().get::<SdfEntity>(table).is_some());
+
+        // Move the table, as if the file had been edited
+        app.world_mut()
+            .resource_mut::<Assets<SdfWorld>>()
+            .get_mut(&handle)
+            .unwrap()
+            .models[1]
+            .pose
+            .xyz = Vec3::new(-1.0, 0.0, 0.0);
+        // Asset events are sent at the end of the frame
+        app.update();
+        app.update();
+
+        let world = app.world();
+        assert!(world.get_entity(ground).is_ok());
+        assert!(world.get_entity(table).is_err());
+        let table = link(&mut app, "table_table_link");
+        let translation = app
+            .world()
+            .get::<GlobalTransform>(table)
+            .unwrap()
+            .translation();
+        assert!(
+            translation.abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), 0.01),
+            "{translation}"
+        );
+    }
+
+    #[test]
+    fn test_table_stands_on_its_legs() {
+        let mut app = sdf_test_app(parse_sdf_content(TABLE_WORLD).unwrap());
+        for _ in 0..100 {
+            app.update();
+        }
+
+        // The legs rest on top of the plane collider, which is 1 cm above the origin
+        let table = link(&mut app, "table_table_link");
+        let transform = app.world().get::<Transform>(table).unwrap();
+        assert_relative_eq!(transform.translation.y, 0.01, epsilon = 0.005);
+        assert_relative_eq!(
+            transform.rotation.angle_between(Quat::IDENTITY),
+            0.0,
+            epsilon = 0.01
+        );
+    }
+
+    const LIGHT_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.7">
+  <world name="light_world">
+    <light type="directional" name="sun">
+      <cast_shadows>true</cast_shadows>
+      <pose>0 0 10 0 0 0</pose>
+      <diffuse>0.8 0.8 0.8 1</diffuse>
+      <specular>0.2 0.2 0.2 1</specular>
+      <direction>-0.5 0.1 -0.9</direction>
+    </light>
+    <light type="spot" name="aisle_lamp">
+      <pose>2 -1 3 0 0 0</pose>
+      <diffuse>1 0.9 0.8 1</diffuse>
+      <intensity>2.0</intensity>
+      <attenuation>
+        <range>15</range>
+        <constant>0.5</constant>
+        <linear>0.01</linear>
+        <quadratic>0.001</quadratic>
+      </attenuation>
+      <direction>0 0 -1</direction>
+      <spot>
+        <inner_angle>0.6</inner_angle>
+        <outer_angle>1.0</outer_angle>
+        <falloff>1.0</falloff>
+      </spot>
+    </light>
+    <model name="lamp_post">
+      <static>true</static>
+      <link name="post_link">
+        <light type="point" name="bulb">
+          <pose>0 0 2 0 0 0</pose>
+        </light>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_parse_lights() {
+        let world = parse_sdf_content(LIGHT_WORLD).unwrap();
+        assert_eq!(world.lights.len(), 2);
+        // The link light's pose must not leak into the link
+        assert_eq!(world.models[0].links[0].pose.xyz, Vec3::ZERO);
+
+        let sun = &world.lights[0];
+        assert_eq!(sun.light_type, "directional");
+        assert!(sun.cast_shadows);
+        assert_eq!(sun.direction, Vec3::new(-0.5, 0.1, -0.9));
+        assert_eq!(sun.pose.xyz, Vec3::new(0.0, 0.0, 10.0));
+
+        let lamp = &world.lights[1];
+        assert!(!lamp.cast_shadows);
+        assert_eq!(lamp.intensity, 2.0);
+        assert_eq!(lamp.attenuation.range, 15.0);
+        assert_eq!(lamp.attenuation.constant, 0.5);
+        assert_eq!(lamp.attenuation.linear, 0.01);
+        assert_eq!(lamp.attenuation.quadratic, 0.001);
+        let spot = lamp.spot.as_ref().unwrap();
+        assert_eq!(
+            (spot.inner_angle, spot.outer_angle, spot.falloff),
+            (0.6, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_spawn_lights() {
+        let mut app = sdf_test_app(parse_sdf_content(LIGHT_WORLD).unwrap());
+        app.update();
+
+        let sun = link(&mut app, "SDF_Light_sun");
+        let world = app.world();
+        let light = world.get::<DirectionalLight>(sun).unwrap();
+        assert!(light.shadows_enabled);
+        // SDF (x, y, z) is Bevy (x, z, -y)
+        let forward = *world.get::<Transform>(sun).unwrap().forward();
+        let expected = Vec3::new(-0.5, -0.9, -0.1).normalize();
+        assert!(forward.abs_diff_eq(expected, 1e-5), "{forward}");
+
+        let lamp = link(&mut app, "SDF_Light_aisle_lamp");
+        let world = app.world();
+        let light = world.get::<SpotLight>(lamp).unwrap();
+        assert_eq!(light.range, 15.0);
+        assert!(!light.shadows_enabled);
+        assert_relative_eq!(light.inner_angle, 0.3);
+        assert_relative_eq!(light.outer_angle, 0.5);
+        let transform = world.get::<Transform>(lamp).unwrap();
+        assert!(transform.forward().abs_diff_eq(Vec3::NEG_Y, 1e-5));
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(2.0, 3.0, 1.0), 1e-5)
+        );
+
+        let mut point_lights = app.world_mut().query::<&PointLight>();
+        assert_eq!(point_lights.iter(app.world()).count(), 0);
+    }
+
+    const PENDULUM_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="pendulum_world">
+    <model name="pendulum">
+      <link name="base">
+        <pose>0 0 1 0 0 0</pose>
+        <inertial><mass>1.0</mass></inertial>
+        <collision name="base_collision">
+          <geometry><box><size>0.1 0.1 0.1</size></box></geometry>
+        </collision>
+      </link>
+      <link name="arm">
+        <pose>0.25 0 1 0 0 0</pose>
+        <inertial><mass>1.0</mass></inertial>
+        <collision name="arm_collision">
+          <geometry><box><size>0.5 0.05 0.05</size></box></geometry>
+        </collision>
+      </link>
+      <joint name="base_to_world" type="fixed">
+        <parent>world</parent>
+        <child>base</child>
+      </joint>
+      <joint name="hinge" type="revolute">
+        <pose>-0.25 0 0 0 0 0</pose>
+        <parent>base</parent>
+        <child>arm</child>
+        <axis>
+          <xyz>0 1 0</xyz>
+          <limit>
+            <lower>-0.5</lower>
+            <upper>0.5</upper>
+            <effort>10</effort>
+            <velocity>2</velocity>
+          </limit>
+          <dynamics>
+            <damping>0.1</damping>
+            <friction>0.0</friction>
+          </dynamics>
+        </axis>
+      </joint>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_parse_joints() {
+        let world = parse_sdf_content(PENDULUM_WORLD).unwrap();
+        let model = &world.models[0];
+        assert_eq!(model.joints.len(), 2);
+        // The joint pose must not leak into the model
+        assert_eq!(model.pose.xyz, Vec3::ZERO);
+
+        let hinge = &model.joints[1];
+        assert_eq!(hinge.joint_type, "revolute");
+        assert_eq!(
+            (hinge.parent.as_str(), hinge.child.as_str()),
+            ("base", "arm")
+        );
+        assert_eq!(hinge.pose.xyz, Vec3::new(-0.25, 0.0, 0.0));
+        let axis = hinge.axis.as_ref().unwrap();
+        assert_eq!(axis.xyz, Vec3::Y);
+        let limit = axis.limit.as_ref().unwrap();
+        assert_eq!((limit.lower, limit.upper), (-0.5, 0.5));
+        assert_eq!((limit.effort, limit.velocity), (10.0, 2.0));
+        assert_eq!(axis.dynamics.as_ref().unwrap().damping, 0.1);
+        assert!(model.joints[0].axis.is_none());
+    }
+
+    #[test]
+    fn test_pendulum_swings_to_joint_limit() {
+        let mut app = sdf_test_app(parse_sdf_content(PENDULUM_WORLD).unwrap());
+        app.update();
+
+        let base = link(&mut app, "pendulum_base");
+        let arm = link(&mut app, "pendulum_arm");
+        let joint = app.world().get::<ImpulseJoint>(arm).unwrap();
+        assert_eq!(joint.parent, base);
+        let limits = joint.data.as_ref().limits(JointAxis::AngX).unwrap();
+        assert_eq!((limits.min, limits.max), (-0.5, 0.5));
+
+        for _ in 0..200 {
+            app.update();
+        }
+
+        // The base is welded to the world, the arm hangs from it at the upper limit
+        let world = app.world();
+        let base_transform = world.get::<Transform>(base).unwrap();
+        assert!(
+            base_transform
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 1.0, 0.0), 0.01),
+            "{}",
+            base_transform.translation
+        );
+        let arm_transform = world.get::<Transform>(arm).unwrap();
+        let expected = Vec3::new(0.25 * 0.5_f32.cos(), 1.0 - 0.25 * 0.5_f32.sin(), 0.0);
+        assert!(
+            arm_transform.translation.abs_diff_eq(expected, 0.02),
+            "{}",
+            arm_transform.translation
+        );
+    }
+
+    /// A bar on a vertical hinge, so gravity does not turn it
+    fn spinner_model(name: &str, y: f32, limit: &str, dynamics: &str) -> String {
+        format!(
+            r#"<model name="{name}">
+      <pose>0 {y} 1 0 0 0</pose>
+      <link name="bar">
+        <pose>0.25 0 0 0 0 0</pose>
+        <inertial><mass>1.0</mass></inertial>
+        <collision name="bar_collision">
+          <geometry><box><size>0.5 0.05 0.05</size></box></geometry>
+        </collision>
+      </link>
+      <joint name="hinge" type="continuous">
+        <pose>-0.25 0 0 0 0 0</pose>
+        <parent>world</parent>
+        <child>bar</child>
+        <axis>
+          <xyz>0 0 1</xyz>
+          <limit>{limit}</limit>
+          <dynamics>{dynamics}</dynamics>
+        </axis>
+      </joint>
+    </model>"#
+        )
+    }
+
+    #[test]
+    fn test_joint_dynamics_and_limits() {
+        let models = [
+            spinner_model(
+                "damped",
+                0.0,
+                "",
+                "<damping>0.5</damping><friction>1.0</friction>",
+            ),
+            spinner_model("stuck", 1.0, "", "<friction>5.0</friction>"),
+            spinner_model("limited", 2.0, "<velocity>2</velocity>", ""),
+            spinner_model(
+                "weak",
+                3.0,
+                "<effort>1</effort><velocity>2</velocity>",
+                "<friction>5.0</friction>",
+            ),
+        ];
+        let world = format!(
+            r#"<sdf version="1.6"><world name="spinners">{}</world></sdf>"#,
+            models.concat()
+        );
+        let mut app = sdf_test_app(parse_sdf_content(&world).unwrap());
+        app.update();
+        let names = ["damped_bar", "stuck_bar", "limited_bar", "weak_bar"];
+        let bars = names.map(|name| link(&mut app, name));
+        for bar in bars {
+            app.world_mut().entity_mut(bar).insert((
+                ExternalForce {
+                    force: Vec3::ZERO,
+                    torque: Vec3::Y * 3.0,
+                },
+                Velocity::zero(),
+            ));
+        }
+        for _ in 0..200 {
+            app.update();
+        }
+
+        let [damped, stuck, limited, weak] =
+            bars.map(|bar| app.world().get::<Velocity>(bar).unwrap().angvel.y);
+        // Friction takes 1 N·m of the torque and damping balances the rest at 4 rad/s
+        assert_relative_eq!(damped, 4.0, epsilon = 0.1);
+        assert!(stuck.abs() < 0.01, "stuck bar turns at {stuck} rad/s");
+        assert_relative_eq!(limited, 2.0, epsilon = 0.05);
+        // The effort limit caps both the friction and the braking
+        assert!(weak > 10.0, "weak bar turns at {weak} rad/s");
+    }
+
+    const WEDGE_WORLD: &str = include_str!("../assets/worlds/wedge_world.sdf");
+
+    #[test]
+    fn test_load_collada_wedge() {
+        let bytes = std::fs::read("assets/models/wedge/meshes/wedge.dae").unwrap();
+        let mesh = load_collada(&bytes).unwrap();
+
+        // Four faces of the polylist are quads, two are triangles
+        assert_eq!(mesh.indices().unwrap().len(), 3 * 8);
+        // Centimeters are scaled to meters and the node translation is applied
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap();
+        let (min, max) = positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), &p| (min.min(Vec3::from(p)), max.max(Vec3::from(p))),
+        );
+        assert!(min.abs_diff_eq(Vec3::new(-0.5, -0.5, 0.0), 1e-6), "{min}");
+        assert!(max.abs_diff_eq(Vec3::new(0.5, 0.5, 0.5), 1e-6), "{max}");
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+    }
+
+    #[test]
+    fn test_parse_mesh_geometry() {
+        let world = parse_sdf_content(WEDGE_WORLD).unwrap();
+        let link = &world.models[1].links[0];
+
+        match &link.collisions[0].geometry {
+            SdfGeometry::Mesh { uri, scale } => {
+                assert_eq!(uri, "model://wedge/meshes/wedge.dae");
+                assert_eq!(*scale, Some(Vec3::new(2.0, 1.0, 0.5)));
+            }
+            geometry => panic!("expected a mesh, got {geometry:?}"),
+        }
+        assert!(matches!(
+            &world.models[0].links[0].visuals[0].geometry,
+            SdfGeometry::Mesh { scale: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_spawn_mesh_colliders() {
+        let mut app = sdf_test_app(parse_sdf_content(WEDGE_WORLD).unwrap());
+        app.update();
+        let ramp = link(&mut app, "ramp_link_collision");
+        let block = link(&mut app, "block_link_collision");
+        let block_link = link(&mut app, "block_link");
+
+        // Colliders are built once the meshes have loaded
+        for _ in 0..500 {
+            let world = app.world();
+            if world.get::<Collider>(ramp).is_some() && world.get::<Collider>(block).is_some() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+            app.update();
+        }
+
+        let world = app.world();
+        let ramp_collider = world.get::<Collider>(ramp).unwrap();
+        assert!(ramp_collider.as_trimesh().is_some());
+        let block_collider = world.get::<Collider>(block).unwrap();
+        assert!(block_collider.as_compound().is_some());
+
+        // The Z-up mesh is turned Y-up and keeps its SDF scale
+        let transform = world.get::<Transform>(block).unwrap();
+        assert_eq!(transform.scale, Vec3::new(2.0, 1.0, 0.5));
+        let up = transform.rotation * Vec3::Z;
+        assert!(up.abs_diff_eq(Vec3::Y, 1e-6), "{up}");
+        assert!(world.get::<RigidBody>(block_link).is_some());
+    }
+
+    /// Fresh directory to hold test models
+    fn model_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sdf_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_model(dir: &Path, name: &str, config: &str, sdf: &str) {
+        let model = dir.join(name);
+        std::fs::create_dir_all(&model).unwrap();
+        std::fs::write(model.join("model.config"), config).unwrap();
+        std::fs::write(model.join("model.sdf"), sdf).unwrap();
+    }
+
+    const MODEL_CONFIG: &str = r#"<?xml version="1.0"?>
+<model><name>model</name><sdf version="1.6">model.sdf</sdf></model>"#;
+
+    const CRATE_MODEL: &str = r#"<sdf version="1.6">
+  <model name="crate">
+    <pose>0 0 0.5 0 0 0</pose>
+    <link name="body">
+      <pose>1 0 0 0 0 0</pose>
+      <visual name="visual">
+        <geometry><mesh><uri>meshes/crate.stl</uri></mesh></geometry>
+      </visual>
+    </link>
+  </model>
+</sdf>"#;
+
+    #[test]
+    fn test_include_applies_overrides() {
+        let dir = model_dir("include_overrides");
+        // The newest SDF version listed in model.config is used
+        write_model(
+            &dir,
+            "crate",
+            r#"<model><sdf version="1.4">old.sdf</sdf><sdf version="1.10">model.sdf</sdf></model>"#,
+            CRATE_MODEL,
+        );
+        let model_path = SdfModelPath {
+            dirs: vec![dir.clone()],
+        };
+
+        let world = parse_sdf_with_model_path(
+            r#"<sdf version="1.6"><world name="warehouse">
+              <include><uri>model://crate</uri></include>
+              <include>
+                <uri>model://crate</uri>
+                <name>crate_2</name>
+                <pose>2 3 0 0 0 1.5</pose>
+                <static>true</static>
+              </include>
+            </world></sdf>"#,
+            &model_path,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let [plain, overridden] = &world.models[..] else {
+            panic!("expected two models, got {:?}", world.models);
+        };
+        assert_eq!(plain.name, "crate");
+        assert_eq!(plain.pose.xyz, Vec3::new(0.0, 0.0, 0.5));
+        assert!(!plain.static_);
+        assert_eq!(overridden.name, "crate_2");
+        assert_eq!(overridden.pose.xyz, Vec3::new(2.0, 3.0, 0.0));
+        assert_eq!(overridden.pose.rpy, Vec3::new(0.0, 0.0, 1.5));
+        assert!(overridden.static_);
+        // Relative mesh URIs resolve inside the model directory
+        match &overridden.links[0].visuals[0].geometry {
+            SdfGeometry::Mesh { uri, .. } => assert_eq!(uri, "model://crate/meshes/crate.stl"),
+            geometry => panic!("expected a mesh, got {geometry:?}"),
+        }
+    }
+
+    #[test]
+    fn test_nested_includes() {
+        let dir = model_dir("nested_includes");
+        write_model(&dir, "crate", MODEL_CONFIG, CRATE_MODEL);
+        write_model(
+            &dir,
+            "cart",
+            MODEL_CONFIG,
+            r#"<sdf version="1.6">
+              <model name="cart">
+                <link name="base"></link>
+                <include>
+                  <uri>model://crate</uri>
+                  <name>load</name>
+                  <pose>0 0 1 0 0 1.5707964</pose>
+                </include>
+                <joint name="mount" type="fixed">
+                  <parent>base</parent>
+                  <child>load::body</child>
+                </joint>
+              </model>
+            </sdf>"#,
+        );
+        let model_path = SdfModelPath {
+            dirs: vec![dir.clone()],
+        };
+
+        let world = parse_sdf_with_model_path(
+            r#"<sdf version="1.6"><world name="w">
+              <include><uri>model://cart</uri><pose>5 0 0 0 0 0</pose></include>
+            </world></sdf>"#,
+            &model_path,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(world.models.len(), 1);
+        let cart = &world.models[0];
+        assert_eq!(cart.pose.xyz, Vec3::new(5.0, 0.0, 0.0));
+        let names: Vec<&str> = cart.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(names, ["base", "load::body"]);
+        assert_eq!(cart.joints[0].child, "load::body");
+
+        // The crate's link pose is composed with the include pose
+        let body = &cart.links[1];
+        assert!(
+            body.pose.xyz.abs_diff_eq(Vec3::new(0.0, 1.0, 1.0), 1e-5),
+            "{}",
+            body.pose.xyz
+        );
+        assert_relative_eq!(body.pose.rpy.z, std::f32::consts::FRAC_PI_2, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_sdf_world_asset_reads_included_models() {
+        let dir = model_dir("asset_includes");
+        write_model(&dir, "crate", MODEL_CONFIG, CRATE_MODEL);
+        std::fs::write(
+            dir.join("warehouse.sdf"),
+            r#"<sdf version="1.6"><world name="warehouse">
+              <include><uri>model://crate</uri></include>
+            </world></sdf>"#,
+        )
+        .unwrap();
+        let model_path = SdfModelPath {
+            dirs: vec![dir.clone()],
+        };
+        let mut app =
+            sdf_test_app_with_model_path(parse_sdf_content(EMPTY_WORLD).unwrap(), model_path);
+
+        // Included models are read by the loader through the model source
+        let handle: Handle<SdfWorld> = app
+            .world()
+            .resource::<AssetServer>()
+            .load("model://warehouse.sdf");
+        for _ in 0..500 {
+            app.update();
+            if app.world().resource::<Assets<SdfWorld>>().contains(&handle) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let sdf_world = app
+            .world()
+            .resource::<Assets<SdfWorld>>()
+            .get(&handle)
+            .unwrap();
+        let names: Vec<&str> = sdf_world
+            .models
+            .iter()
+            .map(|model| model.name.as_str())
+            .collect();
+        assert_eq!(names, ["crate"]);
+        assert_eq!(sdf_world.models[0].pose.xyz, Vec3::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_include_cycle_is_an_error() {
+        let dir = model_dir("include_cycle");
+        let including = |uri: &str| {
+            format!(
+                r#"<sdf version="1.6"><model name="m"><include><uri>{uri}</uri></include></model></sdf>"#
+            )
+        };
+        write_model(&dir, "a", MODEL_CONFIG, &including("model://b"));
+        write_model(&dir, "b", MODEL_CONFIG, &including("model://a"));
+        let model_path = SdfModelPath {
+            dirs: vec![dir.clone()],
+        };
+
+        let cycle = parse_sdf_with_model_path(
+            r#"<sdf version="1.6"><world name="w"><include><uri>model://a</uri></include></world></sdf>"#,
+            &model_path,
+        );
+        let missing = parse_sdf_with_model_path(
+            r#"<sdf version="1.6"><world name="w"><include><uri>model://c</uri></include></world></sdf>"#,
+            &model_path,
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let Err(SdfError::Include { location, message }) = cycle else {
+            panic!("expected an include error, got {:?}", cycle);
+        };
+        assert_eq!(
+            message,
+            "Include cycle: model://a -> model://b -> model://a"
+        );
+        assert_eq!(location.element_path, "sdf/model[m]/include");
+        assert!(
+            missing
+                .unwrap_err()
+                .to_string()
+                .contains("model://c not found")
+        );
+    }
+
+    const MOON_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="moon">
+    <gravity>0 0 -1.62</gravity>
+    <physics name="fast" type="ode">
+      <max_step_size>0.004</max_step_size>
+      <real_time_factor>2.0</real_time_factor>
+    </physics>
+    <model name="ball">
+      <pose>0 0 10 0 0 0</pose>
+      <link name="ball_link">
+        <inertial><mass>1.0</mass></inertial>
+        <collision name="collision">
+          <geometry><sphere><radius>0.1</radius></sphere></geometry>
+        </collision>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_world_physics_drives_rapier() {
+        let world = parse_sdf_content(MOON_WORLD).unwrap();
+        let physics = world.physics.clone().unwrap();
+        assert_eq!(physics.name, "fast");
+        assert_eq!(physics.gravity, Vec3::new(0.0, 0.0, -1.62));
+        assert_relative_eq!(physics.max_step_size, 0.004);
+        assert_relative_eq!(physics.real_time_factor, 2.0);
+
+        let mut app = sdf_test_app(world);
+        for _ in 0..50 {
+            app.update();
+        }
+        let ball = link(&mut app, "ball_ball_link");
+
+        let world = app.world_mut();
+        let gravity = world
+            .query::<&RapierConfiguration>()
+            .single(world)
+            .unwrap()
+            .gravity;
+        assert_eq!(gravity, Vec3::new(0.0, -1.62, 0.0));
+        assert_eq!(
+            world.resource::<Time<Fixed>>().timestep(),
+            Duration::from_millis(4)
+        );
+        assert!(matches!(
+            *world.resource::<TimestepMode>(),
+            TimestepMode::Fixed { dt: 0.004, .. }
+        ));
+
+        // Simulated time runs twice as fast as the 10 ms updates
+        let elapsed = world.resource::<Time<Virtual>>().elapsed_secs();
+        assert_relative_eq!(elapsed, 1.0, epsilon = 0.03);
+        let height = world.get::<GlobalTransform>(ball).unwrap().translation().y;
+        assert_relative_eq!(
+            10.0 - height,
+            0.5 * 1.62 * elapsed * elapsed,
+            epsilon = 0.05
+        );
+    }
+
+    const SURFACE_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="surfaces">
+    <model name="floor">
+      <static>true</static>
+      <link name="link">
+        <collision name="ice">
+          <geometry><box><size>10 10 0.1</size></box></geometry>
+          <surface>
+            <friction><ode><mu>0.05</mu><mu2>0.02</mu2></ode></friction>
+            <contact><ode><kp>1e6</kp><kd>100</kd></ode></contact>
+          </surface>
+        </collision>
+      </link>
+    </model>
+    <model name="ball">
+      <pose>0 0 1 0 0 0</pose>
+      <link name="link">
+        <inertial><mass>1.0</mass></inertial>
+        <collision name="rubber">
+          <geometry><sphere><radius>0.1</radius></sphere></geometry>
+          <surface>
+            <bounce><restitution_coefficient>0.9</restitution_coefficient></bounce>
+          </surface>
+        </collision>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_parse_and_spawn_surfaces() {
+        let world = parse_sdf_content(SURFACE_WORLD).unwrap();
+        let ice = world.models[0].links[0].collisions[0]
+            .surface
+            .clone()
+            .unwrap();
+        assert_relative_eq!(ice.mu, 0.05);
+        assert_relative_eq!(ice.mu2, 0.02);
+        assert_relative_eq!(ice.restitution_coefficient, 0.0);
+        assert_eq!(ice.kp, Some(1e6));
+        assert_eq!(ice.kd, Some(100.0));
+        let rubber = world.models[1].links[0].collisions[0]
+            .surface
+            .clone()
+            .unwrap();
+        assert_relative_eq!(rubber.mu, 1.0);
+        assert_relative_eq!(rubber.restitution_coefficient, 0.9);
+
+        let mut app = sdf_test_app(world);
+        app.update();
+        let ice = link(&mut app, "floor_link_ice");
+        let rubber = link(&mut app, "ball_link_rubber");
+        let world = app.world();
+        let friction = world.get::<Friction>(ice).unwrap();
+        assert_relative_eq!(friction.coefficient, 0.05);
+        assert_eq!(friction.combine_rule, CoefficientCombineRule::Min);
+        let restitution = world.get::<Restitution>(rubber).unwrap();
+        assert_relative_eq!(restitution.coefficient, 0.9);
+        assert_eq!(restitution.combine_rule, CoefficientCombineRule::Max);
+    }
+
+    #[test]
+    fn test_malformed_surface_value() {
+        let world = SURFACE_WORLD.replace("<mu>0.05</mu>", "<mu>abc</mu>");
+        let error = parse_sdf_file(&world, "surfaces.sdf", &SdfModelPath::default()).unwrap_err();
+
+        assert_eq!(
+            error,
+            SdfError::InvalidValue {
+                location: SdfLocation {
+                    file: Some("surfaces.sdf".to_string()),
+                    line: 10,
+                    column: 28,
+                    element_path:
+                        "sdf/world[surfaces]/model[floor]/link[link]/collision[ice]/surface/friction/ode/mu"
+                            .to_string(),
+                },
+                message: "expected a number, got \"abc\"".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_rubber_ball_bounces() {
+        let mut app = sdf_test_app(parse_sdf_content(SURFACE_WORLD).unwrap());
+        app.update();
+        let ball = link(&mut app, "ball_link");
+        let height = |app: &App| {
+            app.world()
+                .get::<GlobalTransform>(ball)
+                .unwrap()
+                .translation()
+                .y
+        };
+
+        // Falls 0.85 m onto the floor, which has no restitution of its own
+        let heights: Vec<f32> = (0..100)
+            .map(|_| {
+                app.update();
+                height(&app)
+            })
+            .collect();
+        let bounce = heights
+            .windows(2)
+            .position(|pair| pair[1] > pair[0])
+            .unwrap();
+        assert!(heights[bounce] < 0.2, "ball never reached the floor");
+        // Without restitution the ball would stay put; Rapier's soft contacts lose more energy
+        // than the 0.9 coefficient alone
+        let rebound = heights[bounce..]
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        assert!(
+            rebound - heights[bounce] > 0.2,
+            "ball rebounded {} m",
+            rebound - heights[bounce]
+        );
+    }
+
+    /// Two 1 m tall shelves, one loaded so its center of mass is past its front edge
+    const SHELF_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="shelves">
+    <model name="ground_plane">
+      <static>true</static>
+      <link name="link">
+        <collision name="collision">
+          <geometry><plane><normal>0 0 1</normal><size>10 10</size></plane></geometry>
+        </collision>
+      </link>
+    </model>
+    <model name="shelf">
+      <pose>0 2 0.5 0 0 0</pose>
+      <link name="link">
+        <inertial>
+          <mass>10</mass>
+          <inertia><ixx>0.967</ixx><iyy>0.867</iyy><izz>0.167</izz></inertia>
+        </inertial>
+        <collision name="collision">
+          <geometry><box><size>0.2 0.4 1.0</size></box></geometry>
+        </collision>
+      </link>
+    </model>
+    <model name="loaded_shelf">
+      <pose>0 0 0.5 0 0 0</pose>
+      <link name="link">
+        <inertial>
+          <pose>0.15 0 0.3 0 0 0</pose>
+          <mass>10</mass>
+          <inertia><ixx>0.967</ixx><iyy>0.867</iyy><izz>0.167</izz></inertia>
+        </inertial>
+        <collision name="collision">
+          <geometry><box><size>0.2 0.4 1.0</size></box></geometry>
+        </collision>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_inertial_mass_properties() {
+        let world = parse_sdf_content(
+            r#"<sdf version="1.6"><world name="w"><model name="m"><link name="link">
+              <inertial>
+                <pose>0.1 0.2 0.3 0 0 0.5</pose>
+                <mass>2</mass>
+                <inertia>
+                  <ixx>1</ixx><iyy>2</iyy><izz>3</izz>
+                  <ixy>0.1</ixy><ixz>0.2</ixz><iyz>0.3</iyz>
+                </inertia>
+              </inertial>
+              <collision name="collision"><geometry><box><size>1 1 1</size></box></geometry></collision>
+            </link></model></world></sdf>"#,
+        )
+        .unwrap();
+        let mut app = sdf_test_app(world);
+        app.update();
+        let body = link(&mut app, "m_link");
+        let collision = link(&mut app, "m_link_collision");
+
+        let world = app.world();
+        let Some(AdditionalMassProperties::MassProperties(properties)) =
+            world.get::<AdditionalMassProperties>(body)
+        else {
+            panic!("expected full mass properties");
+        };
+        assert_relative_eq!(properties.mass, 2.0);
+        // SDF (x, y, z) is Bevy (x, z, -y)
+        assert!(
+            properties
+                .local_center_of_mass
+                .abs_diff_eq(Vec3::new(0.1, 0.3, -0.2), 1e-6),
+            "{}",
+            properties.local_center_of_mass
+        );
+
+        // The principal axes and moments rebuild the rotated tensor in Y-up axes
+        let principal = Mat3::from_quat(properties.principal_inertia_local_frame);
+        let rebuilt =
+            principal * Mat3::from_diagonal(properties.principal_inertia) * principal.transpose();
+        let conv = Mat3::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+        let yaw = Mat3::from_rotation_z(0.5);
+        let tensor = Mat3::from_cols_array(&[1.0, 0.1, 0.2, 0.1, 2.0, 0.3, 0.2, 0.3, 3.0]);
+        let expected = conv * yaw * tensor * yaw.transpose() * conv.transpose();
+        assert!(
+            rebuilt.abs_diff_eq(expected, 1e-4),
+            "{rebuilt} != {expected}"
+        );
+
+        // Collisions add no mass of their own
+        assert!(matches!(
+            world.get::<ColliderMassProperties>(collision),
+            Some(ColliderMassProperties::Density(0.0))
+        ));
+    }
+
+    #[test]
+    fn test_inertial_without_mass_is_dynamic() {
+        let world = parse_sdf_content(
+            r#"<sdf version="1.6"><world name="w">
+              <model name="m"><link name="link">
+                <inertial><inertia><ixx>1</ixx><iyy>1</iyy><izz>1</izz></inertia></inertial>
+                <collision name="collision"><geometry><box><size>1 1 1</size></box></geometry></collision>
+              </link></model>
+              <model name="fixed"><static>true</static><link name="link">
+                <inertial><mass>2</mass></inertial>
+                <collision name="collision"><geometry><box><size>1 1 1</size></box></geometry></collision>
+              </link></model>
+            </world></sdf>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            world.models[0].links[0].inertial.as_ref().unwrap().mass,
+            1.0
+        );
+        let mut app = sdf_test_app(world);
+        app.update();
+        let body = link(&mut app, "m_link");
+        let fixed = link(&mut app, "fixed_link");
+
+        // The inertia tensor applies at the default mass, only <static> fixes a body
+        let world = app.world();
+        assert_eq!(world.get::<RigidBody>(body), Some(&RigidBody::Dynamic));
+        let Some(AdditionalMassProperties::MassProperties(properties)) =
+            world.get::<AdditionalMassProperties>(body)
+        else {
+            panic!("expected full mass properties");
+        };
+        assert_relative_eq!(properties.mass, 1.0);
+        assert_eq!(world.get::<RigidBody>(fixed), Some(&RigidBody::Fixed));
+    }
+
+    #[test]
+    fn test_center_of_mass_tips_shelf() {
+        let mut app = sdf_test_app(parse_sdf_content(SHELF_WORLD).unwrap());
+        app.update();
+        let shelf = link(&mut app, "shelf_link");
+        let loaded_shelf = link(&mut app, "loaded_shelf_link");
+        for _ in 0..150 {
+            app.update();
+        }
+
+        let world = app.world();
+        let up = |entity| world.get::<GlobalTransform>(entity).unwrap().rotation() * Vec3::Y;
+        assert!(up(shelf).y > 0.99, "shelf fell over: {}", up(shelf));
+        // The loaded shelf falls forwards, towards SDF +x
+        assert!(
+            up(loaded_shelf).x > 0.5,
+            "loaded shelf stands: {}",
+            up(loaded_shelf)
+        );
+    }
+
+    const MALFORMED_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.6">
+  <world name="malformed">
+    <model name="box">
+      <pose>0 0 1 0 0</pose>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_parse_error_location() {
+        let error =
+            parse_sdf_file(MALFORMED_WORLD, "malformed.sdf", &SdfModelPath::default()).unwrap_err();
+
+        assert_eq!(
+            error,
+            SdfError::InvalidValue {
+                location: SdfLocation {
+                    file: Some("malformed.sdf".to_string()),
+                    line: 5,
+                    column: 7,
+                    element_path: "sdf/world[malformed]/model[box]/pose".to_string(),
+                },
+                message: "expected 6 numbers, got 5".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "malformed.sdf:5:7 (sdf/world[malformed]/model[box]/pose): expected 6 numbers, got 5"
+        );
+        let unclosed = parse_sdf_content("<sdf version=\"1.6\">\n  <world name=\"w\">\n</sdf>");
+        assert!(matches!(unclosed, Err(SdfError::Xml { location, .. }) if location.line == 3));
+    }
+
+    const POSE_FORMATS_WORLD: &str = r#"<sdf version="1.9"><world name="w">
+      <model name="radians"><pose>1 2 3 0 0 1.5707964</pose></model>
+      <model name="degrees"><pose degrees="true">1 2 3 0 0 90</pose></model>
+      <model name="quaternion"><pose rotation_format="quat_xyzw">1 2 3 0 0 0.70710677 0.70710677</pose></model>
+    </world></sdf>"#;
+
+    #[test]
+    fn test_pose_rotation_formats() {
+        let world = parse_sdf_content(POSE_FORMATS_WORLD).unwrap();
+
+        for model in &world.models {
+            assert_eq!(model.pose.xyz, Vec3::new(1.0, 2.0, 3.0), "{}", model.name);
+            assert!(
+                model
+                    .pose
+                    .rpy
+                    .abs_diff_eq(Vec3::new(0.0, 0.0, std::f32::consts::FRAC_PI_2), 1e-5),
+                "{}: {}",
+                model.name,
+                model.pose.rpy
+            );
+        }
+        let diagnostics = validate_sdf(POSE_FORMATS_WORLD, "poses.sdf", &SdfModelPath::default());
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        // A quaternion takes seven numbers, and the rotation format must be known
+        let short = parse_sdf_content(
+            r#"<sdf version="1.9"><world name="w"><model name="m">
+              <pose rotation_format="quat_xyzw">0 0 0 0 0 0</pose>
+            </model></world></sdf>"#,
+        );
+        assert!(
+            matches!(&short, Err(SdfError::InvalidValue { message, .. }) if message == "expected 7 numbers, got 6"),
+            "{:?}",
+            short
+        );
+        let unknown = validate_sdf(
+            r#"<sdf version="1.9"><world name="w"><model name="m">
+              <pose rotation_format="euler_xyz">0 0 0 0 0 0</pose>
+            </model></world></sdf>"#,
+            "poses.sdf",
+            &SdfModelPath::default(),
+        );
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].severity, SdfSeverity::Error);
+        assert_eq!(unknown[0].message, "unknown rotation format \"euler_xyz\"");
+    }
+
+    #[test]
+    fn test_unknown_elements_are_skipped() {
+        let world = parse_sdf_content(
+            r#"<sdf version="1.6"><world name="w"><model name="m"><link name="l">
+                <pose>1 2 3 0 0 0</pose>
+                <sensor name="camera" type="camera"><pose>9 9 9 0 0 0</pose><update_rate>x</update_rate></sensor>
+            </link></model></world></sdf>"#,
+        )
+        .unwrap();
+
+        assert_eq!(world.models[0].links[0].pose.xyz, Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_validate_sdf() {
+        let content = r#"<?xml version="1.0" ?>
+<sdf version="1.12">
+  <world name="w">
+    <gui><camera name="user"/></gui>
+    <model name="m">
+      <link name="l">
+        <gravity>false</gravity>
+        <inertial><mass>-1</mass></inertial>
+        <collision name="c"><geometry><plane><size>1 2 3</size></plane></geometry></collision>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+        let diagnostics = validate_sdf(content, "test.sdf", &SdfModelPath::default());
+        let found: Vec<(SdfSeverity, usize, &str)> = diagnostics
+            .iter()
+            .map(|d| {
+                let location = d.location.as_ref().unwrap();
+                (d.severity, location.line, d.message.as_str())
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                (
+                    SdfSeverity::Warning,
+                    2,
+                    "SDF version 1.12 is not supported (supported: 1.4 to 1.11)"
+                ),
+                (SdfSeverity::Warning, 4, "unknown element <gui> is ignored"),
+                (SdfSeverity::Error, 8, "mass must not be negative, got -1"),
+                (SdfSeverity::Error, 9, "expected 2 numbers, got 3"),
+            ]
+        );
+
+        // Files without errors are loaded as well, to check their includes
+        let missing = validate_sdf(
+            r#"<sdf version="1.6"><world name="w"><include><uri>model://nowhere</uri></include></world></sdf>"#,
+            "test.sdf",
+            &SdfModelPath::default(),
+        );
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].severity, SdfSeverity::Error);
+        assert!(missing[0].message.contains("model://nowhere not found"));
+
+        for world in ["simple_world.sdf", "wedge_world.sdf"] {
+            let path = format!("assets/worlds/{}", world);
+            let content = std::fs::read_to_string(&path).unwrap();
+            let diagnostics = validate_sdf(&content, &path, &SdfModelPath::default());
+            assert!(
+                diagnostics
+                    .iter()
+                    .all(|d| d.severity == SdfSeverity::Warning),
+                "{:?}",
+                diagnostics
+            );
+        }
+    }
+
+    const SHAPES_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.9">
+  <world name="shapes">
+    <model name="shapes">
+      <static>true</static>
+      <link name="link">
+        <collision name="pillar">
+          <geometry><cylinder><radius>0.2</radius><length>3</length></cylinder></geometry>
+        </collision>
+        <collision name="rail">
+          <geometry><capsule><radius>0.1</radius><length>0.5</length></capsule></geometry>
+        </collision>
+        <collision name="rock">
+          <geometry><ellipsoid><radii>1 2 0.5</radii></ellipsoid></geometry>
+        </collision>
+        <collision name="walls">
+          <geometry>
+            <polyline>
+              <point>0 0</point><point>2 0</point><point>2 1</point>
+              <point>1 1</point><point>1 3</point><point>0 3</point><point>0 0</point>
+              <height>2</height>
+            </polyline>
+            <polyline>
+              <point>5 0</point><point>5 1</point><point>6 1</point><point>6 0</point>
+            </polyline>
+          </geometry>
+        </collision>
+        <visual name="walls">
+          <geometry>
+            <polyline>
+              <point>0 0</point><point>2 0</point><point>2 1</point>
+              <point>1 1</point><point>1 3</point><point>0 3</point>
+              <height>2</height>
+            </polyline>
+          </geometry>
+        </visual>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_parse_shapes() {
+        let world = parse_sdf_content(SHAPES_WORLD).unwrap();
+        let geometries: Vec<&SdfGeometry> = world.models[0].links[0]
+            .collisions
+            .iter()
+            .map(|collision| &collision.geometry)
+            .collect();
+
+        assert_eq!(
+            geometries[0],
+            &SdfGeometry::Cylinder {
+                radius: 0.2,
+                length: 3.0
+            }
+        );
+        assert_eq!(
+            geometries[1],
+            &SdfGeometry::Capsule {
+                radius: 0.1,
+                length: 0.5
+            }
+        );
+        assert_eq!(
+            geometries[2],
+            &SdfGeometry::Ellipsoid {
+                radii: Vec3::new(1.0, 2.0, 0.5)
+            }
+        );
+        let SdfGeometry::Polyline { polylines } = geometries[3] else {
+            panic!("expected a polyline, got {:?}", geometries[3]);
+        };
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(polylines[0].points.len(), 7);
+        assert_eq!(polylines[0].height, 2.0);
+        assert_eq!(polylines[1].points[2], Vec2::new(6.0, 1.0));
+        assert_eq!(polylines[1].height, 1.0);
+
+        let world = parse_sdf_content(
+            r#"<sdf version="1.6"><world name="w"><model name="m"><link name="l"><collision name="c"><geometry>
+                <heightmap><uri>terrain.png</uri><size>10 20 3</size><pos>1 2 -1</pos></heightmap>
+            </geometry></collision></link></model></world></sdf>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            world.models[0].links[0].collisions[0].geometry,
+            SdfGeometry::Heightmap {
+                uri: "terrain.png".to_string(),
+                size: Vec3::new(10.0, 20.0, 3.0),
+                pos: Vec3::new(1.0, 2.0, -1.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_spawn_shape_colliders() {
+        let mut app = sdf_test_app(parse_sdf_content(SHAPES_WORLD).unwrap());
+        app.update();
+        let world = app.world();
+        let collider = |name: &str| {
+            let entity = world
+                .iter_entities()
+                .find(|entity| {
+                    entity.get::<Name>().is_some_and(|n| n.as_str() == name)
+                        && entity.get::<Collider>().is_some()
+                })
+                .unwrap();
+            entity.get::<Collider>().unwrap().clone()
+        };
+
+        // SDF cylinders and capsules run along Z, which is Y in Bevy
+        let pillar = collider("shapes_link_pillar");
+        let cylinder = pillar.as_cylinder().unwrap();
+        assert_relative_eq!(cylinder.half_height(), 1.5);
+        assert_relative_eq!(cylinder.radius(), 0.2);
+        let rail = collider("shapes_link_rail");
+        let capsule = rail.as_capsule().unwrap();
+        assert_relative_eq!(capsule.half_height(), 0.25);
+        assert_relative_eq!(capsule.radius(), 0.1);
+
+        let rock = collider("shapes_link_rock");
+        assert!(rock.as_convex_polyhedron().is_some());
+        let extents: Vec3 = rock.raw.compute_local_aabb().half_extents().into();
+        assert!(
+            extents.abs_diff_eq(Vec3::new(1.0, 0.5, 2.0), 0.05),
+            "{extents}"
+        );
+
+        // Each outline becomes a convex hull
+        let walls = collider("shapes_link_walls");
+        assert_eq!(walls.as_compound().unwrap().shapes().count(), 2);
+        let aabb = walls.raw.compute_local_aabb();
+        let (min, max): (Vec3, Vec3) = (aabb.mins.into(), aabb.maxs.into());
+        assert!(min.abs_diff_eq(Vec3::new(0.0, 0.0, -3.0), 1e-5), "{min}");
+        assert!(max.abs_diff_eq(Vec3::new(6.0, 2.0, 0.0), 1e-5), "{max}");
+    }
+
+    #[test]
+    fn test_polyline_mesh_is_extruded_outline() {
+        let mut app = sdf_test_app(parse_sdf_content(SHAPES_WORLD).unwrap());
+        app.update();
+        // The collision has the same name, so look for the entity with the mesh
+        let handle = app
+            .world_mut()
+            .query::<(&Name, &Mesh3d)>()
+            .iter(app.world())
+            .find(|(name, _)| name.as_str() == "shapes_link_walls")
+            .map(|(_, mesh)| mesh.0.clone())
+            .unwrap();
+        let meshes = app.world().resource::<Assets<Mesh>>();
+        let positions: Vec<Vec3> = meshes
+            .get(&handle)
+            .unwrap()
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+            .iter()
+            .map(|&p| Vec3::from(p))
+            .collect();
+
+        // The concave L-shaped outline of area 4 is capped at both ends
+        let cap_area = |height: f32| -> f32 {
+            positions
+                .chunks(3)
+                .filter(|triangle| triangle.iter().all(|p| p.y == height))
+                .map(|triangle| (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]))
+                .map(|normal| {
+                    assert!(normal.y * (height - 1.0) > 0.0, "cap faces inwards");
+                    normal.length() / 2.0
+                })
+                .sum()
+        };
+        assert_relative_eq!(cap_area(2.0), 4.0, epsilon = 1e-5);
+        assert_relative_eq!(cap_area(0.0), 4.0, epsilon = 1e-5);
+        // Four cap triangles each end and two per side
+        assert_eq!(positions.len(), (4 * 2 + 6 * 2) * 3);
+    }
+
+    #[test]
+    fn test_heightmap_mesh_and_collider() {
+        let mut world = parse_sdf_content(
+            r#"<sdf version="1.6"><world name="w"><model name="hill"><static>true</static><link name="l">
+                <collision name="c"><geometry><heightmap><uri>hill.png</uri><size>4 4 2</size></heightmap></geometry></collision>
+                <visual name="v"><geometry><heightmap><uri>hill.png</uri><size>4 4 2</size></heightmap></geometry></visual>
+            </link></model></world></sdf>"#,
+        )
+        .unwrap();
+        // White in the top right corner, which is the +X +Y corner of the terrain
+        let image = Image::new(
+            bevy::render::render_resource::Extent3d {
+                width: 3,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            bevy::render::render_resource::TextureDimension::D2,
+            vec![0, 128, 255, 0, 0, 0],
+            bevy::render::render_resource::TextureFormat::R8Unorm,
+            bevy::asset::RenderAssetUsages::default(),
+        );
+        let handle = bevy::asset::weak_handle!("7a0f3c52-9d1e-4b6a-8f27-5c3e1d9b4a60");
+        world.images.insert("hill.png".to_string(), handle.clone());
+        let mut app = sdf_test_app(world);
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .insert(&handle, image);
+        app.update();
+        app.update();
+
+        let visual = link(&mut app, "hill_l_v");
+        let mesh = app.world().get::<Mesh3d>(visual).unwrap().0.clone();
+        let positions = app
+            .world()
+            .resource::<Assets<Mesh>>()
+            .get(&mesh)
+            .unwrap()
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+            .to_vec();
+        assert_eq!(positions.len(), 6);
+        assert!(positions.contains(&[2.0, 2.0, -2.0]));
+        assert!(positions.contains(&[-2.0, 0.0, 2.0]));
+
+        let collision = link(&mut app, "hill_l_c");
+        let collider = app.world().get::<Collider>(collision).unwrap();
+        let heightfield = collider.as_heightfield().unwrap();
+        // Two rows and three columns of samples make one row and two columns of cells
+        assert_eq!((heightfield.raw.nrows(), heightfield.raw.ncols()), (1, 2));
+        let top: Vec3 = heightfield.raw.root_aabb().maxs.into();
+        assert!(top.abs_diff_eq(Vec3::new(2.0, 2.0, 2.0), 1e-5), "{top}");
+    }
+
+    fn assert_pose_eq(pose: &SdfPose, expected: &SdfPose) {
+        assert!(
+            pose.xyz.abs_diff_eq(expected.xyz, 1e-4) && pose.rpy.abs_diff_eq(expected.rpy, 1e-4),
+            "{pose:?} != {expected:?}"
+        );
+    }
+
+    const WRITER_WORLD: &str = r#"<?xml version="1.0" ?>
+<sdf version="1.9">
+  <world name="writer_world">
+    <gravity>0 0 0</gravity>
+    <light name="sun" type="directional">
+      <pose>0 0 10 0 0 0</pose>
+      <direction>-0.5 0.1 -0.9</direction>
+    </light>
+    <model name="pendulum">
+      <pose>1 2 0 0 0 0.5</pose>
+      <link name="post">
+        <pose>0 0 1 0 0 0</pose>
+        <collision name="post">
+          <geometry><cylinder><radius>0.05</radius><length>2</length></cylinder></geometry>
+        </collision>
+      </link>
+      <link name="bob">
+        <pose>0 0.5 1.5 0.3 0 0</pose>
+        <inertial><mass>2</mass><inertia><ixx>0.1</ixx><iyy>0.2</iyy><izz>0.3</izz></inertia></inertial>
+        <collision name="ball">
+          <geometry><sphere><radius>0.1</radius></sphere></geometry>
+          <surface>
+            <friction><ode><mu>0.4</mu><mu2>0.4</mu2></ode></friction>
+            <bounce><restitution_coefficient>0.7</restitution_coefficient></bounce>
+          </surface>
+        </collision>
+        <visual name="ball">
+          <geometry><sphere><radius>0.1</radius></sphere></geometry>
+          <material><diffuse>1 0 0 1</diffuse></material>
+        </visual>
+      </link>
+      <joint name="mount" type="fixed"><parent>world</parent><child>post</child></joint>
+      <joint name="hinge" type="revolute">
+        <parent>post</parent>
+        <child>bob</child>
+        <pose>0 -0.5 0 0 0 0</pose>
+        <axis><xyz>1 0 0</xyz><limit><lower>-1</lower><upper>1</upper></limit></axis>
+      </joint>
+    </model>
+    <model name="crate">
+      <pose>3 0 0.25 0 0 0</pose>
+      <link name="body">
+        <inertial><mass>1</mass></inertial>
+        <collision name="box"><geometry><box><size>0.5 0.4 0.3</size></box></geometry></collision>
+      </link>
+    </model>
+  </world>
+</sdf>"#;
+
+    #[test]
+    fn test_write_world_round_trip() {
+        let source = parse_sdf_content(WRITER_WORLD).unwrap();
+        let mut app = sdf_test_app(source.clone());
+        app.update();
+        // Drag the crate to a new place within its model
+        let crate_body = link(&mut app, "crate_body");
+        let dragged =
+            Transform::from_xyz(-1.0, 0.25, 2.0).with_rotation(Quat::from_rotation_y(1.0));
+        *app.world_mut().get_mut::<Transform>(crate_body).unwrap() = dragged;
+        app.update();
+
+        let world = parse_sdf_content(&write_world(app.world())).unwrap();
+        assert_eq!(world.name, "writer_world");
+        assert_eq!(world.physics, source.physics);
+        assert_eq!(world.lights.len(), 1);
+        assert_eq!(world.lights[0].direction, source.lights[0].direction);
+        assert_pose_eq(&world.lights[0].pose, &source.lights[0].pose);
+
+        // Everything but the poses is written as it was read
+        assert_eq!(world.models.len(), 2);
+        for (model, source_model) in world.models.iter().zip(&source.models) {
+            assert_eq!(model.name, source_model.name);
+            assert_eq!(model.static_, source_model.static_);
+            assert_eq!(model.joints, source_model.joints);
+            assert_pose_eq(&model.pose, &source_model.pose);
+            for (link, source_link) in model.links.iter().zip(&source_model.links) {
+                assert_eq!(link.name, source_link.name);
+                assert_eq!(link.collisions, source_link.collisions);
+                assert_eq!(link.visuals, source_link.visuals);
+                assert_eq!(link.inertial, source_link.inertial);
+            }
+        }
+        let pendulum = &world.models[0];
+        assert_pose_eq(&pendulum.links[1].pose, &source.models[0].links[1].pose);
+
+        // The dragged link is where it was left, turned about Z
+        assert_pose_eq(
+            &world.models[1].links[0].pose,
+            &SdfPose {
+                xyz: Vec3::new(-1.0, -2.0, 0.25),
+                rpy: Vec3::new(0.0, 0.0, 1.0),
+            },
+        );
+    }
+
+    #[test]
+    fn test_write_world_bodies() {
+        let mut app = sdf_test_app(
+            parse_sdf_content(
+                r#"<sdf version="1.9"><world name="bodies"><gravity>0 0 0</gravity></world></sdf>"#,
+            )
+            .unwrap(),
+        );
+        let red = app
+            .world_mut()
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(Color::srgb(1.0, 0.0, 0.0));
+        let cube = app
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::new(0.05, 0.1, 0.05));
+        for x in [0.3, -0.3] {
+            app.world_mut().spawn((
+                Mesh3d(cube.clone()),
+                MeshMaterial3d(red.clone()),
+                Transform::from_xyz(x, 0.05, 0.1),
+                RigidBody::Dynamic,
+                Collider::cuboid(0.025, 0.05, 0.025),
+                ColliderMassProperties::Mass(0.2),
+                PickupBlock,
+            ));
+        }
+        let base = app
+            .world_mut()
+            .spawn((
+                ArmLink::Base,
+                Transform::from_xyz(0.0, 0.05, 0.0),
+                RigidBody::Fixed,
+                Collider::cylinder(0.05, 0.06),
+            ))
+            .id();
+        let joint = GenericJointBuilder::new(JointAxesMask::LOCKED_REVOLUTE_AXES)
+            .local_axis1(Vec3::Y)
+            .local_axis2(Vec3::Y)
+            .local_anchor1(Vec3::new(0.0, 0.05, 0.0))
+            .local_anchor2(Vec3::new(0.0, -0.1, 0.0));
+        app.world_mut().spawn((
+            ArmLink::Link1,
+            Transform::from_xyz(0.0, 0.2, 0.0),
+            RigidBody::Dynamic,
+            Collider::cylinder(0.1, 0.05),
+            ColliderMassProperties::Mass(0.25),
+            ImpulseJoint::new(base, TypedJoint::GenericJoint(joint.build())),
+        ));
+        app.update();
+        app.update();
+
+        let world = parse_sdf_content(&write_world(app.world())).unwrap();
+        let names: Vec<&str> = world
+            .models
+            .iter()
+            .map(|model| model.name.as_str())
+            .collect();
+        assert_eq!(names, ["pickup_block_1", "pickup_block_2", "ur3e"]);
+
+        // A block is a box of its collider's size, converted to Z-up
+        let block = &world.models[0];
+        assert!(!block.static_);
+        assert_pose_eq(
+            &block.pose,
+            &SdfPose {
+                xyz: Vec3::new(0.3, -0.1, 0.05),
+                rpy: Vec3::ZERO,
+            },
+        );
+        let block_link = &block.links[0];
+        assert!(matches!(
+            block_link.collisions[0].geometry,
+            SdfGeometry::Box { size } if size.abs_diff_eq(Vec3::new(0.05, 0.05, 0.1), 1e-6)
+        ));
+        assert_eq!(
+            block_link.visuals[0].geometry,
+            block_link.collisions[0].geometry
+        );
+        let material = block_link.visuals[0].material.as_ref().unwrap();
+        assert_eq!(material.diffuse, Some(Color::srgba(1.0, 0.0, 0.0, 1.0)));
+        let inertial = block_link.inertial.as_ref().unwrap();
+        assert_relative_eq!(inertial.mass, 0.2, epsilon = 1e-5);
+        // The long side is along Z, so the box is hardest to turn about X and Y
+        assert_relative_eq!(
+            inertial.izz,
+            0.2 / 12.0 * (0.05 * 0.05 * 2.0),
+            epsilon = 1e-7
+        );
+        assert_relative_eq!(
+            inertial.ixx,
+            0.2 / 12.0 * (0.05 * 0.05 + 0.1 * 0.1),
+            epsilon = 1e-7
+        );
+
+        // The arm's fixed base is fixed to the world, and its link turns about the base's Z axis
+        let arm = &world.models[2];
+        let link_names: Vec<&str> = arm.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(link_names, ["base", "link1"]);
+        assert_pose_eq(
+            &arm.links[1].pose,
+            &SdfPose {
+                xyz: Vec3::new(0.0, 0.0, 0.15),
+                rpy: Vec3::ZERO,
+            },
+        );
+        assert_eq!(arm.joints.len(), 2);
+        assert_eq!(
+            (
+                arm.joints[0].name.as_str(),
+                arm.joints[0].joint_type.as_str()
+            ),
+            ("base_world", "fixed")
+        );
+        let hinge = &arm.joints[1];
+        assert_eq!(
+            (
+                hinge.joint_type.as_str(),
+                hinge.parent.as_str(),
+                hinge.child.as_str()
+            ),
+            ("continuous", "base", "link1")
+        );
+        assert!(hinge.pose.xyz.abs_diff_eq(Vec3::new(0.0, 0.0, -0.1), 1e-6));
+        let axis = hinge.axis.as_ref().unwrap().xyz;
+        assert!(axis.abs_diff_eq(Vec3::Z, 1e-6), "{axis}");
+
+        // The written world loads with the arm jointed again
+        let mut app = sdf_test_app(world);
+        app.update();
+        let link1 = link(&mut app, "ur3e_link1");
+        assert!(app.world().get::<ImpulseJoint>(link1).is_some());
+    }
+}
+
+#[cfg(test)]
+mod urdf_tests {
+    use super::*;
+    use crate::sdf_loader::{SdfGeometry, SdfPlugin};
+    use crate::sdf_model_path::SdfModelPathPlugin;
+    use crate::urdf::{UrdfError, UrdfJointName, parse_urdf, parse_urdf_file, spawn_urdf_robot};
+    use approx::assert_relative_eq;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    const ARM_URDF: &str = r#"<?xml version="1.0"?>
+<robot name="arm">
+  <material name="blue"><color rgba="0 0 1 1"/></material>
+  <link name="world"/>
+  <joint name="world_joint" type="fixed">
+    <parent link="world"/>
+    <child link="base_link"/>
+    <origin xyz="0 0 0.1"/>
+  </joint>
+  <link name="base_link">
+    <inertial>
+      <origin xyz="0 0 0.05"/>
+      <mass value="2"/>
+      <inertia ixx="0.01" ixy="0" ixz="0" iyy="0.01" iyz="0" izz="0.02"/>
+    </inertial>
+    <visual>
+      <geometry><cylinder radius="0.1" length="0.1"/></geometry>
+      <material name="blue"/>
+    </visual>
+    <collision>
+      <geometry><cylinder radius="0.1" length="0.1"/></geometry>
+    </collision>
+  </link>
+  <joint name="shoulder" type="revolute">
+    <parent link="base_link"/>
+    <child link="upper_arm"/>
+    <origin xyz="0 0 0.1" rpy="0 0 1.5707963"/>
+    <axis xyz="0 1 0"/>
+    <limit lower="-1.5" upper="1.5" effort="50" velocity="3"/>
+    <dynamics damping="0.5"/>
+  </joint>
+  <link name="upper_arm">
+    <inertial>
+      <mass value="1"/>
+      <inertia ixx="0.01" iyy="0.01" izz="0.001"/>
+    </inertial>
+    <visual name="shell">
+      <origin xyz="0 0 0.2"/>
+      <geometry>
+        <mesh filename="package://arm_description/meshes/upper_arm.stl" scale="0.001 0.001 0.001"/>
+      </geometry>
+      <material name="grey"><color rgba="0.5 0.5 0.5 1"/></material>
+    </visual>
+    <collision>
+      <origin xyz="0 0 0.2"/>
+      <geometry><box size="0.05 0.05 0.4"/></geometry>
+    </collision>
+  </link>
+  <joint name="elbow" type="continuous">
+    <parent link="upper_arm"/>
+    <child link="forearm"/>
+    <origin xyz="0.3 0 0.4"/>
+  </joint>
+  <link name="forearm">
+    <inertial>
+      <mass value="0.5"/>
+      <inertia ixx="0.001" iyy="0.001" izz="0.001"/>
+    </inertial>
+    <collision><geometry><sphere radius="0.05"/></geometry></collision>
+  </link>
+  <transmission name="shoulder_transmission">
+    <joint name="shoulder"><hardwareInterface>EffortJointInterface</hardwareInterface></joint>
+  </transmission>
+  <gazebo reference="base_link"><material>Gazebo/Blue</material></gazebo>
+</robot>"#;
+
+    #[test]
+    fn test_parse_urdf() {
+        let robot = parse_urdf(ARM_URDF).unwrap();
+        assert_eq!(robot.name, "arm");
+        assert_eq!(robot.root_link(), "world");
+        let links: Vec<&str> = robot.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(links, ["world", "base_link", "upper_arm", "forearm"]);
+        // The <joint> of the transmission is not a joint of the robot
+        assert_eq!(robot.joints.len(), 3);
+
+        let base = &robot.links[1];
+        let inertial = base.inertial.as_ref().unwrap();
+        assert_eq!(inertial.mass, 2.0);
+        assert_eq!(inertial.izz, 0.02);
+        assert_eq!(inertial.pose.xyz, Vec3::new(0.0, 0.0, 0.05));
+        assert_eq!(base.visuals[0].name, "visual_0");
+        assert_eq!(
+            base.visuals[0].geometry,
+            SdfGeometry::Cylinder {
+                radius: 0.1,
+                length: 0.1
+            }
+        );
+        // Named materials of the robot are looked up; the <gazebo> material is ignored
+        let material = base.visuals[0].material.as_ref().unwrap();
+        assert_eq!(material.diffuse, Some(Color::srgba(0.0, 0.0, 1.0, 1.0)));
+
+        let upper_arm = &robot.links[2];
+        assert_eq!(upper_arm.visuals[0].name, "shell");
+        assert_eq!(upper_arm.visuals[0].pose.xyz, Vec3::new(0.0, 0.0, 0.2));
+        assert_eq!(
+            upper_arm.visuals[0].geometry,
+            SdfGeometry::Mesh {
+                uri: "package://arm_description/meshes/upper_arm.stl".to_string(),
+                scale: Some(Vec3::splat(0.001)),
+            }
+        );
+        assert_eq!(
+            upper_arm.collisions[0].geometry,
+            SdfGeometry::Box {
+                size: Vec3::new(0.05, 0.05, 0.4)
+            }
+        );
+
+        let shoulder = &robot.joints[1];
+        assert_eq!(shoulder.name, "shoulder");
+        assert_eq!(shoulder.joint_type, "revolute");
+        assert_eq!(
+            (shoulder.parent.as_str(), shoulder.child.as_str()),
+            ("base_link", "upper_arm")
+        );
+        assert_eq!(shoulder.axis, Vec3::Y);
+        assert_relative_eq!(
+            shoulder.origin.rpy.z,
+            std::f32::consts::FRAC_PI_2,
+            epsilon = 1e-6
+        );
+        let limit = shoulder.limit.as_ref().unwrap();
+        assert_eq!((limit.lower, limit.upper), (-1.5, 1.5));
+        assert_eq!((limit.effort, limit.velocity), (50.0, 3.0));
+        assert_eq!(shoulder.dynamics.as_ref().unwrap().damping, 0.5);
+
+        // Axes default to X
+        let elbow = &robot.joints[2];
+        assert_eq!(elbow.axis, Vec3::X);
+        assert!(elbow.limit.is_none());
+    }
+
+    #[test]
+    fn test_urdf_to_sdf_model() {
+        let model = parse_urdf(ARM_URDF).unwrap().to_sdf_model();
+        assert_eq!(model.name, "arm");
+        assert!(!model.static_);
+
+        // The world link is the world itself
+        let links: Vec<&str> = model.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(links, ["base_link", "upper_arm", "forearm"]);
+        assert_eq!(model.joints[0].parent, "world");
+        assert_eq!(model.joints[0].joint_type, "fixed");
+        assert!(model.joints[0].axis.is_none());
+
+        // Joint origins chain from the root link
+        let pose = |index: usize| &model.links[index].pose;
+        assert!(pose(0).xyz.abs_diff_eq(Vec3::new(0.0, 0.0, 0.1), 1e-6));
+        assert!(pose(1).xyz.abs_diff_eq(Vec3::new(0.0, 0.0, 0.2), 1e-6));
+        assert_relative_eq!(pose(1).rpy.z, std::f32::consts::FRAC_PI_2, epsilon = 1e-5);
+        // The elbow offset is turned by the shoulder's yaw
+        assert!(
+            pose(2).xyz.abs_diff_eq(Vec3::new(0.0, 0.3, 0.6), 1e-5),
+            "{}",
+            pose(2).xyz
+        );
+        assert_relative_eq!(pose(2).rpy.z, std::f32::consts::FRAC_PI_2, epsilon = 1e-5);
+
+        // Joint frames are the child link frames
+        let shoulder = &model.joints[1];
+        assert_eq!(shoulder.pose, Default::default());
+        let axis = shoulder.axis.as_ref().unwrap();
+        assert_eq!(axis.xyz, Vec3::Y);
+        assert_eq!(axis.limit.as_ref().unwrap().upper, 1.5);
+        assert_eq!(axis.dynamics.as_ref().unwrap().damping, 0.5);
+        assert_eq!(model.joints[2].joint_type, "continuous");
+    }
+
+    #[test]
+    fn test_urdf_errors() {
+        let unknown_link = r#"<robot name="r">
+  <link name="a"/>
+  <joint name="j" type="fixed">
+    <parent link="a"/>
+    <child link="b"/>
+  </joint>
+</robot>"#;
+        let Err(UrdfError::InvalidTree { location, message }) =
+            parse_urdf_file(unknown_link, "r.urdf")
+        else {
+            panic!("expected an invalid tree");
+        };
+        assert_eq!(location.to_string(), "r.urdf:3:3 (robot[r]/joint[j])");
+        assert!(message.contains("unknown link \"b\""), "{message}");
+
+        let two_roots = r#"<robot name="r"><link name="a"/><link name="b"/></robot>"#;
+        let error = parse_urdf(two_roots).unwrap_err();
+        assert!(matches!(error, UrdfError::InvalidTree { .. }));
+        assert!(
+            error.to_string().contains("more than one root link: a, b"),
+            "{error}"
+        );
+
+        let loop_ = r#"<robot name="r">
+  <link name="root"/><link name="a"/><link name="b"/>
+  <joint name="ab" type="fixed"><parent link="a"/><child link="b"/></joint>
+  <joint name="ba" type="fixed"><parent link="b"/><child link="a"/></joint>
+</robot>"#;
+        let error = parse_urdf(loop_).unwrap_err();
+        assert!(error.to_string().contains("link a is in a loop"), "{error}");
+
+        let missing_attribute = r#"<robot name="r"><link name="a">
+  <collision><geometry><cylinder radius="0.1"/></geometry></collision>
+</link></robot>"#;
+        let Err(UrdfError::InvalidValue { location, message }) = parse_urdf(missing_attribute)
+        else {
+            panic!("expected an invalid value");
+        };
+        assert_eq!(location.line, 2);
+        assert_eq!(message, "missing attribute length");
+
+        let bad_value = r#"<robot name="r"><link name="a"><inertial><mass value="heavy"/></inertial></link></robot>"#;
+        let error = parse_urdf(bad_value).unwrap_err();
+        assert!(
+            error.to_string().contains("value: expected a number"),
+            "{error}"
+        );
+
+        let bad_type = r#"<robot name="r"><link name="a"/><link name="b"/>
+<joint name="j" type="hinge"><parent link="a"/><child link="b"/></joint></robot>"#;
+        assert!(
+            parse_urdf(bad_type)
+                .unwrap_err()
+                .to_string()
+                .contains("unknown joint type")
+        );
+    }
+
+    #[test]
+    fn test_spawn_urdf_robot() {
+        let robot = parse_urdf(ARM_URDF).unwrap();
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            SdfModelPathPlugin::default(),
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::render::mesh::MeshPlugin,
+            PhysicsPlugin,
+            SdfPlugin,
+        ))
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )))
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(10)))
+        .add_systems(
+            Startup,
+            move |mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  mut materials: ResMut<Assets<StandardMaterial>>,
+                  asset_server: Res<AssetServer>| {
+                spawn_urdf_robot(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &asset_server,
+                    &robot,
+                    Transform::from_xyz(1.0, 0.0, 0.0),
+                );
+            },
+        );
+        for _ in 0..50 {
+            app.update();
+        }
+
+        // Every joint's child link carries the Rapier joint and the joint's name
+        let mut joints: Vec<(String, String)> = app
+            .world_mut()
+            .query_filtered::<(&UrdfJointName, &Name), With<ImpulseJoint>>()
+            .iter(app.world())
+            .map(|(joint, name)| (joint.0.clone(), name.to_string()))
+            .collect();
+        joints.sort();
+        assert_eq!(
+            joints,
+            [
+                ("elbow".to_string(), "arm_forearm".to_string()),
+                ("shoulder".to_string(), "arm_upper_arm".to_string()),
+                ("world_joint".to_string(), "arm_base_link".to_string()),
+            ]
+        );
+
+        // The base is fixed to the world where the robot was spawned, while the arm hangs off it
+        let (base, _) = app
+            .world_mut()
+            .query::<(&GlobalTransform, &Name)>()
+            .iter(app.world())
+            .find(|(_, name)| name.as_str() == "arm_base_link")
+            .unwrap();
+        assert!(
+            base.translation()
+                .abs_diff_eq(Vec3::new(1.0, 0.1, 0.0), 1e-3),
+            "{}",
+            base.translation()
+        );
+    }
+}
+
+#[cfg(test)]
+mod xacro_tests {
+    use crate::urdf::parse_urdf;
+    use crate::xacro::{XacroError, expand_xacro, expand_xacro_file};
+    use bevy::prelude::*;
+
+    fn expand(content: &str) -> String {
+        expand_xacro(content, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_xacro_properties_and_expressions() {
+        let urdf = expand(
+            r#"<robot name="r" xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:property name="radius" value="0.05"/>
+  <xacro:property name="length" value="${radius * 4}"/>
+  <xacro:property name="prefix" value="left"/>
+  <link name="${prefix + '_wheel'}">
+    <a value="${length}" half="${length / 2}" floor="${7 // 2}" power="${2 ** 3}"/>
+    <b value="${-pi / 2}" degrees="${degrees(pi)}" trig="${sqrt(atan2(1, 1) * 4 / pi)}"/>
+    <c value="${'big' if length > 0.1 else 'small'}" text="r=${radius}, $${radius}"/>
+    <d value="${1 == 1 and not (2 &lt; 1)}"/>
+  </link>
+</robot>"#,
+        );
+        assert_eq!(
+            urdf,
+            r#"<?xml version="1.0"?>
+<robot name="r">
+  <link name="left_wheel">
+    <a value="0.2" half="0.1" floor="3" power="8"/>
+    <b value="-1.5707963267948966" degrees="180" trig="1"/>
+    <c value="big" text="r=0.05, ${radius}"/>
+    <d value="True"/>
+  </link>
+</robot>
+"#
+        );
+    }
+
+    const MACRO_XACRO: &str = r#"<robot name="r" xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:property name="mass" value="2"/>
+  <xacro:macro name="box_link" params="name size:=0.1 mass:=^ *origin **extra">
+    <link name="${name}">
+      <visual>
+        <xacro:insert_block name="origin"/>
+        <geometry><box size="${size} ${size} ${size}"/></geometry>
+      </visual>
+      <inertial><mass value="${mass}"/></inertial>
+      <xacro:insert_block name="extra"/>
+    </link>
+  </xacro:macro>
+  <xacro:macro name="pair" params="prefix">
+    <xacro:box_link name="${prefix}_a" size="0.2">
+      <origin xyz="0 0 ${0.2 / 2}"/>
+      <extra><collision><geometry><sphere radius="0.1"/></geometry></collision></extra>
+    </xacro:box_link>
+    <xacro:box_link name="${prefix}_b" mass="3">
+      <origin xyz="0 0 0"/>
+      <extra/>
+    </xacro:box_link>
+    <joint name="${prefix}_joint" type="fixed">
+      <parent link="${prefix}_a"/>
+      <child link="${prefix}_b"/>
+    </joint>
+  </xacro:macro>
+  <xacro:pair prefix="arm"/>
+</robot>"#;
+
+    #[test]
+    fn test_xacro_macros() {
+        let robot = parse_urdf(&expand(MACRO_XACRO)).unwrap();
+        let links: Vec<&str> = robot.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(links, ["arm_a", "arm_b"]);
+        assert_eq!(robot.joints[0].name, "arm_joint");
+
+        let (a, b) = (&robot.links[0], &robot.links[1]);
+        assert_eq!(a.visuals[0].pose.xyz, Vec3::new(0.0, 0.0, 0.1));
+        assert_eq!(
+            a.visuals[0].geometry,
+            crate::sdf_loader::SdfGeometry::Box {
+                size: Vec3::splat(0.2)
+            }
+        );
+        // `mass:=^` takes the mass property of the calling scope, unless given
+        assert_eq!(a.inertial.as_ref().unwrap().mass, 2.0);
+        assert_eq!(b.inertial.as_ref().unwrap().mass, 3.0);
+        // `**extra` inserts the children of the block
+        assert_eq!(a.collisions.len(), 1);
+        assert!(b.collisions.is_empty());
+        assert_eq!(
+            b.visuals[0].geometry,
+            crate::sdf_loader::SdfGeometry::Box {
+                size: Vec3::splat(0.1)
+            }
+        );
+    }
+
+    const ARGS_XACRO: &str = r#"<robot name="$(arg name)" xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:arg name="name" default="bot"/>
+  <xacro:arg name="with_lidar" default="false"/>
+  <link name="base"/>
+  <xacro:if value="$(arg with_lidar)">
+    <link name="lidar"/>
+  </xacro:if>
+  <xacro:unless value="${'$(arg name)' == 'bot'}">
+    <link name="$(arg name)_marker"/>
+  </xacro:unless>
+</robot>"#;
+
+    #[test]
+    fn test_xacro_args_and_conditionals() {
+        assert_eq!(
+            expand(ARGS_XACRO),
+            "<?xml version=\"1.0\"?>\n<robot name=\"bot\">\n  <link name=\"base\"/>\n</robot>\n"
+        );
+
+        let args = ["with_lidar:=true".to_string(), "name:=rover".to_string()];
+        let urdf = expand_xacro(ARGS_XACRO, &args).unwrap();
+        assert!(urdf.contains(r#"<robot name="rover">"#), "{urdf}");
+        assert!(urdf.contains(r#"<link name="lidar"/>"#), "{urdf}");
+        assert!(urdf.contains(r#"<link name="rover_marker"/>"#), "{urdf}");
+    }
+
+    #[test]
+    fn test_xacro_include() {
+        let dir = std::env::temp_dir().join(format!("xacro_include_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("parts")).unwrap();
+        std::fs::write(
+            dir.join("parts/wheel.xacro"),
+            r#"<robot xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:include filename="constants.xacro"/>
+  <xacro:macro name="wheel" params="side">
+    <link name="${side}_wheel">
+      <collision><geometry><cylinder radius="${wheel_radius}" length="0.02"/></geometry></collision>
+    </link>
+  </xacro:macro>
+</robot>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("parts/constants.xacro"),
+            r#"<robot xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:property name="wheel_radius" value="0.036"/>
+</robot>"#,
+        )
+        .unwrap();
+        let robot_file = dir.join("robot.urdf.xacro");
+        std::fs::write(
+            &robot_file,
+            r#"<robot name="rover" xmlns:xacro="http://www.ros.org/wiki/xacro">
+  <xacro:include filename="parts/wheel.xacro"/>
+  <link name="base"/>
+  <xacro:wheel side="left"/>
+  <joint name="left_wheel_joint" type="continuous">
+    <parent link="base"/>
+    <child link="left_wheel"/>
+    <origin xyz="0 ${wheel_radius * 3} 0"/>
+  </joint>
+</robot>"#,
+        )
+        .unwrap();
+
+        // Includes are relative to the file that includes them
+        let robot = parse_urdf(&expand_xacro_file(&robot_file, &[]).unwrap()).unwrap();
+        assert_eq!(robot.links[1].name, "left_wheel");
+        assert_eq!(
+            robot.links[1].collisions[0].geometry,
+            crate::sdf_loader::SdfGeometry::Cylinder {
+                radius: 0.036,
+                length: 0.02
+            }
+        );
+        assert!(
+            robot.joints[0]
+                .origin
+                .xyz
+                .abs_diff_eq(Vec3::new(0.0, 0.108, 0.0), 1e-6)
+        );
+
+        let missing = dir.join("missing.xacro");
+        std::fs::write(
+            &missing,
+            r#"<robot xmlns:xacro="http://www.ros.org/wiki/xacro"><xacro:include filename="nope.xacro"/></robot>"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            expand_xacro_file(&missing, &[]),
+            Err(XacroError::Io { file, .. }) if file.ends_with("nope.xacro")
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_xacro_errors() {
+        let error = |content: &str| expand_xacro(content, &[]).unwrap_err().to_string();
+        assert_eq!(
+            error(r#"<robot><link name="${missing}"/></robot>"#),
+            "Invalid xacro: in ${missing}: undefined property missing"
+        );
+        assert!(
+            error(r#"<robot xmlns:xacro="x"><xacro:macro name="m" params="a"/><xacro:m/></robot>"#)
+                .contains("macro m is missing parameter a")
+        );
+        assert!(
+            error(
+                r#"<robot xmlns:xacro="x"><xacro:macro name="m" params=""/><xacro:m b="1"/></robot>"#
+            )
+            .contains("macro m has no parameter b")
+        );
+        assert!(error(r#"<robot xmlns:xacro="x"><xacro:nope/></robot>"#).contains("nope"));
+        assert!(
+            error(r#"<robot xmlns:xacro="x"><xacro:if value="maybe"/></robot>"#)
+                .contains("not a boolean")
+        );
+        assert!(error(r#"<robot><a b="${1 +}"/></robot>"#).contains("unexpected end"));
+        assert!(
+            error(r#"<robot xmlns:xacro="x"><xacro:macro name="m" params=""><xacro:m/></xacro:macro><xacro:m/></robot>"#)
+                .contains("nest too deeply")
+        );
+        assert!(matches!(
+            expand_xacro("<robot/>", &["name=value".to_string()]),
+            Err(XacroError::Invalid(message)) if message.contains("name:=value")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod robotic_arm_tests {
+    use super::*;
+    use crate::robotic_arm::{
+        ARM_JOINT_COUNT, ArmJointGains, ArmLink, JointStates, JointTargets, PickupBlock,
+        UR3E_JOINTS, drive_arm_joints, joint_state_system, keyboard_input, setup,
+    };
+    use approx::assert_relative_eq;
+    use bevy::ecs::event::Events;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy_rapier3d::plugin::PhysicsSet;
+    use bevy_rapier3d::rapier::na as nalgebra;
+    use std::time::Duration;
+
+    /// Axis of each joint in its parent link's frame, as the arm is built
+    const JOINT_AXES: [Vec3; ARM_JOINT_COUNT] =
+        [Vec3::Y, Vec3::Z, Vec3::Z, Vec3::Z, Vec3::Y, Vec3::Z];
+
+    pub(super) fn arm_test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::render::mesh::MeshPlugin,
+            PhysicsPlugin,
+        ))
+        .init_asset::<StandardMaterial>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<JointTargets>()
+        .init_resource::<ArmJointGains>()
+        .init_resource::<JointStates>()
+        .add_event::<JointStates>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, (keyboard_input, drive_arm_joints).chain())
+        .add_systems(FixedUpdate, joint_state_system.after(PhysicsSet::Writeback))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )))
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(10)));
+        app
+    }
+
+    /// Rotation of each joint's child link relative to its parent link, from Link1 to Link6
+    fn joint_rotations(app: &mut App) -> Vec<Quat> {
+        // The base sorts first, then the links in joint order
+        let mut links = app
+            .world_mut()
+            .query::<(&ArmLink, &Transform)>()
+            .iter(app.world())
+            .filter(|(link, _)| **link != ArmLink::GripperBase)
+            .map(|(link, transform)| {
+                (
+                    link.joint_index().map_or(0, |index| index + 1),
+                    transform.rotation,
+                )
+            })
+            .collect::<Vec<_>>();
+        links.sort_by_key(|(order, _)| *order);
+        links
+            .windows(2)
+            .map(|pair| pair[0].1.inverse() * pair[1].1)
+            .collect()
+    }
+
+    #[test]
+    fn test_joint_targets_clamped_to_datasheet_ranges() {
+        let mut targets = JointTargets::default();
+        targets.set(0, 10.0);
+        targets.set(2, -10.0);
+        targets.set(5, 10.0);
+        assert_eq!(targets.positions[0], 2.0 * PI);
+        assert_eq!(targets.positions[2], -2.0 * PI);
+        assert_eq!(targets.positions[5], 10.0);
+        assert_eq!(UR3E_JOINTS[5].name, "wrist_3_joint");
+
+        assert_eq!(ArmLink::Link1.joint_index(), Some(0));
+        assert_eq!(ArmLink::Link6.joint_index(), Some(5));
+        assert_eq!(ArmLink::Base.joint_index(), None);
+        assert_eq!(ArmLink::GripperBase.joint_index(), None);
+    }
+
+    #[test]
+    fn test_extra_solver_iterations_only_for_the_arm() {
+        let mut app = arm_test_app();
+        app.update();
+
+        let simulation = app
+            .world_mut()
+            .query::<&RapierContextSimulation>()
+            .single(app.world())
+            .unwrap();
+        assert_eq!(
+            simulation
+                .integration_parameters
+                .num_solver_iterations
+                .get(),
+            4
+        );
+        let mut links = app
+            .world_mut()
+            .query_filtered::<Option<&AdditionalSolverIterations>, With<ImpulseJoint>>();
+        let iterations: Vec<_> = links
+            .iter(app.world())
+            .map(|extra| extra.map(|extra| extra.0))
+            .collect();
+        assert_eq!(iterations, [Some(12); ARM_JOINT_COUNT]);
+        // The blocks keep the world's iterations
+        let mut blocks = app
+            .world_mut()
+            .query_filtered::<(), (With<PickupBlock>, With<AdditionalSolverIterations>)>();
+        assert_eq!(blocks.iter(app.world()).count(), 0);
+    }
+
+    #[test]
+    fn test_keyboard_selects_and_turns_joint() {
+        let mut app = arm_test_app();
+        app.update();
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::Digit3);
+        keys.press(KeyCode::ArrowUp);
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let targets = app.world().resource::<JointTargets>();
+        assert_eq!(targets.active, 2);
+        assert!(targets.positions[2] > 0.0);
+        for joint in [0, 1, 3, 4, 5] {
+            assert_eq!(targets.positions[joint], 0.0);
+        }
+    }
+
+    #[test]
+    fn test_each_joint_turns_about_its_axis() {
+        let mut app = arm_test_app();
+        app.update();
+        let initial = joint_rotations(&mut app);
+
+        let targets = [0.5, 0.3, -0.4, 0.4, -0.5, 1.0];
+        let mut joint_targets = app.world_mut().resource_mut::<JointTargets>();
+        for (joint, target) in targets.iter().enumerate() {
+            joint_targets.set(joint, *target);
+        }
+        for _ in 0..200 {
+            app.update();
+        }
+
+        let rotations = joint_rotations(&mut app);
+        for joint in 0..ARM_JOINT_COUNT {
+            let turn = rotations[joint] * initial[joint].inverse();
+            let expected = Quat::from_axis_angle(JOINT_AXES[joint], targets[joint]);
+            assert!(
+                turn.angle_between(expected) < 0.05,
+                "joint {} turned {:?} rather than {} rad about {}",
+                joint + 1,
+                turn.to_axis_angle(),
+                targets[joint],
+                JOINT_AXES[joint]
+            );
+        }
+
+        // The measured joint states agree with the link transforms
+        let joint_states = app.world().resource::<JointStates>();
+        for joint in 0..ARM_JOINT_COUNT {
+            let turn = rotations[joint] * initial[joint].inverse();
+            let (axis, angle) = turn.to_axis_angle();
+            let measured = angle * axis.dot(JOINT_AXES[joint]).signum();
+            assert!(
+                (joint_states.positions[joint] - measured).abs() < 1e-3,
+                "joint {} measured at {} rad but turned {} rad",
+                joint + 1,
+                joint_states.positions[joint],
+                measured
+            );
+            assert!(joint_states.velocities[joint].abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_joint_states_track_a_move() {
+        let mut app = arm_test_app();
+        app.update();
+        app.world_mut().resource_mut::<JointTargets>().set(2, 0.5);
+
+        let mut reached = None;
+        let mut peak_velocity = 0.0f32;
+        let mut peak_effort = 0.0f32;
+        while reached.is_none() && app.world().resource::<JointStates>().stamp < 2.0 {
+            app.update();
+            let joint_states = app.world().resource::<JointStates>();
+            let events = app.world().resource::<Events<JointStates>>();
+            assert_eq!(
+                events.iter_current_update_events().last(),
+                Some(joint_states)
+            );
+
+            peak_velocity = peak_velocity.max(joint_states.velocities[2]);
+            peak_effort = peak_effort.max(joint_states.efforts[2]);
+            if (joint_states.positions[2] - 0.5).abs() < 0.02 {
+                reached = Some(joint_states.stamp);
+            }
+        }
+
+        assert!(
+            reached.is_some(),
+            "joint 3 did not reach 0.5 rad within 2 s"
+        );
+        assert!(peak_velocity > 0.1);
+        // The elbow motor drove the joint towards its target
+        assert!(peak_effort > 0.1);
+
+        // Once the arm settles, the shoulder motor holds the links it carries up against gravity
+        run_arm(&mut app, 100);
+        let gravity_torque = shoulder_gravity_torque(&mut app);
+        let effort = app.world().resource::<JointStates>().efforts[1];
+        assert!(gravity_torque.abs() > 0.1);
+        assert_relative_eq!(effort, -gravity_torque, max_relative = 0.02);
+    }
+
+    fn run_arm(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    /// Torque of gravity about the shoulder lift joint on the links beyond it
+    fn shoulder_gravity_torque(app: &mut App) -> f32 {
+        let mut shoulder_link = None;
+        let mut carried = Vec::new();
+        for (entity, link) in app
+            .world_mut()
+            .query::<(Entity, &ArmLink)>()
+            .iter(app.world())
+        {
+            match link {
+                ArmLink::Base | ArmLink::Link1 => {}
+                ArmLink::Link2 => {
+                    shoulder_link = Some(entity);
+                    carried.push(entity);
+                }
+                _ => carried.push(entity),
+            }
+        }
+        let (body_set, joints, config) = app
+            .world_mut()
+            .query::<(
+                &RapierRigidBodySet,
+                &RapierContextJoints,
+                &RapierConfiguration,
+            )>()
+            .single(app.world())
+            .unwrap();
+        let body = |entity: &Entity| {
+            body_set
+                .entity2body()
+                .get(entity)
+                .and_then(|handle| body_set.bodies.get(*handle))
+        };
+
+        let joint = joints
+            .entity2impulse_joint()
+            .get(&shoulder_link.unwrap())
+            .and_then(|handle| joints.impulse_joints.get(*handle))
+            .unwrap();
+        let frame = body_set.bodies[joint.body1].position() * joint.data.local_frame1;
+        let anchor = Vec3::from(frame.translation.vector);
+        let axis = Vec3::from(frame.rotation * nalgebra::Vector3::x());
+
+        carried
+            .iter()
+            .filter_map(body)
+            .map(|body| {
+                let center_of_mass = Vec3::from(body.center_of_mass().coords);
+                (center_of_mass - anchor)
+                    .cross(body.mass() * config.gravity)
+                    .dot(axis)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod kinematics_tests {
+    use super::robotic_arm_tests::arm_test_app;
+    use super::*;
+    use crate::kinematics::{forward_kinematics, link_frames};
+    use crate::robotic_arm::{ARM_JOINT_COUNT, ArmLink, JointStates, JointTargets, SimpleGripper};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_flange_pose_upright_and_stretched_out() {
+        // As spawned, the arm stands upright with the flange facing -z
+        let flange = forward_kinematics([0.0; ARM_JOINT_COUNT]);
+        assert!(
+            flange
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 0.69395, -0.22315), 1e-5)
+        );
+        assert!((flange.rotation * Vec3::Z).abs_diff_eq(Vec3::NEG_Z, 1e-5));
+
+        // With every DH angle at zero the arm reaches out horizontally
+        let frames = link_frames([0.0, -FRAC_PI_2, 0.0, -FRAC_PI_2, 0.0, 0.0]);
+        assert_eq!(frames[0].translation, Vec3::ZERO);
+        assert!(
+            frames[1]
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 0.15185, 0.0), 1e-5)
+        );
+        assert!(
+            frames[3]
+                .translation
+                .abs_diff_eq(Vec3::new(0.45675, 0.15185, 0.0), 1e-5)
+        );
+        assert!(
+            frames[6]
+                .translation
+                .abs_diff_eq(Vec3::new(0.45675, 0.0665, -0.22315), 1e-5)
+        );
+        assert!((frames[6].rotation * Vec3::Z).abs_diff_eq(Vec3::NEG_Z, 1e-5));
+    }
+
+    #[test]
+    fn test_frames_match_simulated_arm() {
+        let mut app = arm_test_app();
+        app.update();
+
+        let mut flange_offsets = Vec::new();
+        for targets in [[0.0; ARM_JOINT_COUNT], [0.5, 0.3, -0.4, 0.4, -0.5, 1.0]] {
+            let mut joint_targets = app.world_mut().resource_mut::<JointTargets>();
+            for (joint, target) in targets.iter().enumerate() {
+                joint_targets.set(joint, *target);
+            }
+            for _ in 0..150 {
+                app.update();
+            }
+            let joint_states = app.world().resource::<JointStates>();
+            let frames = link_frames(joint_states.positions);
+            assert_eq!(joint_states.flange_pose, frames[ARM_JOINT_COUNT]);
+
+            // Each joint turns about the z axis of the DH frame before it
+            let joints = app
+                .world_mut()
+                .query::<(&ArmLink, &ImpulseJoint)>()
+                .iter(app.world())
+                .filter_map(|(link, joint)| {
+                    let data = joint.data.as_ref();
+                    Some((
+                        link.joint_index()?,
+                        joint.parent,
+                        data.local_anchor1(),
+                        data.local_axis1(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(joints.len(), ARM_JOINT_COUNT);
+            for (index, parent, anchor, axis) in joints {
+                let parent = app.world().get::<GlobalTransform>(parent).unwrap();
+                let anchor = parent.transform_point(anchor);
+                let axis = parent.rotation() * axis;
+                let frame = frames[index];
+                assert!(
+                    (frame.translation - anchor).cross(axis).length() < 2e-3,
+                    "joint {} axis through {anchor} misses DH frame {index} at {}",
+                    index + 1,
+                    frame.translation
+                );
+                assert!((frame.rotation * Vec3::Z).dot(axis).abs() > 0.9999);
+            }
+
+            // The flange is the centre of Link6's end face away from the joint with Link5
+            let (link6, collider, joint_anchor) = app
+                .world_mut()
+                .query::<(&GlobalTransform, &Collider, &ImpulseJoint, &ArmLink)>()
+                .iter(app.world())
+                .find(|(.., link)| **link == ArmLink::Link6)
+                .map(|(transform, collider, joint, _)| {
+                    (
+                        *transform,
+                        collider.clone(),
+                        joint.data.as_ref().local_anchor2(),
+                    )
+                })
+                .unwrap();
+            let half_height = collider.as_cylinder().unwrap().half_height();
+            let flange = link6.transform_point(Vec3::Y * -joint_anchor.y.signum() * half_height);
+            assert!(
+                flange.distance(frames[6].translation) < 2e-3,
+                "Link6 ends at {flange} but the DH flange is at {}",
+                frames[6].translation
+            );
+            flange_offsets.push(link6.rotation().inverse() * frames[6].rotation);
+
+            // The gripper sits on the flange
+            let gripper = *app
+                .world_mut()
+                .query_filtered::<&GlobalTransform, With<SimpleGripper>>()
+                .single(app.world())
+                .unwrap();
+            assert!(
+                gripper.translation().distance(frames[6].translation) < 2e-3,
+                "gripper at {} but flange at {}",
+                gripper.translation(),
+                frames[6].translation
+            );
+        }
+        assert!(flange_offsets[0].angle_between(flange_offsets[1]) < 0.01);
+    }
+}
@@ -10,10 +10,16 @@ use bevy::prelude::*;
 use bevy::scene::Scene;
 use bevy::transform::components::Transform;
 use bevy_rapier3d::{
-    dynamics::{ExternalImpulse, ImpulseJoint, RevoluteJoint, RigidBody, Sleeping, Velocity},
-    geometry::{Collider, ColliderMassProperties, CollisionGroups},
+    dynamics::{
+        CoefficientCombineRule, GenericJointBuilder, ImpulseJoint, JointAxesMask, RigidBody,
+        Sleeping, TypedJoint, Velocity,
+    },
+    geometry::{Collider, ColliderMassProperties, CollisionGroups, Friction},
 };
 
+use crate::diff_drive::DiffDriveController;
+use crate::odometry::Odometry;
+
 const CHASSIS_RADIUS: f32 = 0.175;
 const CHASSIS_HEIGHT: f32 = 0.340;
 const CHASSIS_HEIGHT_OFFSET: f32 = 0.009;
@@ -23,6 +29,10 @@ const WHEEL_WIDTH: f32 = 0.018;
 const WHEEL_OFFSET_X: f32 = 0.0;
 const WHEEL_OFFSET_Z: f32 = 0.1185;
 const WHEEL_MASS: f32 = 0.1;
+const TRACK_WIDTH: f32 = 2.0 * WHEEL_OFFSET_Z;
+const CASTER_RADIUS: f32 = 0.012;
+const CASTER_OFFSET_X: f32 = 0.13;
+const CASTER_CLEARANCE: f32 = 0.002; // Keeps the weight on the driven wheels
 
 #[derive(Component)]
 pub enum Wheel {
@@ -62,12 +72,24 @@ struct WheelPhysicsBundle {
     collider_mass_properties: ColliderMassProperties,
     joint: ImpulseJoint,
     sleeping: Sleeping,
+    velocity: Velocity,
 }
 
 impl WheelPhysicsBundle {
-    fn new(chassis: Entity, axis: Vec3, _anchor1: Vec3, _anchor2: Vec3) -> WheelPhysicsBundle {
-        // Create a RevoluteJoint with the axis and configure anchors via builder pattern
-        let revolute_joint = RevoluteJoint::new(axis);
+    /// `chassis_axis` and `wheel_axis` are the axle expressed in the chassis and wheel frames;
+    /// both point to the robot's left so that a positive motor velocity drives forward.
+    fn new(
+        chassis: Entity,
+        chassis_axis: Vec3,
+        wheel_axis: Vec3,
+        anchor1: Vec3,
+        anchor2: Vec3,
+    ) -> WheelPhysicsBundle {
+        let wheel_joint = GenericJointBuilder::new(JointAxesMask::LOCKED_REVOLUTE_AXES)
+            .local_axis1(chassis_axis)
+            .local_axis2(wheel_axis)
+            .local_anchor1(anchor1)
+            .local_anchor2(anchor2);
 
         WheelPhysicsBundle {
             rigid_body: RigidBody::Dynamic,
@@ -77,8 +99,9 @@ impl WheelPhysicsBundle {
                 crate::STATIC_GROUP,
             ),
             collider_mass_properties: ColliderMassProperties::Mass(WHEEL_MASS),
-            joint: ImpulseJoint::new(chassis, revolute_joint),
+            joint: ImpulseJoint::new(chassis, TypedJoint::GenericJoint(wheel_joint.build())),
             sleeping: Default::default(),
+            velocity: Velocity::default(), // Read back for wheel odometry
         }
     }
 }
@@ -104,7 +127,8 @@ pub fn spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, transform
                     ViewVisibility::default(),
                 ))
                 .insert(ChassisPhysicsBundle::default())
-                .insert(ExternalImpulse::default()) // For applying movement forces
+                .insert(DiffDriveController::new(WHEEL_RADIUS, TRACK_WIDTH))
+                .insert(Odometry::new(WHEEL_RADIUS, TRACK_WIDTH))
                 .insert(crate::RobotChassis) // Marker component for robot control
                 .insert(SceneRoot(
                     asset_server.load::<Scene>("robots/turtlebot4.glb#Scene0"),
@@ -120,6 +144,37 @@ pub fn spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, transform
                     Visibility::default(),
                 ))
                 .insert(ChildOf(chassis));
+
+            /* spawn the IMU inside the base */
+            commands
+                .spawn((
+                    crate::imu::ImuSensor::default(),
+                    Transform::default(), // At the chassis center
+                    GlobalTransform::default(),
+                ))
+                .insert(ChildOf(chassis));
+
+            /* spawn frictionless casters front and back so the chassis rests level on its wheels */
+            for caster_x in [CASTER_OFFSET_X, -CASTER_OFFSET_X] {
+                commands
+                    .spawn((
+                        Collider::ball(CASTER_RADIUS),
+                        CollisionGroups::new(crate::CHASSIS_GROUP, crate::STATIC_GROUP),
+                        Friction {
+                            coefficient: 0.0,
+                            combine_rule: CoefficientCombineRule::Min,
+                        },
+                        Transform::from_xyz(
+                            caster_x,
+                            -0.5 * CHASSIS_HEIGHT - CHASSIS_HEIGHT_OFFSET
+                                + CASTER_CLEARANCE
+                                + CASTER_RADIUS,
+                            0.0,
+                        ),
+                    ))
+                    .insert(ChildOf(chassis));
+            }
+
             /* spawn the left wheel */
             let left_wheel_transform = *transform
                 * Transform::from_xyz(WHEEL_OFFSET_X, WHEEL_RADIUS, -WHEEL_OFFSET_Z)
@@ -140,6 +195,7 @@ pub fn spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, transform
                 ))
                 .insert(WheelPhysicsBundle::new(
                     chassis,
+                    Vec3::NEG_Z,
                     Vec3::Y,
                     left_wheel_anchor1,
                     left_wheel_anchor2,
@@ -164,7 +220,8 @@ pub fn spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, transform
                 ))
                 .insert(WheelPhysicsBundle::new(
                     chassis,
-                    Vec3::Y,
+                    Vec3::NEG_Z,
+                    Vec3::NEG_Y,
                     right_wheel_anchor1,
                     right_wheel_anchor2,
                 ));
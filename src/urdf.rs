@@ -0,0 +1,766 @@
+use bevy::prelude::*;
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::sdf_loader::{
+    SdfAxis, SdfCollision, SdfGeometry, SdfInertial, SdfJoint, SdfJointDynamics, SdfJointLimit,
+    SdfLink, SdfLocation, SdfMaterial, SdfModel, SdfPose, SdfVisual, SdfWorld, compose_poses,
+    get_attribute, locate, parse_color, parse_number, parse_vec3, spawn_sdf_model,
+};
+
+/// A robot described in URDF: a tree of links connected by joints
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrdfRobot {
+    pub name: String,
+    pub links: Vec<UrdfLink>,
+    pub joints: Vec<UrdfJoint>,
+}
+
+/// URDF link; its frame is the frame of the joint it is the child of
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrdfLink {
+    pub name: String,
+    pub visuals: Vec<SdfVisual>,
+    pub collisions: Vec<SdfCollision>,
+    pub inertial: Option<SdfInertial>,
+}
+
+/// URDF joint
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrdfJoint {
+    pub name: String,
+    pub joint_type: String, // revolute, continuous, prismatic, fixed, floating or planar
+    pub parent: String,
+    pub child: String,
+    pub origin: SdfPose, // joint and child link frame relative to the parent link
+    pub axis: Vec3,      // in the joint frame
+    pub limit: Option<SdfJointLimit>,
+    pub dynamics: Option<SdfJointDynamics>,
+}
+
+/// Error raised while parsing URDF
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrdfError {
+    /// The file could not be read
+    Io { file: String, message: String },
+    /// The XML is malformed
+    Xml {
+        location: SdfLocation,
+        message: String,
+    },
+    /// An element or attribute is missing or holds a value of the wrong type
+    InvalidValue {
+        location: SdfLocation,
+        message: String,
+    },
+    /// The links and joints do not form a tree
+    InvalidTree {
+        location: SdfLocation,
+        message: String,
+    },
+}
+
+impl fmt::Display for UrdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrdfError::Io { file, message } => write!(f, "{}: {}", file, message),
+            UrdfError::Xml { location, message } => {
+                write!(f, "{}: invalid XML: {}", location, message)
+            }
+            UrdfError::InvalidValue { location, message }
+            | UrdfError::InvalidTree { location, message } => {
+                write!(f, "{}: {}", location, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UrdfError {}
+
+/// Elements the parser reads; any other element, such as `<gazebo>` or `<transmission>`, is
+/// skipped together with its contents
+const URDF_ELEMENTS: &[&str] = &[
+    "robot",
+    "link",
+    "visual",
+    "collision",
+    "inertial",
+    "origin",
+    "geometry",
+    "box",
+    "cylinder",
+    "sphere",
+    "mesh",
+    "material",
+    "color",
+    "mass",
+    "inertia",
+    "joint",
+    "parent",
+    "child",
+    "axis",
+    "limit",
+    "dynamics",
+];
+
+const JOINT_TYPES: &[&str] = &[
+    "revolute",
+    "continuous",
+    "prismatic",
+    "fixed",
+    "floating",
+    "planar",
+];
+
+/// Marks the child link of a URDF joint, which carries the Rapier joint, with the joint's name
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct UrdfJointName(pub String);
+
+/// Loads a URDF file
+pub fn load_urdf(path: &str) -> Result<UrdfRobot, UrdfError> {
+    let content = std::fs::read_to_string(path).map_err(|e| UrdfError::Io {
+        file: path.to_string(),
+        message: format!("Failed to read URDF file: {}", e),
+    })?;
+    parse_urdf_file(&content, path)
+}
+
+/// Parses URDF XML content
+#[allow(dead_code)]
+pub fn parse_urdf(content: &str) -> Result<UrdfRobot, UrdfError> {
+    parse(content, None)
+}
+
+/// Parses the URDF XML content of `file`, naming the file in errors
+pub fn parse_urdf_file(content: &str, file: &str) -> Result<UrdfRobot, UrdfError> {
+    parse(content, Some(file))
+}
+
+/// Reads an attribute with `parse`, if it is there
+fn attribute<T>(
+    e: &BytesStart,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    get_attribute(e, name)
+        .map(|value| parse(&value).map_err(|message| format!("{}: {}", name, message)))
+        .transpose()
+}
+
+/// Reads an attribute the element must have
+fn required<T>(
+    e: &BytesStart,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    attribute(e, name, parse)?.ok_or_else(|| format!("missing attribute {}", name))
+}
+
+fn text(value: &str) -> Result<String, String> {
+    Ok(value.to_string())
+}
+
+/// URDF parsing context
+#[derive(Debug)]
+struct UrdfContext {
+    robot: UrdfRobot,
+    robot_location: Option<SdfLocation>,
+    link_locations: Vec<SdfLocation>,
+    joint_locations: Vec<SdfLocation>,
+    /// Named materials of the robot
+    materials: HashMap<String, Color>,
+    /// Visuals referring to a material by name: link index, visual index and material name
+    material_references: Vec<(usize, usize, String)>,
+    current_link: Option<UrdfLink>,
+    current_visual: Option<SdfVisual>,
+    current_collision: Option<SdfCollision>,
+    current_inertial: Option<SdfInertial>,
+    current_joint: Option<UrdfJoint>,
+    current_material: Option<(String, Option<Color>)>,
+}
+
+impl UrdfContext {
+    fn new() -> Self {
+        Self {
+            robot: UrdfRobot {
+                name: String::new(),
+                links: Vec::new(),
+                joints: Vec::new(),
+            },
+            robot_location: None,
+            link_locations: Vec::new(),
+            joint_locations: Vec::new(),
+            materials: HashMap::new(),
+            material_references: Vec::new(),
+            current_link: None,
+            current_visual: None,
+            current_collision: None,
+            current_inertial: None,
+            current_joint: None,
+            current_material: None,
+        }
+    }
+
+    /// Reads the start tag of the element `tag`, found at `location`
+    fn start_element(
+        &mut self,
+        tag: &str,
+        e: &BytesStart,
+        location: &SdfLocation,
+    ) -> Result<(), UrdfError> {
+        let invalid = |message| UrdfError::InvalidValue {
+            location: location.clone(),
+            message,
+        };
+        match tag {
+            "robot" => {
+                self.robot.name = get_attribute(e, "name").unwrap_or_default();
+                self.robot_location = Some(location.clone());
+            }
+            "link" => {
+                self.current_link = Some(UrdfLink {
+                    name: required(e, "name", text).map_err(invalid)?,
+                    visuals: Vec::new(),
+                    collisions: Vec::new(),
+                    inertial: None,
+                });
+            }
+            "visual" => {
+                let index = self
+                    .current_link
+                    .as_ref()
+                    .map_or(0, |link| link.visuals.len());
+                self.current_visual = Some(SdfVisual {
+                    name: get_attribute(e, "name").unwrap_or_else(|| format!("visual_{}", index)),
+                    pose: SdfPose::default(),
+                    geometry: SdfGeometry::Box { size: Vec3::ONE },
+                    material: None,
+                });
+            }
+            "collision" => {
+                let index = self
+                    .current_link
+                    .as_ref()
+                    .map_or(0, |link| link.collisions.len());
+                self.current_collision = Some(SdfCollision {
+                    name: get_attribute(e, "name")
+                        .unwrap_or_else(|| format!("collision_{}", index)),
+                    pose: SdfPose::default(),
+                    geometry: SdfGeometry::Box { size: Vec3::ONE },
+                    surface: None,
+                });
+            }
+            "inertial" => {
+                self.current_inertial = Some(SdfInertial {
+                    mass: 0.0,
+                    ixx: 0.0,
+                    iyy: 0.0,
+                    izz: 0.0,
+                    ixy: 0.0,
+                    ixz: 0.0,
+                    iyz: 0.0,
+                    pose: SdfPose::default(),
+                });
+            }
+            "origin" => {
+                let pose = SdfPose {
+                    xyz: attribute(e, "xyz", parse_vec3)
+                        .map_err(invalid)?
+                        .unwrap_or_default(),
+                    rpy: attribute(e, "rpy", parse_vec3)
+                        .map_err(invalid)?
+                        .unwrap_or_default(),
+                };
+                if let Some(collision) = &mut self.current_collision {
+                    collision.pose = pose;
+                } else if let Some(visual) = &mut self.current_visual {
+                    visual.pose = pose;
+                } else if let Some(inertial) = &mut self.current_inertial {
+                    inertial.pose = pose;
+                } else if let Some(joint) = &mut self.current_joint {
+                    joint.origin = pose;
+                }
+            }
+            "box" | "cylinder" | "sphere" | "mesh" => {
+                let geometry = match tag {
+                    "box" => SdfGeometry::Box {
+                        size: required(e, "size", parse_vec3).map_err(invalid)?,
+                    },
+                    // URDF cylinders are along Z, like SDF ones
+                    "cylinder" => SdfGeometry::Cylinder {
+                        radius: required(e, "radius", parse_number).map_err(invalid)?,
+                        length: required(e, "length", parse_number).map_err(invalid)?,
+                    },
+                    "sphere" => SdfGeometry::Sphere {
+                        radius: required(e, "radius", parse_number).map_err(invalid)?,
+                    },
+                    _ => SdfGeometry::Mesh {
+                        uri: required(e, "filename", text).map_err(invalid)?,
+                        scale: attribute(e, "scale", parse_vec3).map_err(invalid)?,
+                    },
+                };
+                if let Some(collision) = &mut self.current_collision {
+                    collision.geometry = geometry;
+                } else if let Some(visual) = &mut self.current_visual {
+                    visual.geometry = geometry;
+                }
+            }
+            "material" => {
+                self.current_material = Some((get_attribute(e, "name").unwrap_or_default(), None));
+            }
+            "color" => {
+                if let Some((_, color)) = &mut self.current_material {
+                    *color = Some(required(e, "rgba", parse_color).map_err(invalid)?);
+                }
+            }
+            "mass" => {
+                if let Some(inertial) = &mut self.current_inertial {
+                    inertial.mass = required(e, "value", parse_number).map_err(invalid)?;
+                }
+            }
+            "inertia" => {
+                if let Some(inertial) = &mut self.current_inertial {
+                    let moment = |name| {
+                        attribute(e, name, parse_number)
+                            .map_err(invalid)
+                            .map(Option::unwrap_or_default)
+                    };
+                    inertial.ixx = moment("ixx")?;
+                    inertial.iyy = moment("iyy")?;
+                    inertial.izz = moment("izz")?;
+                    inertial.ixy = moment("ixy")?;
+                    inertial.ixz = moment("ixz")?;
+                    inertial.iyz = moment("iyz")?;
+                }
+            }
+            "joint" => {
+                let name = required(e, "name", text).map_err(invalid)?;
+                let joint_type = required(e, "type", text).map_err(invalid)?;
+                if !JOINT_TYPES.contains(&joint_type.as_str()) {
+                    return Err(invalid(format!("unknown joint type {:?}", joint_type)));
+                }
+                self.current_joint = Some(UrdfJoint {
+                    name,
+                    joint_type,
+                    parent: String::new(),
+                    child: String::new(),
+                    origin: SdfPose::default(),
+                    axis: Vec3::X,
+                    limit: None,
+                    dynamics: None,
+                });
+            }
+            "parent" | "child" | "axis" | "limit" | "dynamics" => {
+                let Some(joint) = &mut self.current_joint else {
+                    return Ok(());
+                };
+                match tag {
+                    "parent" => joint.parent = required(e, "link", text).map_err(invalid)?,
+                    "child" => joint.child = required(e, "link", text).map_err(invalid)?,
+                    "axis" => joint.axis = required(e, "xyz", parse_vec3).map_err(invalid)?,
+                    "limit" => {
+                        let value = |name, default| {
+                            attribute(e, name, parse_number)
+                                .map_err(invalid)
+                                .map(|value| value.unwrap_or(default))
+                        };
+                        joint.limit = Some(SdfJointLimit {
+                            lower: value("lower", 0.0)?,
+                            upper: value("upper", 0.0)?,
+                            effort: value("effort", -1.0)?,
+                            velocity: value("velocity", -1.0)?,
+                        });
+                    }
+                    _ => {
+                        let value = |name| {
+                            attribute(e, name, parse_number)
+                                .map_err(invalid)
+                                .map(Option::unwrap_or_default)
+                        };
+                        joint.dynamics = Some(SdfJointDynamics {
+                            damping: value("damping")?,
+                            friction: value("friction")?,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Finishes the element `tag` found at `location`, adding it to the element it is in
+    fn end_element(&mut self, tag: &str, location: SdfLocation) {
+        match tag {
+            "link" => {
+                if let Some(link) = self.current_link.take() {
+                    self.robot.links.push(link);
+                    self.link_locations.push(location);
+                }
+            }
+            "visual" => {
+                if let (Some(visual), Some(link)) =
+                    (self.current_visual.take(), &mut self.current_link)
+                {
+                    link.visuals.push(visual);
+                }
+            }
+            "collision" => {
+                if let (Some(collision), Some(link)) =
+                    (self.current_collision.take(), &mut self.current_link)
+                {
+                    link.collisions.push(collision);
+                }
+            }
+            "inertial" => {
+                if let Some(link) = &mut self.current_link {
+                    link.inertial = self.current_inertial.take();
+                }
+            }
+            "joint" => {
+                if let Some(joint) = self.current_joint.take() {
+                    self.robot.joints.push(joint);
+                    self.joint_locations.push(location);
+                }
+            }
+            "material" => {
+                let Some((name, color)) = self.current_material.take() else {
+                    return;
+                };
+                let (Some(visual), Some(link)) = (&mut self.current_visual, &self.current_link)
+                else {
+                    // A material of the robot itself, for visuals to refer to by name
+                    if let Some(color) = color {
+                        self.materials.insert(name, color);
+                    }
+                    return;
+                };
+                match color.or_else(|| self.materials.get(&name).copied()) {
+                    Some(color) => visual.material = Some(color_material(color)),
+                    // Materials may be referred to before they are defined
+                    None if !name.is_empty() => self.material_references.push((
+                        self.robot.links.len(),
+                        link.visuals.len(),
+                        name,
+                    )),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses URDF XML content, checking that the links and joints form a tree
+fn parse(content: &str, file: Option<&str>) -> Result<UrdfRobot, UrdfError> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+
+    let mut context = UrdfContext::new();
+    // Open elements as path segments with their start offsets
+    let mut elements: Vec<(String, usize)> = Vec::new();
+    let mut skip_depth = 0;
+    loop {
+        let event = reader.read_event();
+        let position = reader.buffer_position();
+        match event {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let is_empty = matches!(event, Ok(Event::Empty(_)));
+                if skip_depth > 0 {
+                    skip_depth += usize::from(!is_empty);
+                    continue;
+                }
+                let tag = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                if !URDF_ELEMENTS.contains(&tag.as_str()) {
+                    skip_depth += usize::from(!is_empty);
+                    continue;
+                }
+                let segment = match get_attribute(e, "name") {
+                    Some(name) => format!("{}[{}]", tag, name),
+                    None => tag.clone(),
+                };
+                let start = position - e.len() - 2 - usize::from(is_empty);
+                let location = locate(content, file, &elements, Some(&segment), start);
+                context.start_element(&tag, e, &location)?;
+                if is_empty {
+                    context.end_element(&tag, location);
+                } else {
+                    elements.push((segment, start));
+                }
+            }
+            Ok(Event::End(_)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    continue;
+                }
+                let Some((segment, start)) = elements.pop() else {
+                    continue;
+                };
+                let location = locate(content, file, &elements, Some(&segment), start);
+                let tag = segment.split('[').next().unwrap_or_default();
+                context.end_element(tag, location);
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(UrdfError::Xml {
+                    location: locate(content, file, &elements, None, position),
+                    message: e.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut robot = context.robot;
+    for (link, visual, name) in context.material_references {
+        match context.materials.get(&name) {
+            Some(&color) => {
+                robot.links[link].visuals[visual].material = Some(color_material(color))
+            }
+            None => warn!("URDF material {} is not defined", name),
+        }
+    }
+
+    let robot_location = context
+        .robot_location
+        .ok_or_else(|| UrdfError::InvalidValue {
+            location: locate(content, file, &[], None, 0),
+            message: "no <robot> element".to_string(),
+        })?;
+    check_tree(
+        &robot,
+        &robot_location,
+        &context.link_locations,
+        &context.joint_locations,
+    )?;
+    Ok(robot)
+}
+
+/// SDF material of a URDF color, which lights a visual both directly and ambiently
+fn color_material(color: Color) -> SdfMaterial {
+    SdfMaterial {
+        ambient: Some(color),
+        diffuse: Some(color),
+        specular: None,
+        emissive: None,
+    }
+}
+
+/// Checks that every joint connects known links and that they form a single tree
+fn check_tree(
+    robot: &UrdfRobot,
+    robot_location: &SdfLocation,
+    link_locations: &[SdfLocation],
+    joint_locations: &[SdfLocation],
+) -> Result<(), UrdfError> {
+    let mut links = HashSet::new();
+    for (link, location) in robot.links.iter().zip(link_locations) {
+        if !links.insert(link.name.as_str()) {
+            return Err(UrdfError::InvalidTree {
+                location: location.clone(),
+                message: format!("link {} is defined twice", link.name),
+            });
+        }
+    }
+
+    let mut children = HashSet::new();
+    for (joint, location) in robot.joints.iter().zip(joint_locations) {
+        let invalid = |message| UrdfError::InvalidTree {
+            location: location.clone(),
+            message,
+        };
+        for link in [&joint.parent, &joint.child] {
+            if !links.contains(link.as_str()) {
+                return Err(invalid(format!(
+                    "joint {} refers to unknown link {:?}",
+                    joint.name, link
+                )));
+            }
+        }
+        if !children.insert(joint.child.as_str()) {
+            return Err(invalid(format!(
+                "link {} is the child of more than one joint",
+                joint.child
+            )));
+        }
+    }
+
+    let roots: Vec<&str> = robot
+        .links
+        .iter()
+        .map(|link| link.name.as_str())
+        .filter(|link| !children.contains(link))
+        .collect();
+    let root = match roots[..] {
+        [root] => root,
+        [] => {
+            return Err(UrdfError::InvalidTree {
+                location: robot_location.clone(),
+                message: "no root link, every link is the child of a joint".to_string(),
+            });
+        }
+        _ => {
+            return Err(UrdfError::InvalidTree {
+                location: robot_location.clone(),
+                message: format!("more than one root link: {}", roots.join(", ")),
+            });
+        }
+    };
+
+    // With a single root and a single parent per link, links out of reach form loops
+    let reached = tree_order(robot, root);
+    for (link, location) in robot.links.iter().zip(link_locations) {
+        if !reached.contains(&link.name.as_str()) {
+            return Err(UrdfError::InvalidTree {
+                location: location.clone(),
+                message: format!("link {} is in a loop of joints", link.name),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Links reachable from `root` through joints, parents before their children
+fn tree_order<'a>(robot: &'a UrdfRobot, root: &'a str) -> Vec<&'a str> {
+    let mut order = vec![root];
+    let mut queue = VecDeque::from([root]);
+    while let Some(parent) = queue.pop_front() {
+        for joint in robot.joints.iter().filter(|joint| joint.parent == parent) {
+            order.push(joint.child.as_str());
+            queue.push_back(joint.child.as_str());
+        }
+    }
+    order
+}
+
+impl UrdfRobot {
+    /// The link that is not the child of any joint
+    pub fn root_link(&self) -> &str {
+        self.links
+            .iter()
+            .map(|link| link.name.as_str())
+            .find(|link| !self.joints.iter().any(|joint| joint.child == *link))
+            .unwrap_or_default()
+    }
+
+    /// The robot as an SDF model, with every link posed relative to the root link.
+    ///
+    /// A root link named `world` is the world itself, as in Gazebo, so joints to it fix the robot
+    /// in place. Floating joints leave their child free; planar joints are not supported and do
+    /// the same.
+    pub fn to_sdf_model(&self) -> SdfModel {
+        let root = self.root_link();
+        let mut poses: HashMap<&str, SdfPose> = HashMap::from([(root, SdfPose::default())]);
+        for link in tree_order(self, root) {
+            for joint in self.joints.iter().filter(|joint| joint.parent == link) {
+                let pose = compose_poses(&poses[link], &joint.origin);
+                poses.insert(&joint.child, pose);
+            }
+        }
+
+        let links = self
+            .links
+            .iter()
+            .filter(|link| !(link.name == "world" && link.name == root))
+            .map(|link| SdfLink {
+                name: link.name.clone(),
+                pose: poses.get(link.name.as_str()).cloned().unwrap_or_default(),
+                visuals: link.visuals.clone(),
+                collisions: link.collisions.clone(),
+                inertial: link.inertial.clone(),
+                linear_damping: None,
+                angular_damping: None,
+            })
+            .collect();
+        let joints = self
+            .joints
+            .iter()
+            .filter_map(|joint| {
+                match joint.joint_type.as_str() {
+                    "floating" => return None,
+                    "planar" => {
+                        warn!(
+                            "Planar joint {} is not supported, {} moves freely",
+                            joint.name, joint.child
+                        );
+                        return None;
+                    }
+                    _ => {}
+                }
+                // The joint frame is the child link frame
+                Some(SdfJoint {
+                    name: joint.name.clone(),
+                    joint_type: joint.joint_type.clone(),
+                    parent: joint.parent.clone(),
+                    child: joint.child.clone(),
+                    pose: SdfPose::default(),
+                    axis: (joint.joint_type != "fixed").then(|| SdfAxis {
+                        xyz: joint.axis,
+                        limit: joint.limit.clone(),
+                        dynamics: joint.dynamics.clone(),
+                    }),
+                })
+            })
+            .collect();
+
+        SdfModel {
+            name: self.name.clone(),
+            static_: false,
+            pose: SdfPose::default(),
+            links,
+            joints,
+        }
+    }
+}
+
+/// Spawns a URDF robot at `transform` and returns its root entity.
+///
+/// The robot is spawned like an SDF model, in a world of its own that the root entity stands in
+/// for, so it gets the same link entities, Rapier bodies and joints. Each joint's child link is
+/// tagged with a [`UrdfJointName`].
+pub fn spawn_urdf_robot(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    asset_server: &Res<AssetServer>,
+    robot: &UrdfRobot,
+    transform: Transform,
+) -> Entity {
+    let root = commands
+        .spawn((
+            transform,
+            Name::new(robot.name.clone()),
+            Visibility::default(),
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+        ))
+        .id();
+    let world = SdfWorld {
+        name: robot.name.clone(),
+        models: Vec::new(),
+        lights: Vec::new(),
+        physics: None,
+        scene: None,
+        meshes: HashMap::new(),
+        images: HashMap::new(),
+    };
+    let links = spawn_sdf_model(
+        commands,
+        meshes,
+        materials,
+        asset_server,
+        root,
+        &world,
+        &robot.to_sdf_model(),
+    );
+    for joint in &robot.joints {
+        if let Some(&child) = links.get(&joint.child) {
+            commands
+                .entity(child)
+                .insert(UrdfJointName(joint.name.clone()));
+        }
+    }
+    root
+}
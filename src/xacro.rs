@@ -0,0 +1,1006 @@
+use bevy::asset::io::file::FileAssetReader;
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Error raised while expanding xacro
+#[derive(Debug, Clone, PartialEq)]
+pub enum XacroError {
+    /// A file could not be read
+    Io { file: String, message: String },
+    /// The XML of a file is malformed
+    Xml { file: String, message: String },
+    /// A xacro element, argument or expression is invalid
+    Invalid(String),
+}
+
+impl fmt::Display for XacroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XacroError::Io { file, message } => write!(f, "{}: {}", file, message),
+            XacroError::Xml { file, message } => write!(f, "{}: invalid XML: {}", file, message),
+            XacroError::Invalid(message) => write!(f, "Invalid xacro: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for XacroError {}
+
+fn invalid(message: String) -> XacroError {
+    XacroError::Invalid(message)
+}
+
+/// Expands xacro content into plain URDF (or any other XML).
+///
+/// `args` are command-line style `name:=value` arguments, read with `$(arg name)`. Includes are
+/// relative to the working directory.
+#[allow(dead_code)]
+pub fn expand_xacro(content: &str, args: &[String]) -> Result<String, XacroError> {
+    expand(content, "<string>", Path::new("."), args)
+}
+
+/// Reads and expands a xacro file; includes are relative to the file. See [`expand_xacro`].
+pub fn expand_xacro_file(path: &Path, args: &[String]) -> Result<String, XacroError> {
+    let content = read_file(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    expand(&content, &path.to_string_lossy(), dir, args)
+}
+
+fn read_file(path: &Path) -> Result<String, XacroError> {
+    std::fs::read_to_string(path).map_err(|e| XacroError::Io {
+        file: path.to_string_lossy().into_owned(),
+        message: format!("Failed to read xacro file: {}", e),
+    })
+}
+
+fn expand(content: &str, file: &str, dir: &Path, args: &[String]) -> Result<String, XacroError> {
+    let args = args
+        .iter()
+        .map(|arg| {
+            arg.split_once(":=")
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .ok_or_else(|| invalid(format!("argument {:?} is not name:=value", arg)))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    let root = parse_tree(content, file)?;
+    let mut expander = Expander {
+        args,
+        macros: HashMap::new(),
+        scopes: vec![HashMap::new()],
+        depth: 0,
+    };
+    let mut root = match expander.expand_element(&root, dir)?.pop() {
+        Some(root) => root,
+        None => return Err(invalid("the root element expands to nothing".to_string())),
+    };
+    root.attributes.retain(|(name, _)| name != "xmlns:xacro");
+
+    let mut xml = String::from("<?xml version=\"1.0\"?>\n");
+    write_element(&root, 0, &mut xml);
+    Ok(xml)
+}
+
+/// Minimal XML element tree; text is only kept for elements without children
+#[derive(Debug, Clone, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn from_start(e: &BytesStart) -> Self {
+        let attributes = e
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .map(|attr| {
+                let value = attr
+                    .unescape_value()
+                    .map(|value| value.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).into_owned());
+                (
+                    String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+                    value,
+                )
+            })
+            .collect();
+        Element {
+            name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, XacroError> {
+        self.attribute(name)
+            .ok_or_else(|| invalid(format!("<{}> has no {} attribute", self.name, name)))
+    }
+}
+
+fn parse_tree(content: &str, file: &str) -> Result<Element, XacroError> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+    let xml_error = |message: String| XacroError::Xml {
+        file: file.to_string(),
+        message,
+    };
+
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) => stack.push(Element::from_start(e)),
+            Ok(Event::Empty(ref e)) => {
+                let element = Element::from_start(e);
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let Some(element) = stack.last_mut() {
+                    let text = e.unescape().map_err(|e| xml_error(e.to_string()))?;
+                    element.text.push_str(&text);
+                }
+            }
+            Ok(Event::End(_)) => {
+                let element = stack.pop().filter(|_| !stack.is_empty());
+                match (element, stack.last_mut()) {
+                    (Some(element), Some(parent)) => parent.children.push(element),
+                    _ => return Err(xml_error("unbalanced tags".to_string())),
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(xml_error(e.to_string())),
+            _ => {}
+        }
+    }
+
+    stack
+        .pop()
+        .filter(|_| stack.is_empty())
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| xml_error("no root element".to_string()))
+}
+
+/// Writes an element and its children, indented by `depth`
+fn write_element(element: &Element, depth: usize, xml: &mut String) {
+    let indent = "  ".repeat(depth);
+    xml.push_str(&indent);
+    xml.push('<');
+    xml.push_str(&element.name);
+    for (name, value) in &element.attributes {
+        xml.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+    }
+    if !element.children.is_empty() {
+        xml.push_str(">\n");
+        for child in &element.children {
+            write_element(child, depth + 1, xml);
+        }
+        xml.push_str(&format!("{}</{}>\n", indent, element.name));
+    } else if !element.text.is_empty() {
+        xml.push_str(&format!(">{}</{}>\n", escape(&element.text), element.name));
+    } else {
+        xml.push_str("/>\n");
+    }
+}
+
+/// Value of a property or expression
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl Value {
+    /// Reads text the way xacro does: numbers and booleans become such, anything else is text
+    fn from_text(text: &str) -> Value {
+        let trimmed = text.trim();
+        match trimmed {
+            "true" | "True" => Value::Bool(true),
+            "false" | "False" => Value::Bool(false),
+            _ => match trimmed.parse::<f64>() {
+                Ok(number) if trimmed.bytes().any(|b| b.is_ascii_digit()) => Value::Number(number),
+                _ => Value::Text(text.to_string()),
+            },
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Number(number) => *number != 0.0,
+            Value::Bool(value) => *value,
+            Value::Text(text) => !text.is_empty(),
+        }
+    }
+
+    fn number(&self) -> Result<f64, XacroError> {
+        match self {
+            Value::Number(number) => Ok(*number),
+            Value::Bool(value) => Ok(f64::from(u8::from(*value))),
+            Value::Text(text) => Err(invalid(format!("expected a number, got {:?}", text))),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Whole numbers are written without a fraction, like Python integers
+            Value::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Bool(true) => write!(f, "True"),
+            Value::Bool(false) => write!(f, "False"),
+            Value::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// A property: a value, or a block of elements to insert with `xacro:insert_block`
+#[derive(Debug, Clone)]
+enum Property {
+    Value(Value),
+    Block(Vec<Element>),
+}
+
+/// Parameter of a macro
+#[derive(Debug)]
+enum Param {
+    /// `name` or `name:=default`; a default of `^` takes the value from the calling scope, `^|x`
+    /// falls back to `x`
+    Value {
+        name: String,
+        default: Option<String>,
+    },
+    /// `*name`: the next child element of the call
+    Element(String),
+    /// `**name`: the children of the next child element of the call
+    Children(String),
+}
+
+#[derive(Debug)]
+struct Macro {
+    params: Vec<Param>,
+    body: Element,
+    /// Directory of the file the macro is defined in
+    dir: PathBuf,
+}
+
+/// Splits a macro's `params` attribute, keeping quoted defaults together
+fn parse_params(params: &str) -> Vec<Param> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    for c in params.chars() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+        .into_iter()
+        .map(|word| {
+            if let Some(name) = word.strip_prefix("**") {
+                Param::Children(name.to_string())
+            } else if let Some(name) = word.strip_prefix('*') {
+                Param::Element(name.to_string())
+            } else if let Some((name, default)) = word.split_once(":=") {
+                Param::Value {
+                    name: name.to_string(),
+                    default: Some(default.to_string()),
+                }
+            } else {
+                Param::Value {
+                    name: word,
+                    default: None,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Deepest nesting of includes and macro calls, so recursive ones fail instead of overflowing
+const MAX_DEPTH: usize = 100;
+
+/// Expands xacro elements, keeping the properties, macros and arguments defined so far
+struct Expander {
+    args: HashMap<String, String>,
+    macros: HashMap<String, Rc<Macro>>,
+    /// Property scopes, global first; each macro call opens one
+    scopes: Vec<HashMap<String, Property>>,
+    depth: usize,
+}
+
+impl Expander {
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Expands an element into the elements that replace it, `dir` being where its file is
+    fn expand_element(
+        &mut self,
+        element: &Element,
+        dir: &Path,
+    ) -> Result<Vec<Element>, XacroError> {
+        let Some(tag) = element.name.strip_prefix("xacro:") else {
+            // Attributes are substituted after the children, so the root element can use the
+            // arguments it declares
+            let children = self.expand_children(element, dir)?;
+            let attributes = element
+                .attributes
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), self.substitute(value)?)))
+                .collect::<Result<_, XacroError>>()?;
+            return Ok(vec![Element {
+                name: element.name.clone(),
+                attributes,
+                children,
+                text: self.substitute(&element.text)?,
+            }]);
+        };
+
+        match tag {
+            "property" => {
+                let name = element.required("name")?.to_string();
+                let property = match element.attribute("value") {
+                    Some(value) => Property::Value(self.evaluate_text(value)?),
+                    None => Property::Block(self.expand_children(element, dir)?),
+                };
+                let scope = match element.attribute("scope") {
+                    Some("global") => 0,
+                    Some("parent") => self.scopes.len().saturating_sub(2),
+                    _ => self.scopes.len() - 1,
+                };
+                self.scopes[scope].insert(name, property);
+                Ok(Vec::new())
+            }
+            "arg" => {
+                let name = element.required("name")?;
+                if !self.args.contains_key(name) {
+                    if let Some(default) = element.attribute("default") {
+                        let default = self.substitute(default)?;
+                        self.args.insert(name.to_string(), default);
+                    }
+                }
+                Ok(Vec::new())
+            }
+            "include" => {
+                let filename = self.substitute(element.required("filename")?)?;
+                let path = dir.join(filename);
+                self.nested(|expander| {
+                    let root = parse_tree(&read_file(&path)?, &path.to_string_lossy())?;
+                    // The root element of an included file only wraps what it defines
+                    expander.expand_children(&root, path.parent().unwrap_or(Path::new(".")))
+                })
+            }
+            "macro" => {
+                let name = element.required("name")?.to_string();
+                let params = parse_params(element.attribute("params").unwrap_or_default());
+                let body = element.clone();
+                let dir = dir.to_path_buf();
+                self.macros
+                    .insert(name, Rc::new(Macro { params, body, dir }));
+                Ok(Vec::new())
+            }
+            "if" | "unless" => {
+                let value = element.required("value")?;
+                let condition = match self.evaluate_text(value)? {
+                    Value::Text(text) => {
+                        return Err(invalid(format!(
+                            "<xacro:{}> value {:?} is not a boolean",
+                            tag, text
+                        )));
+                    }
+                    value => value.truthy(),
+                };
+                if condition == (tag == "if") {
+                    self.expand_children(element, dir)
+                } else {
+                    Ok(Vec::new())
+                }
+            }
+            "insert_block" => {
+                let name = self.substitute(element.required("name")?)?;
+                match self.property(&name) {
+                    Some(Property::Block(block)) => Ok(block.clone()),
+                    _ => Err(invalid(format!("no block named {}", name))),
+                }
+            }
+            "call" => {
+                let name = self.substitute(element.required("macro")?)?;
+                self.call(&name, element, dir)
+            }
+            name => self.call(name, element, dir),
+        }
+    }
+
+    fn expand_children(
+        &mut self,
+        element: &Element,
+        dir: &Path,
+    ) -> Result<Vec<Element>, XacroError> {
+        let mut children = Vec::new();
+        for child in &element.children {
+            children.extend(self.expand_element(child, dir)?);
+        }
+        Ok(children)
+    }
+
+    /// Runs `f` one level deeper in includes and macro calls
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, XacroError>,
+    ) -> Result<T, XacroError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(invalid(
+                "includes or macro calls nest too deeply".to_string(),
+            ));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Expands a call of the macro `name`, the element `call`
+    fn call(&mut self, name: &str, call: &Element, dir: &Path) -> Result<Vec<Element>, XacroError> {
+        let Some(macro_) = self.macros.get(name).cloned() else {
+            return Err(invalid(format!("unknown xacro element or macro {}", name)));
+        };
+        for (attribute, _) in &call.attributes {
+            let known = macro_
+                .params
+                .iter()
+                .any(|param| matches!(param, Param::Value { name, .. } if name == attribute));
+            if !(known || call.name == "xacro:call" && attribute == "macro") {
+                return Err(invalid(format!(
+                    "macro {} has no parameter {}",
+                    name, attribute
+                )));
+            }
+        }
+
+        // Arguments are evaluated where the macro is called
+        let mut blocks = call.children.iter();
+        let mut scope = HashMap::new();
+        for param in &macro_.params {
+            match param {
+                Param::Value {
+                    name: param,
+                    default,
+                } => {
+                    let value = match (call.attribute(param), default.as_deref()) {
+                        (Some(value), _) => self.evaluate_text(value)?,
+                        (None, Some(default)) if default.starts_with('^') => {
+                            match self.property(param) {
+                                Some(Property::Value(value)) => value.clone(),
+                                _ => match default.strip_prefix("^|") {
+                                    Some(default) => self.evaluate_text(default)?,
+                                    None => {
+                                        return Err(invalid(format!(
+                                            "macro {}: no {} to take from the calling scope",
+                                            name, param
+                                        )));
+                                    }
+                                },
+                            }
+                        }
+                        (None, Some(default)) => self.evaluate_text(default)?,
+                        (None, None) => {
+                            return Err(invalid(format!(
+                                "macro {} is missing parameter {}",
+                                name, param
+                            )));
+                        }
+                    };
+                    scope.insert(param.clone(), Property::Value(value));
+                }
+                Param::Element(block_name) | Param::Children(block_name) => {
+                    let Some(block) = blocks.next() else {
+                        return Err(invalid(format!(
+                            "macro {} is missing block {}",
+                            name, block_name
+                        )));
+                    };
+                    let mut block = self.expand_element(block, dir)?;
+                    if matches!(param, Param::Children(_)) {
+                        block = block
+                            .into_iter()
+                            .flat_map(|element| element.children)
+                            .collect();
+                    }
+                    scope.insert(block_name.clone(), Property::Block(block));
+                }
+            }
+        }
+
+        self.scopes.push(scope);
+        let result = self.nested(|expander| expander.expand_children(&macro_.body, &macro_.dir));
+        self.scopes.pop();
+        result
+    }
+
+    /// Value of text that may be a single `${...}` expression, or text with substitutions
+    fn evaluate_text(&self, text: &str) -> Result<Value, XacroError> {
+        let trimmed = text.trim();
+        if let Some(expression) = trimmed
+            .strip_prefix("${")
+            .and_then(|rest| rest.strip_suffix('}'))
+        {
+            if expression_end(trimmed, 2) == Some(trimmed.len() - 1) {
+                return self.evaluate(expression);
+            }
+        }
+        Ok(Value::from_text(&self.substitute(text)?))
+    }
+
+    /// Replaces `${expression}` and `$(command)` in text; `$$` escapes a `$`
+    fn substitute(&self, text: &str) -> Result<String, XacroError> {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index..];
+            if let Some(escaped) = rest.strip_prefix("$$") {
+                result.push('$');
+                rest = escaped;
+            } else if rest.starts_with("${") {
+                let end = expression_end(rest, 2)
+                    .ok_or_else(|| invalid(format!("unclosed ${{ in {:?}", text)))?;
+                result.push_str(&self.evaluate(&rest[2..end])?.to_string());
+                rest = &rest[end + 1..];
+            } else if rest.starts_with("$(") {
+                let end = command_end(rest)
+                    .ok_or_else(|| invalid(format!("unclosed $( in {:?}", text)))?;
+                result.push_str(&self.command(&rest[2..end])?);
+                rest = &rest[end + 1..];
+            } else {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Result of a `$(command args...)` substitution
+    fn command(&self, command: &str) -> Result<String, XacroError> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        match (name, &args[..]) {
+            ("arg", [arg]) => self
+                .args
+                .get(*arg)
+                .cloned()
+                .ok_or_else(|| invalid(format!("undefined argument {}", arg))),
+            ("find", [package]) => find_package(package)
+                .map(|path| path.to_string_lossy().into_owned())
+                .ok_or_else(|| invalid(format!("package {} not found", package))),
+            ("env", [variable]) => std::env::var(variable)
+                .map_err(|_| invalid(format!("environment variable {} is not set", variable))),
+            ("optenv", [variable, default @ ..]) => {
+                Ok(std::env::var(variable).unwrap_or_else(|_| default.join(" ")))
+            }
+            ("eval", _) => Ok(self.evaluate(&args.join(" "))?.to_string()),
+            _ => Err(invalid(format!("unsupported substitution $({})", command))),
+        }
+    }
+
+    fn evaluate(&self, expression: &str) -> Result<Value, XacroError> {
+        // Like xacro, `$(arg name)` and other substitutions inside an expression come first
+        let expression = &self.substitute(expression)?;
+        let lookup = |name: &str| match self.property(name) {
+            Some(Property::Value(value)) => Ok(value.clone()),
+            Some(Property::Block(_)) => Err(invalid(format!("{} is a block, not a value", name))),
+            None => Err(invalid(format!("undefined property {}", name))),
+        };
+        evaluate_expression(expression, &lookup)
+            .map_err(|e| invalid(format!("in ${{{}}}: {}", expression, invalid_message(e))))
+    }
+}
+
+fn invalid_message(error: XacroError) -> String {
+    match error {
+        XacroError::Invalid(message) => message,
+        error => error.to_string(),
+    }
+}
+
+/// Index of the `}` closing the expression starting at `start`, skipping quoted strings
+fn expression_end(text: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in text[start..].char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '}') => return Some(start + index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the `)` closing the `$(` that `text` starts with
+fn command_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Directory of a ROS package, searched for in `ROS_PACKAGE_PATH`, the `share` directories of
+/// `AMENT_PREFIX_PATH`, then the `packages` directory of the assets, which `package://` URIs load from
+fn find_package(name: &str) -> Option<PathBuf> {
+    let paths = |variable| {
+        std::env::var_os(variable)
+            .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+    let mut dirs = paths("ROS_PACKAGE_PATH");
+    dirs.extend(
+        paths("AMENT_PREFIX_PATH")
+            .into_iter()
+            .map(|prefix| prefix.join("share")),
+    );
+    dirs.push(FileAssetReader::get_base_path().join("assets/packages"));
+    dirs.into_iter()
+        .map(|dir| dir.join(name))
+        .find(|dir| dir.is_dir())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String),
+    Operator(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "**", "//", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, XacroError> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let length = if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let mut length = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            // Exponent
+            if rest[length..].starts_with(['e', 'E']) {
+                let exponent = &rest[length + 1..];
+                let sign = usize::from(exponent.starts_with(['+', '-']));
+                let digits = exponent[sign..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(exponent.len() - sign);
+                if digits > 0 {
+                    length += 1 + sign + digits;
+                }
+            }
+            let number = rest[..length]
+                .parse()
+                .map_err(|_| invalid(format!("invalid number {}", &rest[..length])))?;
+            tokens.push(Token::Number(number));
+            length
+        } else if c == '\'' || c == '"' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| invalid("unclosed string".to_string()))?;
+            tokens.push(Token::Text(rest[1..end + 1].to_string()));
+            end + 2
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..length].to_string()));
+            length
+        } else if let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| rest.starts_with(**operator))
+        {
+            tokens.push(Token::Operator(operator));
+            operator.len()
+        } else {
+            return Err(invalid(format!("unexpected {:?}", c)));
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Evaluates a Python-like xacro expression, looking names up with `lookup`
+fn evaluate_expression(
+    expression: &str,
+    lookup: &dyn Fn(&str) -> Result<Value, XacroError>,
+) -> Result<Value, XacroError> {
+    let mut parser = ExpressionParser {
+        tokens: tokenize(expression)?,
+        position: 0,
+        lookup,
+    };
+    let value = parser.conditional()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(invalid(format!("unexpected {:?}", token))),
+    }
+}
+
+/// Recursive descent evaluator, one method per level of operator precedence
+struct ExpressionParser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    lookup: &'a dyn Fn(&str) -> Result<Value, XacroError>,
+}
+
+impl ExpressionParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Moves past the next token if it is `operator` or the keyword `operator`
+    fn accept(&mut self, operator: &str) -> bool {
+        let accepted = match self.peek() {
+            Some(Token::Operator(o)) => *o == operator,
+            Some(Token::Name(name)) => name == operator,
+            _ => false,
+        };
+        self.position += usize::from(accepted);
+        accepted
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), XacroError> {
+        if self.accept(operator) {
+            Ok(())
+        } else {
+            Err(invalid(format!("expected {}", operator)))
+        }
+    }
+
+    /// `a if condition else b`
+    fn conditional(&mut self) -> Result<Value, XacroError> {
+        let value = self.or()?;
+        if !self.accept("if") {
+            return Ok(value);
+        }
+        let condition = self.or()?;
+        self.expect("else")?;
+        let otherwise = self.conditional()?;
+        Ok(if condition.truthy() { value } else { otherwise })
+    }
+
+    fn or(&mut self) -> Result<Value, XacroError> {
+        let mut value = self.and()?;
+        while self.accept("or") {
+            let right = self.and()?;
+            if !value.truthy() {
+                value = right;
+            }
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<Value, XacroError> {
+        let mut value = self.not()?;
+        while self.accept("and") {
+            let right = self.not()?;
+            if value.truthy() {
+                value = right;
+            }
+        }
+        Ok(value)
+    }
+
+    fn not(&mut self) -> Result<Value, XacroError> {
+        if self.accept("not") {
+            return Ok(Value::Bool(!self.not()?.truthy()));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Value, XacroError> {
+        let left = self.sum()?;
+        let Some(Token::Operator(operator)) = self.peek().cloned() else {
+            return Ok(left);
+        };
+        if !["==", "!=", "<", "<=", ">", ">="].contains(&operator) {
+            return Ok(left);
+        }
+        self.position += 1;
+        let right = self.sum()?;
+        let ordering = match (&left, &right) {
+            (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
+            (Value::Text(_), _) | (_, Value::Text(_)) => None,
+            _ => left.number()?.partial_cmp(&right.number()?),
+        };
+        use std::cmp::Ordering::*;
+        Ok(Value::Bool(match operator {
+            "==" => ordering == Some(Equal),
+            "!=" => ordering != Some(Equal),
+            "<" => ordering == Some(Less),
+            "<=" => matches!(ordering, Some(Less | Equal)),
+            ">" => ordering == Some(Greater),
+            _ => matches!(ordering, Some(Greater | Equal)),
+        }))
+    }
+
+    fn sum(&mut self) -> Result<Value, XacroError> {
+        let mut value = self.product()?;
+        loop {
+            if self.accept("+") {
+                let right = self.product()?;
+                value = match (value, right) {
+                    (Value::Text(a), Value::Text(b)) => Value::Text(a + &b),
+                    (a, b) => Value::Number(a.number()? + b.number()?),
+                };
+            } else if self.accept("-") {
+                let right = self.product()?;
+                value = Value::Number(value.number()? - right.number()?);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Value, XacroError> {
+        let mut value = self.unary()?;
+        loop {
+            let operator = ["*", "//", "/", "%"]
+                .into_iter()
+                .find(|operator| self.accept(operator));
+            let Some(operator) = operator else {
+                return Ok(value);
+            };
+            let (a, b) = (value.number()?, self.unary()?.number()?);
+            if b == 0.0 && operator != "*" {
+                return Err(invalid("division by zero".to_string()));
+            }
+            value = Value::Number(match operator {
+                "*" => a * b,
+                "//" => (a / b).floor(),
+                "/" => a / b,
+                _ => a.rem_euclid(b),
+            });
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value, XacroError> {
+        if self.accept("-") {
+            Ok(Value::Number(-self.unary()?.number()?))
+        } else if self.accept("+") {
+            Ok(Value::Number(self.unary()?.number()?))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Value, XacroError> {
+        let base = self.atom()?;
+        if self.accept("**") {
+            let exponent = self.unary()?;
+            return Ok(Value::Number(base.number()?.powf(exponent.number()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Value, XacroError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| invalid("unexpected end of expression".to_string()))?;
+        self.position += 1;
+        match token {
+            Token::Number(number) => Ok(Value::Number(number)),
+            Token::Text(text) => Ok(Value::Text(text)),
+            Token::Operator("(") => {
+                let value = self.conditional()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Token::Name(name) if self.accept("(") => {
+                let mut args = Vec::new();
+                if !self.accept(")") {
+                    loop {
+                        args.push(self.conditional()?);
+                        if self.accept(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                call_function(&name, &args)
+            }
+            Token::Name(name) => match name.strip_prefix("math.").unwrap_or(&name) {
+                "True" | "true" => Ok(Value::Bool(true)),
+                "False" | "false" => Ok(Value::Bool(false)),
+                "pi" if (self.lookup)("pi").is_err() => Ok(Value::Number(std::f64::consts::PI)),
+                "e" if (self.lookup)("e").is_err() => Ok(Value::Number(std::f64::consts::E)),
+                _ => (self.lookup)(&name),
+            },
+            token => Err(invalid(format!("unexpected {:?}", token))),
+        }
+    }
+}
+
+/// Calls one of the math functions xacro expressions can use
+fn call_function(name: &str, args: &[Value]) -> Result<Value, XacroError> {
+    let numbers = args
+        .iter()
+        .map(Value::number)
+        .collect::<Result<Vec<f64>, _>>();
+    let name = name.strip_prefix("math.").unwrap_or(name);
+    let number = match (name, args) {
+        ("str", [value]) => return Ok(Value::Text(value.to_string())),
+        ("bool", [value]) => return Ok(Value::Bool(value.truthy())),
+        ("float" | "int", [Value::Text(text)]) => match Value::from_text(text) {
+            Value::Text(_) => return Err(invalid(format!("{}({:?}) is not a number", name, text))),
+            value => call_function(name, &[value])?.number()?,
+        },
+        _ => match (name, &numbers?[..]) {
+            ("float", [x]) => *x,
+            ("int", [x]) => x.trunc(),
+            ("abs" | "fabs", [x]) => x.abs(),
+            ("sqrt", [x]) => x.sqrt(),
+            ("sin", [x]) => x.sin(),
+            ("cos", [x]) => x.cos(),
+            ("tan", [x]) => x.tan(),
+            ("asin", [x]) => x.asin(),
+            ("acos", [x]) => x.acos(),
+            ("atan", [x]) => x.atan(),
+            ("atan2", [y, x]) => y.atan2(*x),
+            ("exp", [x]) => x.exp(),
+            ("log", [x]) => x.ln(),
+            ("log", [x, base]) => x.log(*base),
+            ("log10", [x]) => x.log10(),
+            ("pow", [x, y]) => x.powf(*y),
+            ("floor", [x]) => x.floor(),
+            ("ceil", [x]) => x.ceil(),
+            ("round", [x]) => x.round(),
+            ("radians", [x]) => x.to_radians(),
+            ("degrees", [x]) => x.to_degrees(),
+            ("min", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.min(*b)),
+            ("max", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.max(*b)),
+            _ => {
+                return Err(invalid(format!(
+                    "unknown function {} with {} arguments",
+                    name,
+                    args.len()
+                )));
+            }
+        },
+    };
+    Ok(Value::Number(number))
+}